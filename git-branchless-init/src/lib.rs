@@ -84,7 +84,9 @@ echo 'branchless: This is a bug. Please report it.'
     ),
 ];
 
-const ALL_ALIASES: &[(&str, &str)] = &[
+/// The Git aliases to install, as pairs of the alias name and the
+/// `git branchless` subcommand which it runs.
+pub const ALL_ALIASES: &[(&str, &str)] = &[
     ("amend", "amend"),
     ("hide", "hide"),
     ("move", "move"),
@@ -120,6 +120,16 @@ pub fn get_comment_char(repo: &Repo) -> eyre::Result<char> {
     Ok(comment_char)
 }
 
+/// Get the characters which can separate the key and value of a commit message
+/// trailer, as configured by `trailer.separators`.
+#[instrument]
+pub fn get_trailer_separators(repo: &Repo) -> eyre::Result<String> {
+    let separators = repo
+        .get_readonly_config()?
+        .get_or_else("trailer.separators", || ":".to_owned())?;
+    Ok(separators)
+}
+
 /// Get the commit template message, if any.
 #[instrument]
 pub fn get_commit_template(repo: &Repo) -> eyre::Result<Option<String>> {
@@ -234,6 +244,14 @@ pub fn get_commit_descriptors_differential_revision(repo: &Repo) -> eyre::Result
         .get_or("branchless.commitDescriptors.differentialRevision", true)
 }
 
+/// If `true`, show the cached code review status of each commit in the
+/// smartlog.
+#[instrument]
+pub fn get_commit_descriptors_review_status(repo: &Repo) -> eyre::Result<bool> {
+    repo.get_readonly_config()?
+        .get_or("branchless.commitDescriptors.reviewStatus", true)
+}
+
 /// If `true`, show the age of each commit in the smartlog.
 #[instrument]
 pub fn get_commit_descriptors_relative_time(repo: &Repo) -> eyre::Result<bool> {
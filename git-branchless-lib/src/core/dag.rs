@@ -9,7 +9,7 @@ use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
 use eden_dag::namedag::MemNameDag;
-use eden_dag::nameset::hints::Hints;
+use eden_dag::nameset::hints::{Flags, Hints};
 use eden_dag::ops::{DagPersistent, Parents};
 use eden_dag::{DagAlgorithm, Group, VertexListWithOptions, VertexOptions};
 use eyre::Context;
@@ -503,6 +503,13 @@ impl Dag {
     /// definition in `query_visible_heads`.
     #[instrument]
     pub fn filter_visible_commits(&self, commits: CommitSet) -> eyre::Result<CommitSet> {
+        // Computing the range would evaluate a lazy filter (such as a revset
+        // pattern matcher) against every commit it could contain, so defer to
+        // its membership test instead.
+        if commits.hints().contains(Flags::FILTER) {
+            return Ok(commits.intersection(self.query_visible_commits_slow()?));
+        }
+
         let visible_heads = self.query_visible_heads()?;
         Ok(commits.intersection(
             &self.run_blocking(self.inner.range(commits.clone(), visible_heads.clone()))?,
@@ -20,6 +20,7 @@ use crate::core::formatting::Glyphs;
 pub enum OperationType {
     BuildRebasePlan,
     CalculateDiff,
+    CallForgeApi(Arc<String>),
     CalculatePatchId,
     CheckForCycles,
     ConstrainCommits,
@@ -55,6 +56,7 @@ impl Display for OperationType {
         match self {
             OperationType::BuildRebasePlan => write!(f, "Building rebase plan"),
             OperationType::CalculateDiff => write!(f, "Computing diffs"),
+            OperationType::CallForgeApi(request) => write!(f, "Calling forge API: {request}"),
             OperationType::CalculatePatchId => write!(f, "Hashing commit contents"),
             OperationType::CheckForCycles => write!(f, "Checking for cycles"),
             OperationType::ConstrainCommits => write!(f, "Creating commit constraints"),
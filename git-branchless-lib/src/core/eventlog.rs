@@ -691,6 +691,16 @@ WHERE event_tx_id = :event_tx_id
     }
 }
 
+/// The prefix of the references which record the parent branch of each
+/// submitted branch, when `branchless.submit.branch.recordStacks` is set.
+///
+/// Each reference points to a blob of the form `parent <branch name>\nhead
+/// <commit hash>\n`, where the parent is the main branch for the bottom branch
+/// of a stack, and the head is the submitted commit of the branch. Other
+/// clones can fetch them with the refspec
+/// `+refs/branchless/stacks/*:refs/branchless/stacks/*`.
+pub const STACK_REF_PREFIX: &str = "refs/branchless/stacks/";
+
 /// Determine whether a given reference is used to keep a commit alive.
 ///
 /// Returns: Whether or not the given reference is used internally to keep the
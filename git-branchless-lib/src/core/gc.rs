@@ -15,7 +15,7 @@ use tracing::instrument;
 
 use crate::core::effects::Effects;
 use crate::core::eventlog::{
-    is_gc_ref, CommitActivityStatus, EventCursor, EventLogDb, EventReplayer,
+    is_gc_ref, CommitActivityStatus, EventCursor, EventLogDb, EventReplayer, STACK_REF_PREFIX,
 };
 use crate::core::formatting::Pluralize;
 use crate::git::{NonZeroOid, Reference, Repo};
@@ -33,6 +33,11 @@ pub fn find_dangling_references<'repo>(
         if !is_gc_ref(&reference_name) {
             continue;
         }
+        // Stack records point to blobs rather than commits, and aren't
+        // garbage-collected.
+        if reference_name.as_str().starts_with(STACK_REF_PREFIX) {
+            continue;
+        }
 
         // The graph only contains commits, so we don't need to handle the
         // case of the reference not peeling to a valid commit. (It might be
@@ -16,10 +16,11 @@ use tracing::instrument;
 
 use crate::core::config::{
     get_commit_descriptors_branches, get_commit_descriptors_differential_revision,
-    get_commit_descriptors_relative_time,
+    get_commit_descriptors_relative_time, get_commit_descriptors_review_status,
 };
 use crate::git::{
-    CategorizedReferenceName, Commit, NonZeroOid, ReferenceName, Repo, ResolvedReferenceInfo,
+    read_review_info_cache, CategorizedReferenceName, ChecksState, Commit, NonZeroOid,
+    ReferenceName, Repo, ResolvedReferenceInfo, ReviewInfo, ReviewInfoCache, ReviewState,
 };
 
 use super::eventlog::{Event, EventCursor, EventReplayer};
@@ -395,7 +396,9 @@ impl<'a> DifferentialRevisionDescriptor<'a> {
     }
 }
 
-fn extract_diff_number(message: &str) -> Option<String> {
+/// Extract the Phabricator revision, such as `D123`, from the `Differential
+/// Revision` trailer of a commit message.
+pub fn extract_diff_number(message: &str) -> Option<String> {
     lazy_static! {
         static ref RE: Regex = Regex::new(
             r"(?mx)
@@ -440,6 +443,90 @@ impl<'a> NodeDescriptor for DifferentialRevisionDescriptor<'a> {
     }
 }
 
+/// Display the code review associated with a given commit, along with its
+/// review and CI status. This is read from the cache populated by `git submit`,
+/// so it may be out of date, but it never requires network access.
+#[derive(Debug)]
+pub struct ReviewStatusDescriptor<'a> {
+    review_info_cache: Option<ReviewInfoCache>,
+    redactor: &'a Redactor,
+}
+
+impl<'a> ReviewStatusDescriptor<'a> {
+    /// Constructor.
+    pub fn new(repo: &Repo, redactor: &'a Redactor) -> eyre::Result<Self> {
+        let review_info_cache = if get_commit_descriptors_review_status(repo)? {
+            Some(read_review_info_cache(repo)?)
+        } else {
+            None
+        };
+        Ok(ReviewStatusDescriptor {
+            review_info_cache,
+            redactor,
+        })
+    }
+}
+
+impl<'a> NodeDescriptor for ReviewStatusDescriptor<'a> {
+    #[instrument]
+    fn describe_node(
+        &mut self,
+        _glyphs: &Glyphs,
+        object: &NodeObject,
+    ) -> eyre::Result<Option<StyledString>> {
+        match self.redactor {
+            Redactor::Enabled { .. } => return Ok(None),
+            Redactor::Disabled => {}
+        }
+        let review_info_cache = match &self.review_info_cache {
+            Some(review_info_cache) => review_info_cache,
+            None => return Ok(None),
+        };
+        let ReviewInfo {
+            name,
+            url: _,
+            review_state,
+            checks_state,
+        } = match review_info_cache.get(object.get_oid()) {
+            Some(review_info) => review_info,
+            None => return Ok(None),
+        };
+
+        let mut states = Vec::new();
+        if let Some(review_state) = review_state {
+            let color = match review_state {
+                ReviewState::Draft | ReviewState::ReviewRequired => Some(BaseColor::Yellow.dark()),
+                ReviewState::Approved => Some(BaseColor::Green.light()),
+                ReviewState::ChangesRequested | ReviewState::Blocked => {
+                    Some(BaseColor::Red.light())
+                }
+                // Nothing is waiting on a review, so don't clutter the smartlog.
+                ReviewState::NotRequired => None,
+            };
+            if let Some(color) = color {
+                states.push(StyledString::styled(review_state.description(), color));
+            }
+        }
+        if let Some(checks_state) = checks_state {
+            let color = match checks_state {
+                ChecksState::Pending => BaseColor::Yellow.dark(),
+                ChecksState::Success => BaseColor::Green.light(),
+                ChecksState::Failure => BaseColor::Red.light(),
+            };
+            states.push(StyledString::styled(checks_state.description(), color));
+        }
+
+        let mut result = StyledStringBuilder::new().append_styled(name, BaseColor::Green.dark());
+        if !states.is_empty() {
+            result = result
+                .append_plain(" (")
+                .append(StyledStringBuilder::join(", ", states))
+                .append_plain(")");
+        }
+        Ok(Some(result.build()))
+    }
+}
+
 /// Display how long ago the given commit was committed.
 #[derive(Debug)]
 pub struct RelativeTimeDescriptor {
@@ -15,6 +15,54 @@ pub struct Diff<'repo> {
     pub(super) inner: git2::Diff<'repo>,
 }
 
+/// Summary statistics about a diff, similar to `git diff --shortstat`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    /// The number of files which were changed.
+    pub files_changed: usize,
+
+    /// The number of lines which were added.
+    pub lines_added: usize,
+
+    /// The number of lines which were removed.
+    pub lines_removed: usize,
+
+    /// Whether any of the changed files are binary.
+    pub touches_binary: bool,
+}
+
+impl DiffStats {
+    /// The total number of lines which were added or removed.
+    pub fn lines_changed(&self) -> usize {
+        self.lines_added + self.lines_removed
+    }
+}
+
+impl Diff<'_> {
+    /// Calculate summary statistics for this diff.
+    pub fn get_stats(&self) -> eyre::Result<DiffStats> {
+        let stats = self.inner.stats().wrap_err("Calculating diff stats")?;
+        let mut touches_binary = false;
+        self.inner
+            .foreach(
+                &mut |_delta, _progress| true,
+                Some(&mut |_delta, _binary| {
+                    touches_binary = true;
+                    true
+                }),
+                None,
+                None,
+            )
+            .wrap_err("Iterating over diff deltas")?;
+        Ok(DiffStats {
+            files_changed: stats.files_changed(),
+            lines_added: stats.insertions(),
+            lines_removed: stats.deletions(),
+            touches_binary,
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct GitHunk {
     old_start: usize,
@@ -7,6 +7,7 @@ mod object;
 mod oid;
 mod reference;
 mod repo;
+mod review;
 mod run;
 mod snapshot;
 mod status;
@@ -14,7 +15,7 @@ mod test;
 mod tree;
 
 pub use config::{Config, ConfigRead, ConfigValue, ConfigWrite};
-pub use diff::{process_diff_for_record, Diff};
+pub use diff::{process_diff_for_record, Diff, DiffStats};
 pub use index::{update_index, Index, IndexEntry, Stage, UpdateIndexCommand};
 pub use object::Commit;
 pub use oid::{MaybeZeroOid, NonZeroOid};
@@ -26,12 +27,17 @@ pub use repo::{
     Error as RepoError, GitErrorCode, GitVersion, PatchId, Repo, ResolvedReferenceInfo,
     Result as RepoResult, Time,
 };
+pub use review::{
+    get_review_info_cache_path, read_review_info_cache, update_review_info_cache, ChecksState,
+    ReviewInfo, ReviewInfoCache, ReviewState,
+};
 pub use run::{GitRunInfo, GitRunOpts, GitRunResult};
 pub use snapshot::{WorkingCopyChangesType, WorkingCopySnapshot};
 pub use status::{FileMode, FileStatus, StatusEntry};
 pub use test::{
     get_latest_test_command_path, get_test_locks_dir, get_test_tree_dir, get_test_worktrees_dir,
-    make_test_command_slug, SerializedNonZeroOid, SerializedTestResult, TestCommand,
+    make_test_command_slug, read_cached_test_results, read_latest_test_command, DirectoryTestCache,
+    SerializedNonZeroOid, SerializedTestResult, TestCacheBackend, TestCommand,
     TEST_ABORT_EXIT_CODE, TEST_INDETERMINATE_EXIT_CODE, TEST_SUCCESS_EXIT_CODE,
 };
 pub use tree::{dehydrate_tree, get_changed_paths_between_trees, hydrate_tree, Tree};
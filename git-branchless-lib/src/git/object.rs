@@ -167,6 +167,26 @@ impl<'repo> Commit<'repo> {
         Ok(result)
     }
 
+    /// Get the "trailer" metadata from this commit's message, like
+    /// [`Commit::get_trailers`], but recognizing any of the characters in
+    /// `separators` as the separator between the trailer key and value (see
+    /// the `trailer.separators` config option). Values which span multiple
+    /// lines are unfolded into a single line. Messages which aren't valid
+    /// UTF-8 are decoded lossily.
+    #[instrument]
+    pub fn get_trailers_with_separators(&self, separators: &str) -> Vec<(String, String)> {
+        parse_trailers(&self.get_message_raw().to_str_lossy(), separators)
+    }
+
+    /// Determine whether this commit has a cryptographic signature, such as a
+    /// GPG or SSH signature. The signature itself is not verified.
+    #[instrument]
+    pub fn is_signed(&self) -> bool {
+        ["gpgsig", "gpgsig-sha256"]
+            .into_iter()
+            .any(|field| self.inner.header_field_bytes(field).is_ok())
+    }
+
     /// Print a one-line description of this commit containing its OID and
     /// summary.
     #[instrument]
@@ -315,6 +335,77 @@ impl<'repo> Commit<'repo> {
     }
 }
 
+/// Split a trailer line into its key and value, if it is one. As in Git, the
+/// key is everything before the first separator character, and may only be
+/// followed by whitespace, not contain it.
+fn split_trailer_line<'a>(line: &'a str, separators: &str) -> Option<(&'a str, &'a str)> {
+    let separator_index = line.find(|c| separators.contains(c))?;
+    let key = line[..separator_index].trim_end();
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    let value = &line[separator_index..];
+    let separator_len = value.chars().next().map(char::len_utf8).unwrap_or_default();
+    Some((key, value[separator_len..].trim()))
+}
+
+/// Parse the trailers out of the last paragraph of the given commit message.
+///
+/// Following Git, the last paragraph is considered to be a trailer block if
+/// every line in it is a trailer, or if it contains a Git-generated trailer
+/// such as `Signed-off-by:` and at least 25% of its lines are trailers.
+fn parse_trailers(message: &str, separators: &str) -> Vec<(String, String)> {
+    let lines: Vec<&str> = message
+        .trim_end()
+        .lines()
+        .rev()
+        .take_while(|line| !line.trim().is_empty())
+        .collect();
+    if lines.len() == message.trim_end().lines().count() {
+        // The message only has a single paragraph, which is the summary.
+        return Vec::new();
+    }
+
+    let mut trailers: Vec<(String, String)> = Vec::new();
+    let mut num_trailer_lines = 0;
+    let mut num_non_trailer_lines = 0;
+    let mut has_git_generated_trailer = false;
+    let mut in_trailer = false;
+    for line in lines.into_iter().rev() {
+        if line.starts_with(char::is_whitespace) && in_trailer {
+            if let Some((_key, value)) = trailers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+
+        if line.starts_with("Signed-off-by: ") || line.starts_with("(cherry picked from commit ") {
+            has_git_generated_trailer = true;
+        }
+        match split_trailer_line(line, separators) {
+            Some((key, value)) => {
+                num_trailer_lines += 1;
+                in_trailer = true;
+                trailers.push((key.to_owned(), value.to_owned()));
+            }
+            None => {
+                num_non_trailer_lines += 1;
+                in_trailer = false;
+            }
+        }
+    }
+
+    let is_trailer_block = num_trailer_lines > 0
+        && (num_non_trailer_lines == 0
+            || (has_git_generated_trailer && num_trailer_lines * 3 >= num_non_trailer_lines));
+    if is_trailer_block {
+        trailers
+    } else {
+        Vec::new()
+    }
+}
+
 pub struct Blob<'repo> {
     pub(super) inner: git2::Blob<'repo>,
 }
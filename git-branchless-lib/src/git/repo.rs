@@ -41,7 +41,7 @@ use crate::git::{Branch, BranchType, Commit, Reference, ReferenceName};
 use super::index::{Index, IndexEntry};
 use super::snapshot::WorkingCopySnapshot;
 use super::status::FileMode;
-use super::{tree, Diff, StatusEntry};
+use super::{tree, Diff, DiffStats, StatusEntry};
 
 #[allow(missing_docs)]
 #[derive(Debug, Error)]
@@ -826,6 +826,23 @@ impl Repo {
         Ok(Some(diff))
     }
 
+    /// Get summary statistics for the changes introduced by the given commit,
+    /// relative to its first parent (or the empty tree for a root commit).
+    #[instrument]
+    pub fn get_diff_stats_for_commit(
+        &self,
+        effects: &Effects,
+        commit: &Commit,
+    ) -> eyre::Result<DiffStats> {
+        let parent_tree = match commit.get_parents().into_iter().next() {
+            Some(parent) => Some(self.find_tree_or_fail(parent.get_tree()?.get_oid())?),
+            None => None,
+        };
+        let current_tree = commit.get_tree()?;
+        let diff = self.get_diff_between_trees(effects, parent_tree.as_ref(), &current_tree, 0)?;
+        diff.get_stats()
+    }
+
     /// Get the diff between two trees. This is more performant than calling
     /// libgit2's `diff_tree_to_tree` directly since it dehydrates commits
     /// before diffing them.
@@ -1138,6 +1155,24 @@ impl Repo {
         Ok(all_branches)
     }
 
+    /// Get all remote-tracking branches in the repository.
+    #[instrument]
+    pub fn get_all_remote_branches(&self) -> Result<Vec<Branch>> {
+        let mut all_branches = Vec::new();
+        for branch in self
+            .inner
+            .branches(Some(git2::BranchType::Remote))
+            .map_err(Error::GetBranches)?
+        {
+            let (branch, _branch_type) = branch.map_err(Error::ReadBranch)?;
+            all_branches.push(Branch {
+                repo: self,
+                inner: branch,
+            });
+        }
+        Ok(all_branches)
+    }
+
     /// Look up the branch with the given name. Returns `None` if not found.
     #[instrument]
     pub fn find_branch(&self, name: &str, branch_type: BranchType) -> Result<Option<Branch>> {
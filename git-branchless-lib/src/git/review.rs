@@ -0,0 +1,165 @@
+//! Saving and loading of cached code review information for commits, such as
+//! the pull request associated with a commit and whether it has been
+//! approved.
+//!
+//! The cache is written by `git submit` when it queries the forge, and read
+//! when rendering the smartlog, so that rendering never has to make network
+//! requests.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use eyre::Context;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::{NonZeroOid, Repo, RepoError};
+
+/// The review state of a code review.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReviewState {
+    /// The code review is a draft and not ready for review.
+    Draft,
+
+    /// The code review is waiting for reviewers.
+    ReviewRequired,
+
+    /// The code review has been approved.
+    Approved,
+
+    /// A reviewer has requested changes.
+    ChangesRequested,
+
+    /// The forge doesn't require the code review to be approved before it's
+    /// merged.
+    NotRequired,
+
+    /// The code review can't be merged for a reason other than its approval
+    /// or CI checks, such as merge conflicts.
+    Blocked,
+}
+
+impl ReviewState {
+    /// A short human-readable description of the state.
+    pub fn description(&self) -> &'static str {
+        match self {
+            ReviewState::Draft => "draft",
+            ReviewState::ReviewRequired => "review required",
+            ReviewState::Approved => "approved",
+            ReviewState::ChangesRequested => "changes requested",
+            ReviewState::NotRequired => "no review required",
+            ReviewState::Blocked => "blocked",
+        }
+    }
+}
+
+/// The combined state of the CI checks for a code review.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChecksState {
+    /// At least one check is still running, and none have failed.
+    Pending,
+
+    /// All checks passed.
+    Success,
+
+    /// At least one check failed.
+    Failure,
+}
+
+impl ChecksState {
+    /// A short human-readable description of the state.
+    pub fn description(&self) -> &'static str {
+        match self {
+            ChecksState::Pending => "checks pending",
+            ChecksState::Success => "checks passed",
+            ChecksState::Failure => "checks failed",
+        }
+    }
+}
+
+/// Information about the code review associated with a commit.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ReviewInfo {
+    /// The forge-specific name of the code review, such as `#123` for a GitHub
+    /// pull request.
+    pub name: String,
+
+    /// The URL of the code review, if any.
+    pub url: Option<String>,
+
+    /// The review state, if known.
+    pub review_state: Option<ReviewState>,
+
+    /// The state of the CI checks, if known.
+    pub checks_state: Option<ChecksState>,
+}
+
+/// The on-disk cache of code review information, keyed by commit OID.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ReviewInfoCache {
+    /// The review information for each commit. Sorted for determinism.
+    pub reviews: BTreeMap<String, ReviewInfo>,
+}
+
+impl ReviewInfoCache {
+    /// Get the cached review information for the given commit, if any.
+    pub fn get(&self, commit_oid: NonZeroOid) -> Option<&ReviewInfo> {
+        self.reviews.get(&commit_oid.to_string())
+    }
+}
+
+/// Get the path to the file where cached code review information is stored.
+pub fn get_review_info_cache_path(repo: &Repo) -> Result<PathBuf, RepoError> {
+    Ok(repo.get_branchless_dir()?.join("review-info.json"))
+}
+
+/// Load the cached code review information. A missing or unreadable cache is
+/// treated as empty, since it can always be regenerated by `git submit`.
+pub fn read_review_info_cache(repo: &Repo) -> eyre::Result<ReviewInfoCache> {
+    let path = get_review_info_cache_path(repo)?;
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(err) => {
+            return Err(err).wrap_err_with(|| format!("Reading review info cache at {path:?}"))
+        }
+    };
+    match serde_json::from_str(&contents) {
+        Ok(cache) => Ok(cache),
+        Err(err) => {
+            warn!(?err, ?path, "Could not deserialize review info cache");
+            Ok(Default::default())
+        }
+    }
+}
+
+/// Update the cached code review information for the given commits. A value
+/// of `None` removes any cached information for that commit, such as when its
+/// code review has been closed.
+pub fn update_review_info_cache(
+    repo: &Repo,
+    review_infos: HashMap<NonZeroOid, Option<ReviewInfo>>,
+) -> eyre::Result<()> {
+    let mut cache = read_review_info_cache(repo)?;
+    for (commit_oid, review_info) in review_infos {
+        let key = commit_oid.to_string();
+        match review_info {
+            Some(review_info) => {
+                cache.reviews.insert(key, review_info);
+            }
+            None => {
+                cache.reviews.remove(&key);
+            }
+        }
+    }
+
+    let path = get_review_info_cache_path(repo)?;
+    let contents = serde_json::to_string_pretty(&cache)?;
+    fs::write(&path, contents)
+        .wrap_err_with(|| format!("Writing review info cache at {path:?}"))?;
+    Ok(())
+}
@@ -5,11 +5,14 @@
 //! Regrettably, this adds `serde` as a new dependency to `git-branchless-lib`,
 //! which will increase build times.
 
-use std::{fmt::Display, path::PathBuf};
+use std::fmt::{Debug, Display};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+use eyre::WrapErr;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use super::{Commit, NonZeroOid, Repo, RepoError};
+use super::{Commit, MaybeZeroOid, NonZeroOid, Repo, RepoError};
 
 /// The exit status to use when a test command succeeds.
 pub const TEST_SUCCESS_EXIT_CODE: i32 = 0;
@@ -105,6 +108,58 @@ pub struct SerializedTestResult {
     pub snapshot_tree_oid: Option<SerializedNonZeroOid>,
     #[serde(default)]
     pub interactive: bool,
+
+    /// The wall time of a single run of the test command, averaged over all
+    /// runs. This is `None` for results which were written before durations
+    /// were recorded.
+    #[serde(default)]
+    pub duration_secs: Option<f64>,
+
+    /// The number of times that the test command was run (see `git test run
+    /// --repeat`).
+    #[serde(default = "default_num_runs")]
+    pub num_runs: usize,
+
+    /// Whether the runs of the test command disagreed about the result. In
+    /// that case, `exit_code` is the exit code of the first unsuccessful run.
+    #[serde(default)]
+    pub flaky: bool,
+}
+
+fn default_num_runs() -> usize {
+    1
+}
+
+impl SerializedTestResult {
+    /// Whether this result can be reused by other clones of the repository
+    /// which test the same tree with the same command.
+    ///
+    /// Only passing results are shared, since failures are frequently caused
+    /// by the environment rather than by the contents of the tree. Results
+    /// which produced a snapshot of a different tree (i.e. from `git test fix`)
+    /// aren't shared, since the snapshot tree may not exist in the other
+    /// repository.
+    pub fn is_shareable(&self, tree_oid: MaybeZeroOid) -> bool {
+        let Self {
+            command: _,
+            exit_code,
+            head_commit_oid: _,
+            snapshot_tree_oid,
+            interactive,
+            duration_secs: _,
+            num_runs: _,
+            flaky,
+        } = self;
+        *exit_code == TEST_SUCCESS_EXIT_CODE
+            && !interactive
+            && !flaky
+            && match snapshot_tree_oid {
+                None => true,
+                Some(SerializedNonZeroOid(snapshot_tree_oid)) => {
+                    MaybeZeroOid::from(*snapshot_tree_oid) == tree_oid
+                }
+            }
+    }
 }
 
 /// Get the directory where the results of running tests are stored.
@@ -137,3 +192,209 @@ pub fn get_test_worktrees_dir(repo: &Repo) -> Result<PathBuf, RepoError> {
 pub fn get_latest_test_command_path(repo: &Repo) -> Result<PathBuf, RepoError> {
     Ok(get_test_dir(repo)?.join("latest-command"))
 }
+
+/// A store of test results, keyed by tree OID and command slug (see
+/// [`make_test_command_slug`]). Each entry is a directory containing the
+/// `result`, `stdout`, and `stderr` files produced by running the test
+/// command.
+pub trait TestCacheBackend: Debug + Send + Sync {
+    /// Copy the stored entry for the given tree and command into `dest_dir`.
+    /// Returns `false` if there is no such entry.
+    fn fetch(
+        &self,
+        tree_oid: MaybeZeroOid,
+        command_slug: &str,
+        dest_dir: &Path,
+    ) -> eyre::Result<bool>;
+
+    /// Copy the entry in `src_dir` into the store for the given tree and
+    /// command. Returns `false` if there was already an entry, in which case it
+    /// is left unchanged.
+    fn store(
+        &self,
+        tree_oid: MaybeZeroOid,
+        command_slug: &str,
+        src_dir: &Path,
+    ) -> eyre::Result<bool>;
+}
+
+/// A [`TestCacheBackend`] which stores entries in a directory on disk, using
+/// the same layout as the repository's own test cache. The directory may be
+/// shared between several clones, such as over a network filesystem.
+#[derive(Clone, Debug)]
+pub struct DirectoryTestCache {
+    root: PathBuf,
+}
+
+impl DirectoryTestCache {
+    const ENTRY_FILE_NAMES: &'static [&'static str] = &["stdout", "stderr", "result"];
+
+    /// Constructor.
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Get the backend for the repository's own test cache.
+    pub fn for_repo(repo: &Repo) -> Result<Self, RepoError> {
+        Ok(Self::new(get_test_dir(repo)?))
+    }
+
+    /// Get the root directory of this cache.
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get the directory for the entry with the given tree and command.
+    pub fn get_entry_dir(&self, tree_oid: MaybeZeroOid, command_slug: &str) -> PathBuf {
+        self.root.join(tree_oid.to_string()).join(command_slug)
+    }
+
+    /// Read the result stored for the given tree and command, if any.
+    pub fn read_result(
+        &self,
+        tree_oid: MaybeZeroOid,
+        command_slug: &str,
+    ) -> eyre::Result<Option<SerializedTestResult>> {
+        let result_path = self.get_entry_dir(tree_oid, command_slug).join("result");
+        let result_contents = match std::fs::read_to_string(&result_path) {
+            Ok(result_contents) => result_contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("Reading test result {result_path:?}"))
+            }
+        };
+        if result_contents.is_empty() {
+            // The result file is created empty before the command runs, so an
+            // empty file indicates a result which isn't available yet.
+            return Ok(None);
+        }
+        let result = serde_json::from_str(&result_contents)
+            .wrap_err_with(|| format!("Parsing test result {result_path:?}"))?;
+        Ok(Some(result))
+    }
+
+    /// Copy the entry in `src_dir` into this cache for the given tree and
+    /// command, replacing any existing entry.
+    pub fn replace(
+        &self,
+        tree_oid: MaybeZeroOid,
+        command_slug: &str,
+        src_dir: &Path,
+    ) -> eyre::Result<()> {
+        copy_test_cache_entry(src_dir, &self.get_entry_dir(tree_oid, command_slug))
+    }
+
+    /// List the tree OIDs and command slugs of the entries in this cache.
+    /// Directories which aren't named after a tree OID (such as the locks
+    /// directory) are skipped.
+    pub fn list_entries(&self) -> eyre::Result<Vec<(MaybeZeroOid, String)>> {
+        let tree_dirs = match std::fs::read_dir(&self.root) {
+            Ok(tree_dirs) => tree_dirs,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err)
+                    .wrap_err_with(|| format!("Reading test cache directory {:?}", self.root))
+            }
+        };
+
+        let mut result = Vec::new();
+        for tree_dir in tree_dirs {
+            let tree_dir = tree_dir?;
+            if !tree_dir.file_type()?.is_dir() {
+                continue;
+            }
+            let tree_oid: MaybeZeroOid = match tree_dir.file_name().to_str().map(str::parse) {
+                Some(Ok(tree_oid)) => tree_oid,
+                Some(Err(_)) | None => continue,
+            };
+            for command_dir in std::fs::read_dir(tree_dir.path())? {
+                let command_dir = command_dir?;
+                if !command_dir.file_type()?.is_dir() {
+                    continue;
+                }
+                if let Some(command_slug) = command_dir.file_name().to_str() {
+                    result.push((tree_oid, command_slug.to_owned()));
+                }
+            }
+        }
+        result.sort();
+        Ok(result)
+    }
+}
+
+/// Copy the files of a cache entry from `src_dir` into `dest_dir`. The
+/// `result` file is copied last and replaced atomically, so that a concurrent
+/// reader never observes a result without its output.
+fn copy_test_cache_entry(src_dir: &Path, dest_dir: &Path) -> eyre::Result<()> {
+    std::fs::create_dir_all(dest_dir)
+        .wrap_err_with(|| format!("Creating test cache directory {dest_dir:?}"))?;
+    for file_name in DirectoryTestCache::ENTRY_FILE_NAMES {
+        let src_path = src_dir.join(file_name);
+        let contents = match std::fs::read(&src_path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("Reading test cache file {src_path:?}"))
+            }
+        };
+        let mut temp_file = tempfile::NamedTempFile::new_in(dest_dir)?;
+        temp_file.write_all(&contents)?;
+        let dest_path = dest_dir.join(file_name);
+        temp_file
+            .persist(&dest_path)
+            .wrap_err_with(|| format!("Writing test cache file {dest_path:?}"))?;
+    }
+    Ok(())
+}
+
+impl TestCacheBackend for DirectoryTestCache {
+    fn fetch(
+        &self,
+        tree_oid: MaybeZeroOid,
+        command_slug: &str,
+        dest_dir: &Path,
+    ) -> eyre::Result<bool> {
+        if self.read_result(tree_oid, command_slug)?.is_none() {
+            return Ok(false);
+        }
+        copy_test_cache_entry(&self.get_entry_dir(tree_oid, command_slug), dest_dir)?;
+        Ok(true)
+    }
+
+    fn store(
+        &self,
+        tree_oid: MaybeZeroOid,
+        command_slug: &str,
+        src_dir: &Path,
+    ) -> eyre::Result<bool> {
+        if self.read_result(tree_oid, command_slug)?.is_some() {
+            return Ok(false);
+        }
+        copy_test_cache_entry(src_dir, &self.get_entry_dir(tree_oid, command_slug))?;
+        Ok(true)
+    }
+}
+
+/// Read the cached results of each test command which has been run on the
+/// given commit's tree. Returns `None` if the results couldn't be read.
+pub fn read_cached_test_results(repo: &Repo, commit: &Commit) -> Option<Vec<SerializedTestResult>> {
+    let commit_test_dir = get_test_tree_dir(repo, commit).ok()?;
+    let mut all_results = Vec::new();
+    for dir in std::fs::read_dir(commit_test_dir).ok()? {
+        let dir = dir.ok()?;
+        if dir.file_type().ok()?.is_dir() {
+            let result_path = dir.path().join("result");
+            let result_contents = std::fs::read_to_string(result_path).ok()?;
+            let result: SerializedTestResult = serde_json::from_str(&result_contents).ok()?;
+            all_results.push(result);
+        }
+    }
+    Some(all_results)
+}
+
+/// Read the test command most recently passed to `git test run`, if any.
+pub fn read_latest_test_command(repo: &Repo) -> Option<String> {
+    let latest_command_path = get_latest_test_command_path(repo).ok()?;
+    let latest_command = std::fs::read_to_string(latest_command_path).ok()?;
+    Some(latest_command)
+}
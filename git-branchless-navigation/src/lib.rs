@@ -36,6 +36,7 @@ use lib::core::formatting::Pluralize;
 use lib::core::node_descriptors::{
     BranchesDescriptor, CommitMessageDescriptor, CommitOidDescriptor,
     DifferentialRevisionDescriptor, NodeDescriptor, Redactor, RelativeTimeDescriptor,
+    ReviewStatusDescriptor,
 };
 use lib::git::{GitRunInfo, NonZeroOid, Repo};
 
@@ -396,6 +397,7 @@ pub fn traverse_commits(
                 &Redactor::Disabled,
             )?,
             &mut DifferentialRevisionDescriptor::new(&repo, &Redactor::Disabled)?,
+            &mut ReviewStatusDescriptor::new(&repo, &Redactor::Disabled)?,
             &mut CommitMessageDescriptor::new(&Redactor::Disabled)?,
         ],
         head_oid,
@@ -545,6 +547,7 @@ pub fn switch(
                         &Redactor::Disabled,
                     )?,
                     &mut DifferentialRevisionDescriptor::new(&repo, &Redactor::Disabled)?,
+                    &mut ReviewStatusDescriptor::new(&repo, &Redactor::Disabled)?,
                     &mut CommitMessageDescriptor::new(&Redactor::Disabled)?,
                 ],
             )? {
@@ -0,0 +1,581 @@
+//! Generation of shell completion scripts from the `clap` definitions.
+//!
+//! The scripts complete subcommand names and flags statically. Arguments which
+//! accept a revset are completed dynamically, by calling back into `git
+//! branchless completions <shell> --revset <word>`, since the candidates (such
+//! as branch names) depend on the repository.
+//!
+//! The Git aliases installed by `git branchless init` (such as `git sl` for
+//! `git branchless smartlog`) are completed like the subcommands they run.
+
+use std::any::TypeId;
+use std::io::{self, Write};
+
+use clap::{Arg, Command as ClapCommand};
+
+use crate::{get_command, Revset, Shell};
+
+/// The completion-relevant information about a subcommand.
+#[derive(Debug)]
+struct SubcommandInfo {
+    name: String,
+    about: String,
+
+    /// All flags, including short flags and visible aliases.
+    flags: Vec<String>,
+
+    /// The flags which take a revset as their value.
+    revset_flags: Vec<String>,
+
+    /// Whether the subcommand accepts revsets as positional arguments.
+    takes_positional_revsets: bool,
+
+    /// The names of any nested subcommands, such as `git test run`.
+    nested_subcommands: Vec<String>,
+}
+
+fn is_revset_arg(arg: &Arg) -> bool {
+    arg.get_value_parser().type_id() == TypeId::of::<Revset>()
+}
+
+fn get_flag_names(arg: &Arg) -> Vec<String> {
+    let mut result = Vec::new();
+    for short in arg.get_short_and_visible_aliases().unwrap_or_default() {
+        result.push(format!("-{short}"));
+    }
+    for long in arg.get_long_and_visible_aliases().unwrap_or_default() {
+        result.push(format!("--{long}"));
+    }
+    result
+}
+
+fn get_about(command: &ClapCommand) -> String {
+    command
+        .get_about()
+        .map(|about| about.to_string())
+        .and_then(|about| about.lines().next().map(|line| line.to_owned()))
+        .unwrap_or_default()
+}
+
+fn get_subcommand_info(subcommand: &ClapCommand) -> SubcommandInfo {
+    let mut info = SubcommandInfo {
+        name: subcommand.get_name().to_owned(),
+        about: get_about(subcommand),
+        flags: Default::default(),
+        revset_flags: Default::default(),
+        takes_positional_revsets: false,
+        nested_subcommands: Default::default(),
+    };
+
+    // Nested subcommands are not distinguished from each other, so complete
+    // the union of their arguments.
+    let commands = std::iter::once(subcommand).chain(
+        subcommand
+            .get_subcommands()
+            .filter(|nested_subcommand| !nested_subcommand.is_hide_set()),
+    );
+    for command in commands {
+        if command.get_name() != subcommand.get_name() {
+            info.nested_subcommands.push(command.get_name().to_owned());
+        }
+        for arg in command.get_arguments() {
+            if arg.is_hide_set() {
+                continue;
+            }
+            if arg.is_positional() {
+                info.takes_positional_revsets |= is_revset_arg(arg);
+                continue;
+            }
+
+            let flag_names = get_flag_names(arg);
+            if is_revset_arg(arg) {
+                info.revset_flags.extend(flag_names.iter().cloned());
+            }
+            info.flags.extend(flag_names);
+        }
+    }
+
+    for names in [
+        &mut info.flags,
+        &mut info.revset_flags,
+        &mut info.nested_subcommands,
+    ] {
+        names.sort();
+        names.dedup();
+    }
+    info
+}
+
+fn get_subcommand_infos() -> Vec<SubcommandInfo> {
+    let mut command = get_command();
+    command.build();
+    command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set())
+        .map(get_subcommand_info)
+        .collect()
+}
+
+/// Quote the given string for use as a single-quoted shell word.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// A Git alias for a subcommand, such as `git sl` for `git branchless
+/// smartlog`.
+#[derive(Debug)]
+struct AliasInfo<'a> {
+    name: &'a str,
+    subcommand_name: &'a str,
+}
+
+/// Get the aliases which run one of the provided subcommands, given as pairs
+/// of the alias name and the subcommand name.
+fn get_alias_infos<'a>(
+    subcommands: &[SubcommandInfo],
+    aliases: &[(&'a str, &'a str)],
+) -> Vec<AliasInfo<'a>> {
+    aliases
+        .iter()
+        .filter(|(_name, subcommand_name)| {
+            subcommands
+                .iter()
+                .any(|subcommand| subcommand.name == *subcommand_name)
+        })
+        .map(|(name, subcommand_name)| AliasInfo {
+            name,
+            subcommand_name,
+        })
+        .collect()
+}
+
+/// Write the completion script for the given shell. `aliases` contains the
+/// Git aliases which should be completed like the subcommands they run, as
+/// pairs of the alias name and the subcommand name.
+pub fn write_completion_script(
+    shell: Shell,
+    aliases: &[(&str, &str)],
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let subcommands = get_subcommand_infos();
+    let aliases = get_alias_infos(&subcommands, aliases);
+    match shell {
+        Shell::Bash => write_bash_script(&subcommands, &aliases, out),
+        Shell::Zsh => write_zsh_script(&subcommands, &aliases, out),
+        Shell::Fish => write_fish_script(&subcommands, &aliases, out),
+    }
+}
+
+/// Write the arms of a shell `case` statement which set `subcommand` to the
+/// subcommand run by each alias whose name isn't already a subcommand name.
+fn write_alias_case_arms(
+    subcommands: &[SubcommandInfo],
+    aliases: &[AliasInfo],
+    indent: &str,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    for AliasInfo {
+        name,
+        subcommand_name,
+    } in aliases
+    {
+        if subcommands
+            .iter()
+            .any(|subcommand| subcommand.name == *name)
+        {
+            continue;
+        }
+        writeln!(out, "{indent}{name})")?;
+        writeln!(out, "{indent}    subcommand={}", quote(subcommand_name))?;
+        writeln!(out, "{indent}    break")?;
+        writeln!(out, "{indent}    ;;")?;
+    }
+    Ok(())
+}
+
+fn write_bash_script(
+    subcommands: &[SubcommandInfo],
+    aliases: &[AliasInfo],
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let subcommand_names: Vec<&str> = subcommands
+        .iter()
+        .map(|subcommand| subcommand.name.as_str())
+        .collect();
+
+    writeln!(
+        out,
+        "# bash completion for git-branchless, generated by `git branchless completions bash`.
+# This defines `_git_branchless`, which is used by Git's own completion script to
+# complete `git branchless ...`.
+
+_git_branchless_revsets() {{
+    local IFS=$'\\n'
+    COMPREPLY=($(git branchless completions bash --revset \"$1\" 2>/dev/null))
+    # Don't add a space after a function name, so that its arguments can be
+    # typed immediately.
+    if [[ ${{#COMPREPLY[@]}} == 1 && ${{COMPREPLY[0]}} == *\"(\" ]]; then
+        compopt -o nospace 2>/dev/null
+    fi
+}}
+
+_git_branchless() {{
+    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"
+    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"
+    local subcommand word
+    for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do
+        case \"$word\" in
+            {})
+                subcommand=\"$word\"
+                break
+                ;;",
+        subcommand_names.join("|"),
+    )?;
+    write_alias_case_arms(subcommands, aliases, "            ", out)?;
+    writeln!(
+        out,
+        "        esac
+    done
+
+    local flags=\"\" revset_flags=\"\" positional=\"\" takes_positional_revsets=0
+    case \"$subcommand\" in
+        \"\")
+            COMPREPLY=($(compgen -W {} -- \"$cur\"))
+            return
+            ;;",
+        quote(&subcommand_names.join(" ")),
+    )?;
+    for subcommand in subcommands {
+        let SubcommandInfo {
+            name,
+            about: _,
+            flags,
+            revset_flags,
+            takes_positional_revsets,
+            nested_subcommands,
+        } = subcommand;
+        writeln!(out, "        {name})")?;
+        writeln!(out, "            flags={}", quote(&flags.join(" ")))?;
+        if !revset_flags.is_empty() {
+            writeln!(
+                out,
+                "            revset_flags={}",
+                quote(&revset_flags.join(" "))
+            )?;
+        }
+        if !nested_subcommands.is_empty() {
+            writeln!(
+                out,
+                "            positional={}",
+                quote(&nested_subcommands.join(" "))
+            )?;
+        }
+        if *takes_positional_revsets {
+            writeln!(out, "            takes_positional_revsets=1")?;
+        }
+        writeln!(out, "            ;;")?;
+    }
+    writeln!(
+        out,
+        "    esac
+
+    # Stop completing nested subcommands once one has been provided.
+    for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do
+        if [[ \" $positional \" == *\" $word \"* ]]; then
+            positional=\"\"
+        fi
+    done
+
+    if [[ \" $revset_flags \" == *\" $prev \"* ]]; then
+        _git_branchless_revsets \"$cur\"
+    elif [[ \"$cur\" == -* ]]; then
+        COMPREPLY=($(compgen -W \"$flags\" -- \"$cur\"))
+    elif [[ -n \"$positional\" ]]; then
+        COMPREPLY=($(compgen -W \"$positional\" -- \"$cur\"))
+    elif [[ $takes_positional_revsets == 1 ]]; then
+        _git_branchless_revsets \"$cur\"
+    else
+        COMPREPLY=($(compgen -f -- \"$cur\"))
+    fi
+}}
+
+complete -F _git_branchless git-branchless"
+    )?;
+
+    // Git's completion script calls `_git_<alias>` to complete `git <alias>`.
+    for AliasInfo {
+        name,
+        subcommand_name: _,
+    } in aliases
+    {
+        writeln!(out, "_git_{name}() {{ _git_branchless; }}")?;
+    }
+    Ok(())
+}
+
+fn write_zsh_script(
+    subcommands: &[SubcommandInfo],
+    aliases: &[AliasInfo],
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let subcommand_names: Vec<&str> = subcommands
+        .iter()
+        .map(|subcommand| subcommand.name.as_str())
+        .collect();
+    let subcommand_descriptions: Vec<String> = subcommands
+        .iter()
+        .map(|subcommand| quote(&format!("{}:{}", subcommand.name, subcommand.about)))
+        .collect();
+
+    writeln!(
+        out,
+        "#compdef git-branchless
+# zsh completion for git-branchless, generated by `git branchless completions zsh`.
+# This defines `_git-branchless`, which is also used by zsh's Git completion to
+# complete `git branchless ...`.
+
+_git_branchless_revsets() {{
+    local -a candidates functions
+    candidates=(${{(f)\"$(git branchless completions zsh --revset \"$PREFIX\" 2>/dev/null)\"}})
+    # Don't add a space after a function name, so that its arguments can be
+    # typed immediately.
+    functions=(${{(M)candidates:#*\\(:*}})
+    candidates=(${{candidates:#*\\(:*}})
+    _describe -t revsets 'revset' candidates -Q
+    _describe -t functions 'revset function' functions -Q -S ''
+}}
+
+_git-branchless() {{
+    local subcommand i
+    # `$words[1]` is the alias name when completing an alias such as `git sl`.
+    for (( i = 1; i < CURRENT; i++ )); do
+        case \"$words[i]\" in
+            {})
+                subcommand=\"$words[i]\"
+                break
+                ;;",
+        subcommand_names.join("|"),
+    )?;
+    write_alias_case_arms(subcommands, aliases, "            ", out)?;
+    writeln!(
+        out,
+        "        esac
+    done
+
+    local -a flags revset_flags positional
+    local takes_positional_revsets=0
+    case \"$subcommand\" in
+        \"\")
+            local -a subcommands
+            subcommands=({})
+            _describe -t commands 'git-branchless command' subcommands
+            return
+            ;;",
+        subcommand_descriptions.join(" "),
+    )?;
+    for subcommand in subcommands {
+        let SubcommandInfo {
+            name,
+            about: _,
+            flags,
+            revset_flags,
+            takes_positional_revsets,
+            nested_subcommands,
+        } = subcommand;
+        let quote_all =
+            |names: &[String]| names.iter().map(|s| quote(s)).collect::<Vec<_>>().join(" ");
+        writeln!(out, "        {name})")?;
+        writeln!(out, "            flags=({})", quote_all(flags))?;
+        if !revset_flags.is_empty() {
+            writeln!(
+                out,
+                "            revset_flags=({})",
+                quote_all(revset_flags)
+            )?;
+        }
+        if !nested_subcommands.is_empty() {
+            writeln!(
+                out,
+                "            positional=({})",
+                quote_all(nested_subcommands)
+            )?;
+        }
+        if *takes_positional_revsets {
+            writeln!(out, "            takes_positional_revsets=1")?;
+        }
+        writeln!(out, "            ;;")?;
+    }
+    writeln!(
+        out,
+        "    esac
+
+    # Stop completing nested subcommands once one has been provided.
+    for (( i = 2; i < CURRENT; i++ )); do
+        if (( ${{positional[(Ie)$words[i]]}} )); then
+            positional=()
+        fi
+    done
+
+    if (( ${{revset_flags[(Ie)$words[CURRENT-1]]}} )); then
+        _git_branchless_revsets
+    elif [[ $PREFIX == -* ]]; then
+        compadd -a flags
+    elif (( $#positional )); then
+        compadd -a positional
+    elif (( takes_positional_revsets )); then
+        _git_branchless_revsets
+    else
+        _files
+    fi
+}}
+"
+    )?;
+
+    // zsh's Git completion calls `_git-<alias>` to complete `git <alias>`.
+    for AliasInfo {
+        name,
+        subcommand_name: _,
+    } in aliases
+    {
+        writeln!(out, "_git-{name}() {{ _git-branchless \"$@\" }}")?;
+    }
+
+    writeln!(
+        out,
+        "
+if [[ $zsh_eval_context[-1] == loadautofunc ]]; then
+    _git-branchless \"$@\"
+else
+    compdef _git-branchless git-branchless
+fi"
+    )?;
+    Ok(())
+}
+
+fn write_fish_script(
+    subcommands: &[SubcommandInfo],
+    aliases: &[AliasInfo],
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let subcommand_names: Vec<&str> = subcommands
+        .iter()
+        .map(|subcommand| subcommand.name.as_str())
+        .collect();
+
+    writeln!(
+        out,
+        "# fish completion for git-branchless, generated by `git branchless completions fish`.
+# This completes `git-branchless ...`, `git branchless ...` and the Git aliases
+# installed by `git branchless init`, such as `git sl`.
+
+function __git_branchless_subcommand
+    for token in (commandline -opc)[2..-1]
+        switch $token
+            case {}
+                echo $token
+                return 0",
+        subcommand_names.join(" "),
+    )?;
+    for AliasInfo {
+        name,
+        subcommand_name,
+    } in aliases
+    {
+        if subcommand_names.contains(name) {
+            continue;
+        }
+        writeln!(out, "            case {name}")?;
+        writeln!(out, "                echo {subcommand_name}")?;
+        writeln!(out, "                return 0")?;
+    }
+    writeln!(
+        out,
+        "        end
+    end
+    return 1
+end
+
+function __git_branchless_needs_subcommand
+    not __git_branchless_subcommand >/dev/null
+end
+
+function __git_branchless_using_subcommand
+    test (__git_branchless_subcommand) = $argv[1]
+end
+
+function __git_branchless_revsets
+    git branchless completions fish --revset (commandline -ct) 2>/dev/null
+end
+"
+    )?;
+
+    let git_subcommand_names = std::iter::once("branchless")
+        .chain(aliases.iter().map(|alias| alias.name))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let write_complete = |out: &mut dyn Write, condition: &str, rest: &str| -> io::Result<()> {
+        writeln!(
+            out,
+            "complete -c git-branchless -n {} {rest}",
+            quote(condition)
+        )?;
+        writeln!(
+            out,
+            "complete -c git -n {} {rest}",
+            quote(&format!(
+                "__fish_seen_subcommand_from {git_subcommand_names}; and {condition}"
+            ))
+        )?;
+        Ok(())
+    };
+
+    for subcommand in subcommands {
+        write_complete(
+            out,
+            "__git_branchless_needs_subcommand",
+            &format!(
+                "-f -a {} -d {}",
+                quote(&subcommand.name),
+                quote(&subcommand.about)
+            ),
+        )?;
+    }
+    for subcommand in subcommands {
+        let SubcommandInfo {
+            name,
+            about: _,
+            flags,
+            revset_flags,
+            takes_positional_revsets,
+            nested_subcommands,
+        } = subcommand;
+        let condition = format!("__git_branchless_using_subcommand {name}");
+        for flag in flags {
+            let flag_option = match flag.strip_prefix("--") {
+                Some(long) => format!("-l {}", quote(long)),
+                None => format!("-s {}", quote(flag.trim_start_matches('-'))),
+            };
+            if revset_flags.contains(flag) {
+                write_complete(
+                    out,
+                    &condition,
+                    &format!("{flag_option} -x -a '(__git_branchless_revsets)'"),
+                )?;
+            } else {
+                write_complete(out, &condition, &flag_option)?;
+            }
+        }
+        if !nested_subcommands.is_empty() {
+            let nested_subcommands = nested_subcommands.join(" ");
+            write_complete(
+                out,
+                &format!("{condition}; and not __fish_seen_subcommand_from {nested_subcommands}"),
+                &format!("-f -a {}", quote(&nested_subcommands)),
+            )?;
+        }
+        if *takes_positional_revsets {
+            write_complete(out, &condition, "-f -a '(__git_branchless_revsets)'")?;
+        }
+    }
+    Ok(())
+}
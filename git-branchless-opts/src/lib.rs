@@ -20,6 +20,10 @@ use std::str::FromStr;
 use clap::{Args, Command as ClapCommand, CommandFactory, Parser, ValueEnum};
 use lib::git::NonZeroOid;
 
+mod completions;
+
+pub use completions::write_completion_script;
+
 /// A revset expression. Can be a commit hash, branch name, or one of the
 /// various revset functions.
 #[derive(Clone, Debug)]
@@ -261,6 +265,33 @@ pub struct InitArgs {
     pub main_branch_name: Option<String>,
 }
 
+/// A shell for which completions can be generated.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Shell {
+    /// Bash, via `git`'s own completion script or for `git-branchless` directly.
+    Bash,
+
+    /// Zsh.
+    Zsh,
+
+    /// Fish.
+    Fish,
+}
+
+/// Generate shell completions.
+#[derive(Debug, Parser)]
+pub struct CompletionsArgs {
+    /// The shell to generate completions for.
+    #[clap(value_enum)]
+    pub shell: Shell,
+
+    /// Instead of printing the completion script, print the candidates for
+    /// completing the given partial revset expression, one per line. This is
+    /// invoked by the completion scripts themselves.
+    #[clap(value_parser, long = "revset", allow_hyphen_values = true)]
+    pub revset: Option<String>,
+}
+
 /// Install git-branchless's man-pages to the given path.
 #[derive(Debug, Parser)]
 pub struct InstallManPagesArgs {
@@ -280,8 +311,8 @@ pub struct InstallManPagesArgs {
 #[derive(Debug, Parser)]
 pub struct QueryArgs {
     /// The query to execute.
-    #[clap(value_parser)]
-    pub revset: Revset,
+    #[clap(value_parser, required_unless_present("list_functions"))]
+    pub revset: Option<Revset>,
 
     /// Options for resolving revset expressions.
     #[clap(flatten)]
@@ -295,6 +326,65 @@ pub struct QueryArgs {
     /// stable for use in scripts.
     #[clap(action, short = 'r', long = "raw", conflicts_with("show_branches"))]
     pub raw: bool,
+
+    /// Print each matching commit according to the given template, one per
+    /// line. The placeholders `{oid}`, `{short_oid}`, `{summary}`,
+    /// `{author_name}`, `{author_email}`, `{author_date}`, `{branches}`,
+    /// `{parents}` and `{test_status}` are replaced with information about the
+    /// commit. Use `{{` and `}}` for literal braces.
+    #[clap(
+        value_parser,
+        long = "format",
+        conflicts_with_all(&["show_branches", "raw"])
+    )]
+    pub format: Option<String>,
+
+    /// Print each matching commit as a JSON object on its own line, with the
+    /// same fields as the placeholders accepted by `--format`.
+    #[clap(
+        action,
+        long = "json",
+        conflicts_with_all(&["show_branches", "raw", "format"])
+    )]
+    pub json: bool,
+
+    /// Print the parts of the query as a tree, along with the number of
+    /// commits that each part evaluated to and how long it took to evaluate,
+    /// rather than the resulting commits.
+    #[clap(
+        action,
+        long = "explain",
+        conflicts_with_all(&["show_branches", "raw", "format", "json"])
+    )]
+    pub explain: bool,
+
+    /// Render exactly the resulting commits as a graph, like the smartlog,
+    /// including public commits. Runs of commits which aren't part of the
+    /// result are collapsed into a marker with the number of omitted commits.
+    #[clap(
+        action,
+        long = "graph",
+        conflicts_with_all(&["show_branches", "raw", "format", "json", "explain"])
+    )]
+    pub graph: bool,
+
+    /// List the functions which can be used in queries, including
+    /// user-defined aliases, along with the number of arguments that each
+    /// accepts. Exits with an error if any aliases are invalid.
+    #[clap(
+        action,
+        long = "list-functions",
+        conflicts_with_all(&[
+            "revset",
+            "show_branches",
+            "raw",
+            "format",
+            "json",
+            "explain",
+            "graph"
+        ])
+    )]
+    pub list_functions: bool,
 }
 
 /// Create a commit by interactively selecting which changes to include.
@@ -365,10 +455,23 @@ pub enum ForgeKind {
     /// default push remote with `git config remote.pushDefault <remote>`.
     Branch,
 
+    /// Push commits to `refs/for/<main branch>` on the default push remote
+    /// for review on Gerrit. A `Change-Id` trailer is added to each commit
+    /// which doesn't already have one.
+    Gerrit,
+
     /// Force-push branches to the remote and create a pull request for each
-    /// branch using the `gh` command-line tool. WARNING: likely buggy!
+    /// branch. If a GitHub API token is configured (via `git config
+    /// branchless.submit.github.token` or the `GITHUB_TOKEN` environment
+    /// variable), calls the GitHub API directly; otherwise, uses the `gh`
+    /// command-line tool. WARNING: likely buggy!
     Github,
 
+    /// Force-push branches to the remote and create a merge request for each
+    /// branch using the `glab` command-line tool. Each merge request targets
+    /// the branch of the previous commit in the stack.
+    Gitlab,
+
     /// Submit code reviews to Phabricator using the `arc` command-line tool.
     Phabricator,
 }
@@ -422,6 +525,33 @@ pub struct SubmitArgs {
     /// created. (This may still trigger fetching information from the forge.)
     #[clap(short = 'n', long = "dry-run")]
     pub dry_run: bool,
+
+    /// Don't push or create anything. Instead, print a report of the local
+    /// name, remote name, code review URL, and submission status of each
+    /// commit.
+    #[clap(
+        action,
+        long = "status",
+        conflicts_with_all(&["create", "draft", "message", "dry_run"])
+    )]
+    pub status: bool,
+
+    /// With `--status`, print the report as JSON.
+    #[clap(action, long = "json", requires("status"))]
+    pub json: bool,
+
+    /// Instead of pushing the commits for review, land them onto the main
+    /// branch and push it. Each commit's code review must have been approved.
+    /// Any ancestors of the commits which aren't on the main branch yet are
+    /// landed as well. The commits must already be on top of the main branch;
+    /// if it has moved, sync and resubmit them first. Not supported for the
+    /// Gerrit and Phabricator forges.
+    #[clap(
+        action,
+        long = "land",
+        conflicts_with_all(&["create", "draft", "message", "status"])
+    )]
+    pub land: bool,
 }
 
 /// Run a command on each commit in a given set and aggregate the results.
@@ -452,6 +582,10 @@ pub enum Command {
     /// report.
     BugReport,
 
+    /// Generate shell completions.
+    #[clap(hide = true)]
+    Completions(CompletionsArgs),
+
     /// Use the partial commit selector UI as a Git-compatible difftool; see
     /// git-difftool(1) for more information on Git difftools.
     Difftool(scm_diff_editor::Opts),
@@ -752,6 +886,10 @@ pub enum TestExecutionStrategy {
     /// to run tests in parallel, or if you want to run tests on a different commit without
     /// invalidating build artifacts in the current directory, or if you want to run tests while
     /// your working copy is dirty.
+    ///
+    /// Each job reuses its worktree between runs, so untracked build artifacts are kept. The
+    /// command set in `branchless.test.worktreeSetup` is run in a worktree when it's created. Run
+    /// `git test clean --worktrees` to remove the worktrees.
     Worktree,
 }
 
@@ -771,6 +909,46 @@ pub enum TestSearchStrategy {
     Binary,
 }
 
+/// The format of a test report.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum TestReportFormat {
+    /// A JUnit XML file with one test case per commit, as understood by most
+    /// CI systems.
+    Junit,
+
+    /// A JSON array with one object per commit.
+    Json,
+}
+
+/// A report of the test results to write to a file, specified as
+/// `<format>=<path>`, such as `junit=report.xml`.
+#[derive(Clone, Debug)]
+pub struct TestReport {
+    /// The format of the report.
+    pub format: TestReportFormat,
+
+    /// The path to write the report to.
+    pub path: PathBuf,
+}
+
+impl FromStr for TestReport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format_name, path) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected <format>=<path>, but got: {s}"))?;
+        let format = TestReportFormat::from_str(format_name, true)?;
+        if path.is_empty() {
+            return Err(format!("no path provided for the {format_name} report"));
+        }
+        Ok(Self {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
 /// Arguments which apply to all commands. Used during setup.
 #[derive(Debug, Parser)]
 pub struct GlobalArgs {
@@ -822,12 +1000,23 @@ pub enum TestSubcommand {
     /// Clean any cached test results.
     Clean {
         /// The set of commits whose results should be cleaned.
-        #[clap(value_parser, default_value = "stack() | @")]
+        #[clap(
+            value_parser,
+            default_value = "stack() | @",
+            conflicts_with("worktrees")
+        )]
         revset: Revset,
 
         /// Options for resolving revset expressions.
         #[clap(flatten)]
         resolve_revset_options: ResolveRevsetOptions,
+
+        /// Remove the worktrees used by `--strategy worktree` instead of
+        /// cleaning test results. The command set in
+        /// `branchless.test.worktreeTeardown` is run in each worktree before
+        /// it's removed.
+        #[clap(long = "worktrees")]
+        worktrees: bool,
     },
 
     /// Run a given command on a set of commits and present the successes and failures.
@@ -878,6 +1067,19 @@ pub enum TestSubcommand {
         /// How many jobs to execute in parallel. The value `0` indicates to use all CPUs.
         #[clap(short = 'j', long = "jobs")]
         jobs: Option<usize>,
+
+        /// Run the test command this many times on each commit. If the runs
+        /// don't all have the same result, then the commit is reported as
+        /// flaky and counted as a failure. Cached results from fewer runs are
+        /// ignored.
+        #[clap(long = "repeat", default_value_t = 1)]
+        repeat: usize,
+
+        /// Write a report of the results for each commit to a file, in
+        /// addition to printing them. Specified as `junit=<path>` or
+        /// `json=<path>`. May be provided multiple times.
+        #[clap(value_parser, long = "report")]
+        reports: Vec<TestReport>,
     },
 
     /// Show the results of a set of previous test runs.
@@ -902,6 +1104,13 @@ pub enum TestSubcommand {
         /// Show the test output as well.
         #[clap(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
         verbosity: u8,
+
+        /// Show the differences between the stdout and stderr of the test
+        /// command on two commits, such as the last passing commit and the
+        /// first failing commit. The revset must contain exactly two commits,
+        /// and the output of the earlier commit is shown as the original.
+        #[clap(long = "diff", conflicts_with("verbosity"))]
+        diff: bool,
     },
 
     /// Run a given command on a set of commits and present the successes and failures.
@@ -947,6 +1156,41 @@ pub enum TestSubcommand {
         #[clap(flatten)]
         move_options: MoveOptions,
     },
+
+    /// Copy the cached passing test results for a set of commits into a
+    /// directory, so that they can be imported into another clone of the
+    /// repository.
+    Export {
+        /// The directory to export the test results into. It will be created
+        /// if it doesn't exist.
+        #[clap(value_parser)]
+        path: PathBuf,
+
+        /// The set of commits whose test results should be exported.
+        #[clap(value_parser, default_value = "stack() | @")]
+        revset: Revset,
+
+        /// Options for resolving revset expressions.
+        #[clap(flatten)]
+        resolve_revset_options: ResolveRevsetOptions,
+    },
+
+    /// Copy the passing test results from a directory created by `git test
+    /// export` into this repository's cache. Existing passing results are
+    /// not overwritten, but failing ones are.
+    Import {
+        /// The directory to import the test results from.
+        #[clap(value_parser)]
+        path: PathBuf,
+    },
+}
+
+/// Get the `clap` definition of the `git-branchless` command-line interface.
+fn get_command() -> ClapCommand {
+    // Explicitly set the name here, or else clap thinks that the name of the
+    // command is `git-branchless-opts` (and that its subcommands are
+    // `git-branchless-opts-amend`, etc.).
+    Opts::command().name("git-branchless")
 }
 
 /// Generate and write man-pages into the specified directory.
@@ -958,11 +1202,7 @@ pub fn write_man_pages(man_dir: &Path) -> std::io::Result<()> {
     let man1_dir = man_dir.join("man1");
     std::fs::create_dir_all(&man1_dir)?;
 
-    let app =
-        // Explicitly set the name here, or else clap thinks that the name of the
-        // command is `git-branchless-opts` (and that its subcommands are
-        // `git-branchless-opts-amend`, etc.).
-        Opts::command().name("git-branchless");
+    let app = get_command();
     generate_man_page(&man1_dir, "git-branchless", &app)?;
     for subcommand in app.get_subcommands() {
         let subcommand_exe_name = format!("git-branchless-{}", subcommand.get_name());
@@ -1,17 +1,49 @@
+mod template;
+
 use std::fmt::Write;
+use std::time::SystemTime;
 
 use git_branchless_invoke::CommandContext;
 use itertools::Itertools;
 use lib::core::dag::Dag;
 use lib::core::effects::{Effects, OperationType};
 use lib::core::eventlog::{EventLogDb, EventReplayer};
+use lib::core::formatting::Pluralize;
+use lib::core::node_descriptors::{
+    BranchesDescriptor, CommitMessageDescriptor, CommitOidDescriptor,
+    ObsolescenceExplanationDescriptor, Redactor, RelativeTimeDescriptor,
+};
 use lib::core::repo_ext::RepoExt;
-use lib::git::{CategorizedReferenceName, GitRunInfo, Repo};
+use lib::git::{read_latest_test_command, CategorizedReferenceName, GitRunInfo, Repo};
 use lib::util::{ExitCode, EyreExitOr};
 use tracing::instrument;
 
 use git_branchless_opts::{QueryArgs, ResolveRevsetOptions, Revset};
-use git_branchless_revset::resolve_commits;
+use git_branchless_revset::{
+    explain_revset, list_functions, resolve_commits, ExplainNode, FunctionInfo,
+};
+use git_branchless_smartlog::{make_exact_graph, render_graph};
+
+use crate::template::{CommitInfo, Template};
+
+/// How to print each commit resulting from a query.
+#[derive(Debug)]
+enum CommitFormat {
+    /// A human-readable description of the commit.
+    Friendly,
+
+    /// Only the commit's OID.
+    Raw,
+
+    /// The commit's information substituted into a template.
+    Template(Template),
+
+    /// The commit's information as a JSON object.
+    Json,
+
+    /// All of the commits rendered together as a graph, like the smartlog.
+    Graph,
+}
 
 /// `query` command.
 #[instrument]
@@ -25,17 +57,103 @@ pub fn command_main(ctx: CommandContext, args: QueryArgs) -> EyreExitOr<()> {
         resolve_revset_options,
         show_branches,
         raw,
+        format,
+        json,
+        explain,
+        graph,
+        list_functions,
     } = args;
+    let revset = match revset {
+        Some(revset) if !list_functions => revset,
+        _ => return print_functions(&effects),
+    };
+
+    let commit_format = match format {
+        Some(format) => match Template::parse(&format) {
+            Ok(template) => CommitFormat::Template(template),
+            Err(err) => {
+                writeln!(effects.get_error_stream(), "{err}")?;
+                return Ok(Err(ExitCode(1)));
+            }
+        },
+        None if json => CommitFormat::Json,
+        None if graph => CommitFormat::Graph,
+        None if raw => CommitFormat::Raw,
+        None => CommitFormat::Friendly,
+    };
     query(
         &effects,
         &git_run_info,
         revset,
         &resolve_revset_options,
         show_branches,
-        raw,
+        explain,
+        commit_format,
     )
 }
 
+/// Print the builtin functions and aliases which can be used in queries.
+#[instrument]
+fn print_functions(effects: &Effects) -> EyreExitOr<()> {
+    let repo = Repo::from_current_dir()?;
+    let functions = list_functions(&repo)?;
+
+    let render_arity = |arities: &[usize]| -> String {
+        match arities {
+            [arity] => Pluralize {
+                determiner: None,
+                amount: *arity,
+                unit: ("argument", "arguments"),
+            }
+            .to_string(),
+            arities => format!(
+                "{} arguments",
+                arities.iter().map(|arity| arity.to_string()).join(" or ")
+            ),
+        }
+    };
+
+    writeln!(effects.get_output_stream(), "Builtin functions:")?;
+    for function in functions.iter() {
+        if let FunctionInfo::Builtin { name, arities } = function {
+            writeln!(
+                effects.get_output_stream(),
+                "  {name}: {}",
+                render_arity(arities)
+            )?;
+        }
+    }
+
+    let mut is_valid = true;
+    let mut printed_header = false;
+    for function in functions.iter() {
+        if let FunctionInfo::Alias { name, alias, arity } = function {
+            if !printed_header {
+                writeln!(effects.get_output_stream(), "Aliases:")?;
+                printed_header = true;
+            }
+            match arity {
+                Ok(arity) => writeln!(
+                    effects.get_output_stream(),
+                    "  {name}: {} = {alias}",
+                    render_arity(&[*arity])
+                )?,
+                Err(err) => {
+                    is_valid = false;
+                    let err = err.to_string().replace('\n', "\n    ");
+                    writeln!(effects.get_output_stream(), "  {name}: invalid: {err}")?;
+                }
+            }
+        }
+    }
+
+    if is_valid {
+        Ok(Ok(()))
+    } else {
+        Ok(Err(ExitCode(1)))
+    }
+}
+
 #[instrument]
 fn query(
     effects: &Effects,
@@ -43,7 +161,8 @@ fn query(
     query: Revset,
     resolve_revset_options: &ResolveRevsetOptions,
     show_branches: bool,
-    raw: bool,
+    explain: bool,
+    commit_format: CommitFormat,
 ) -> EyreExitOr<()> {
     let repo = Repo::from_current_dir()?;
     let conn = repo.get_db_conn()?;
@@ -59,6 +178,19 @@ fn query(
         &references_snapshot,
     )?;
 
+    if explain {
+        let explanation =
+            match explain_revset(effects, &repo, &mut dag, &query, resolve_revset_options) {
+                Ok(explanation) => explanation,
+                Err(err) => {
+                    err.describe(effects)?;
+                    return Ok(Err(ExitCode(1)));
+                }
+            };
+        print_explanation(effects, &explanation, 0)?;
+        return Ok(Ok(()));
+    }
+
     let commit_set =
         match resolve_commits(effects, &repo, &mut dag, &[query], resolve_revset_options) {
             Ok(commit_sets) => commit_sets[0].clone(),
@@ -89,27 +221,93 @@ fn query(
             let ref_name = CategorizedReferenceName::new(ref_name);
             writeln!(effects.get_output_stream(), "{}", ref_name.render_suffix())?;
         }
+    } else if let CommitFormat::Graph = commit_format {
+        let graph = make_exact_graph(effects, &repo, &dag, &commit_set)?;
+        let lines = render_graph(
+            effects,
+            &repo,
+            &dag,
+            &graph,
+            references_snapshot.head_oid,
+            &mut [
+                &mut CommitOidDescriptor::new(true)?,
+                &mut RelativeTimeDescriptor::new(&repo, SystemTime::now())?,
+                &mut ObsolescenceExplanationDescriptor::new(&event_replayer, event_cursor)?,
+                &mut BranchesDescriptor::new(
+                    &repo,
+                    &repo.get_head_info()?,
+                    &references_snapshot,
+                    &Redactor::Disabled,
+                )?,
+                &mut CommitMessageDescriptor::new(&Redactor::Disabled)?,
+            ],
+        )?;
+        for line in lines {
+            writeln!(
+                effects.get_output_stream(),
+                "{}",
+                effects.get_glyphs().render(line)?
+            )?;
+        }
     } else {
         let commit_oids = {
             let (effects, _progress) = effects.start_operation(OperationType::SortCommits);
             let _effects = effects;
             dag.sort(&commit_set)?
         };
+        let latest_test_command = match commit_format {
+            CommitFormat::Template(_) | CommitFormat::Json => read_latest_test_command(&repo),
+            CommitFormat::Friendly | CommitFormat::Raw | CommitFormat::Graph => None,
+        };
         for commit_oid in commit_oids {
-            if raw {
-                writeln!(effects.get_output_stream(), "{commit_oid}")?;
-            } else {
-                let commit = repo.find_commit_or_fail(commit_oid)?;
-                writeln!(
-                    effects.get_output_stream(),
-                    "{}",
-                    effects
-                        .get_glyphs()
-                        .render(commit.friendly_describe(effects.get_glyphs())?)?,
-                )?;
-            }
+            let commit = repo.find_commit_or_fail(commit_oid)?;
+            let line = match &commit_format {
+                CommitFormat::Friendly => effects
+                    .get_glyphs()
+                    .render(commit.friendly_describe(effects.get_glyphs())?)?,
+                CommitFormat::Raw => commit_oid.to_string(),
+                CommitFormat::Graph => unreachable!("graph is rendered above"),
+                CommitFormat::Template(template) => template.render(&CommitInfo::new(
+                    &repo,
+                    &references_snapshot,
+                    latest_test_command.as_deref(),
+                    &commit,
+                )?),
+                CommitFormat::Json => CommitInfo::new(
+                    &repo,
+                    &references_snapshot,
+                    latest_test_command.as_deref(),
+                    &commit,
+                )?
+                .to_json()?,
+            };
+            writeln!(effects.get_output_stream(), "{line}")?;
         }
     }
 
     Ok(Ok(()))
 }
+
+/// Print the evaluated node and its children, indented by their depth.
+fn print_explanation(effects: &Effects, node: &ExplainNode, depth: usize) -> eyre::Result<()> {
+    let ExplainNode {
+        expr,
+        num_commits,
+        duration,
+        children,
+    } = node;
+    writeln!(
+        effects.get_output_stream(),
+        "{}{expr}: {} in {duration:.2?}",
+        "  ".repeat(depth),
+        Pluralize {
+            determiner: None,
+            amount: *num_commits,
+            unit: ("commit", "commits"),
+        },
+    )?;
+    for child in children {
+        print_explanation(effects, child, depth + 1)?;
+    }
+    Ok(())
+}
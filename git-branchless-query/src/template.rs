@@ -0,0 +1,244 @@
+//! Machine-readable output for `git query`, either as a user-provided template
+//! or as JSON.
+
+use itertools::Itertools;
+use lib::core::repo_ext::RepoReferencesSnapshot;
+use lib::git::{
+    read_cached_test_results, CategorizedReferenceName, Commit, Repo, TEST_ABORT_EXIT_CODE,
+    TEST_INDETERMINATE_EXIT_CODE, TEST_SUCCESS_EXIT_CODE,
+};
+use serde::Serialize;
+use thiserror::Error;
+
+/// An error parsing a `--format` template.
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error(
+        "unknown placeholder {{{name}}} in format template (expected one of: {})",
+        render_placeholder_names()
+    )]
+    UnknownPlaceholder { name: String },
+
+    #[error("unclosed placeholder in format template: {{{text}")]
+    UnclosedPlaceholder { text: String },
+
+    #[error("unmatched '}}' in format template (use '}}}}' for a literal '}}')")]
+    UnmatchedBrace,
+}
+
+/// Information about a commit which can appear in a template. Each field
+/// corresponds to a template placeholder of the same name.
+#[derive(Debug, Serialize)]
+pub struct CommitInfo {
+    oid: String,
+    short_oid: String,
+    summary: String,
+    author_name: String,
+    author_email: String,
+    author_date: String,
+    branches: Vec<String>,
+    parents: Vec<String>,
+    test_status: Option<&'static str>,
+}
+
+impl CommitInfo {
+    /// Collect the information about the given commit. The test status is
+    /// reported for `latest_test_command`, if any.
+    pub fn new(
+        repo: &Repo,
+        references_snapshot: &RepoReferencesSnapshot,
+        latest_test_command: Option<&str>,
+        commit: &Commit,
+    ) -> eyre::Result<Self> {
+        let author = commit.get_author();
+        let branches = match references_snapshot
+            .branch_oid_to_names
+            .get(&commit.get_oid())
+        {
+            Some(branch_names) => branch_names
+                .iter()
+                .sorted()
+                .map(|branch_name| CategorizedReferenceName::new(branch_name).render_suffix())
+                .collect(),
+            None => Vec::new(),
+        };
+        let test_status = latest_test_command.and_then(|latest_test_command| {
+            read_cached_test_results(repo, commit)
+                .unwrap_or_default()
+                .into_iter()
+                .find(|test_result| test_result.command.to_string() == latest_test_command)
+                .map(|test_result| match test_result.exit_code {
+                    TEST_SUCCESS_EXIT_CODE => "passed",
+                    TEST_INDETERMINATE_EXIT_CODE => "skipped",
+                    TEST_ABORT_EXIT_CODE => "aborted",
+                    _ => "failed",
+                })
+        });
+
+        Ok(Self {
+            oid: commit.get_oid().to_string(),
+            short_oid: commit.get_short_oid()?,
+            summary: commit.get_summary()?.to_string(),
+            author_name: author.get_name().unwrap_or_default().to_owned(),
+            author_email: author.get_email().unwrap_or_default().to_owned(),
+            author_date: match author.get_time().to_date_time() {
+                Some(date_time) => date_time.to_rfc3339(),
+                None => String::new(),
+            },
+            branches,
+            parents: commit
+                .get_parent_oids()
+                .into_iter()
+                .map(|parent_oid| parent_oid.to_string())
+                .collect(),
+            test_status,
+        })
+    }
+
+    /// Render this commit as a single line of JSON.
+    pub fn to_json(&self) -> eyre::Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+fn render_placeholder_names() -> String {
+    Placeholder::ALL
+        .iter()
+        .map(|placeholder| format!("{{{}}}", placeholder.get_name()))
+        .join(", ")
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Placeholder {
+    Oid,
+    ShortOid,
+    Summary,
+    AuthorName,
+    AuthorEmail,
+    AuthorDate,
+    Branches,
+    Parents,
+    TestStatus,
+}
+
+impl Placeholder {
+    const ALL: &'static [Placeholder] = &[
+        Placeholder::Oid,
+        Placeholder::ShortOid,
+        Placeholder::Summary,
+        Placeholder::AuthorName,
+        Placeholder::AuthorEmail,
+        Placeholder::AuthorDate,
+        Placeholder::Branches,
+        Placeholder::Parents,
+        Placeholder::TestStatus,
+    ];
+
+    fn get_name(&self) -> &'static str {
+        match self {
+            Placeholder::Oid => "oid",
+            Placeholder::ShortOid => "short_oid",
+            Placeholder::Summary => "summary",
+            Placeholder::AuthorName => "author_name",
+            Placeholder::AuthorEmail => "author_email",
+            Placeholder::AuthorDate => "author_date",
+            Placeholder::Branches => "branches",
+            Placeholder::Parents => "parents",
+            Placeholder::TestStatus => "test_status",
+        }
+    }
+
+    fn render(&self, info: &CommitInfo, out: &mut String) {
+        let CommitInfo {
+            oid,
+            short_oid,
+            summary,
+            author_name,
+            author_email,
+            author_date,
+            branches,
+            parents,
+            test_status,
+        } = info;
+        match self {
+            Placeholder::Oid => out.push_str(oid),
+            Placeholder::ShortOid => out.push_str(short_oid),
+            Placeholder::Summary => out.push_str(summary),
+            Placeholder::AuthorName => out.push_str(author_name),
+            Placeholder::AuthorEmail => out.push_str(author_email),
+            Placeholder::AuthorDate => out.push_str(author_date),
+            Placeholder::Branches => out.push_str(&branches.join(" ")),
+            Placeholder::Parents => out.push_str(&parents.join(" ")),
+            Placeholder::TestStatus => out.push_str(test_status.unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum TemplatePart {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A parsed `--format` template, such as `{short_oid} {summary}`.
+#[derive(Debug)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    /// Parse the template, checking that each placeholder is known.
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err(TemplateError::UnmatchedBrace),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(TemplateError::UnclosedPlaceholder { text: name }),
+                        }
+                    }
+                    let placeholder = Placeholder::ALL
+                        .iter()
+                        .find(|placeholder| placeholder.get_name() == name)
+                        .ok_or(TemplateError::UnknownPlaceholder { name })?;
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(TemplatePart::Placeholder(*placeholder));
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Render the template for the given commit.
+    pub fn render(&self, info: &CommitInfo) -> String {
+        let mut result = String::new();
+        for part in self.parts.iter() {
+            match part {
+                TemplatePart::Literal(literal) => result.push_str(literal),
+                TemplatePart::Placeholder(placeholder) => placeholder.render(info, &mut result),
+            }
+        }
+        result
+    }
+}
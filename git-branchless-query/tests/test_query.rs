@@ -1,4 +1,6 @@
-use lib::testing::{make_git, GitRunOptions};
+use lib::testing::{
+    make_git, make_git_with_remote_repo, GitInitOptions, GitRunOptions, GitWrapperWithRemoteRepo,
+};
 
 #[test]
 fn test_query() -> eyre::Result<()> {
@@ -87,7 +89,7 @@ fn test_query_eval_error() -> eyre::Result<()> {
             },
         )?;
         insta::assert_snapshot!(stderr, @r###"
-        Evaluation error for expression 'foo()': no function with the name 'foo' could be found; these functions are available: all, ancestors, ancestors.nth, author.date, author.email, author.name, branches, children, committer.date, committer.email, committer.name, current, descendants, difference, draft, exactly, heads, intersection, main, merges, message, none, not, only, parents, parents.nth, paths.changed, public, range, roots, siblings, stack, tests.failed, tests.fixable, tests.passed, union
+        Evaluation error for expression 'foo()': no function with the name 'foo' could be found; these functions are available: ahead_of_upstream, all, ancestors, ancestors.nth, author.date, author.email, author.name, behind_upstream, branches, children, committer.date, committer.email, committer.name, current, descendants, difference, draft, exactly, files_changed, heads, intersection, lines_changed, main, merges, message, none, not, only, parents, parents.nth, paths.changed, predecessors, public, range, remote_branches, roots, siblings, signed, stack, successors, tests.failed, tests.fixable, tests.flaky, tests.passed, tests.slower_than, touched_since, touches_binary, trailer, union, upstream
         "###);
         insta::assert_snapshot!(stdout, @"");
     }
@@ -208,3 +210,349 @@ fn test_query_hidden_commits() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_query_list_functions() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.run(&["config", "branchless.revsets.alias.myStack", "stack() & $1"])?;
+    {
+        let (stdout, stderr) = git.branchless("query", &["--list-functions"])?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        Builtin functions:
+          ahead_of_upstream: 0 arguments
+          all: 0 arguments
+          ancestors: 1 argument
+          ancestors.nth: 2 arguments
+          author.date: 1 argument
+          author.email: 1 argument
+          author.name: 1 argument
+          behind_upstream: 0 arguments
+          branches: 0 or 1 arguments
+          children: 1 argument
+          committer.date: 1 argument
+          committer.email: 1 argument
+          committer.name: 1 argument
+          current: 1 argument
+          descendants: 1 argument
+          difference: 2 arguments
+          draft: 0 arguments
+          exactly: 2 arguments
+          files_changed: 1 argument
+          heads: 1 argument
+          intersection: 2 arguments
+          lines_changed: 1 argument
+          main: 0 arguments
+          merges: 0 arguments
+          message: 1 argument
+          none: 0 arguments
+          not: 1 argument
+          only: 2 arguments
+          parents: 1 argument
+          parents.nth: 2 arguments
+          paths.changed: 1 argument
+          predecessors: 1 argument
+          public: 0 arguments
+          range: 2 arguments
+          remote_branches: 0 or 1 arguments
+          roots: 1 argument
+          siblings: 1 argument
+          signed: 0 arguments
+          stack: 0 or 1 arguments
+          successors: 1 argument
+          tests.failed: 0 or 1 arguments
+          tests.fixable: 0 or 1 arguments
+          tests.flaky: 0 or 1 arguments
+          tests.passed: 0 or 1 arguments
+          tests.slower_than: 1 or 2 arguments
+          touched_since: 1 argument
+          touches_binary: 0 arguments
+          trailer: 1 or 2 arguments
+          union: 2 arguments
+          upstream: 1 argument
+        Aliases:
+          mystack: 1 argument = stack() & $1
+        "###);
+    }
+
+    git.run(&["config", "branchless.revsets.alias.broken", "draft("])?;
+    git.run(&["config", "branchless.revsets.alias.loop", "parents(loop())"])?;
+    {
+        let (stdout, stderr) = git.branchless_with_options(
+            "query",
+            &["--list-functions"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @"");
+        let (_builtins, aliases) = stdout.split_once("Aliases:\n").unwrap();
+        insta::assert_snapshot!(aliases, @r###"
+        broken: invalid: failed to parse alias 'broken' (defined by branchless.revsets.alias.broken): 'draft('
+          parse error: Unrecognized EOF found at 6
+          Expected one of "(", ")", "..", ":", "::", a commit/branch/tag or a string literal
+        loop: invalid: alias 'loop' (defined by branchless.revsets.alias.loop) expands to itself infinitely: loop -> loop
+        mystack: 1 argument = stack() & $1
+        "###);
+    }
+
+    {
+        let (stdout, stderr) = git.branchless_with_options(
+            "query",
+            &["loop()"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @"Evaluation error for expression 'loop()': alias 'loop' (defined by branchless.revsets.alias.loop) expands to itself infinitely: loop -> loop");
+        insta::assert_snapshot!(stdout, @"");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_query_format() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.commit_file("test1", 1)?;
+    git.run(&["branch", "foo"])?;
+    git.commit_file("test2", 2)?;
+    git.branchless("test", &["run", "-x", "exit 0", ".^"])?;
+
+    {
+        let (stdout, stderr) = git.branchless(
+            "query",
+            &[
+                ".^::",
+                "--format",
+                "{short_oid} {{{branches}}} {summary} <{author_email}> {test_status}",
+            ],
+        )?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        62fc20d {foo} create test1.txt <test@example.com> passed
+        96d1c37 {master} create test2.txt <test@example.com>
+        "###);
+    }
+
+    {
+        let (stdout, stderr) = git.branchless("query", &[".^::", "--json"])?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        {"oid":"62fc20d2a290daea0d52bdc2ed2ad4be6491010e","short_oid":"62fc20d","summary":"create test1.txt","author_name":"Testy McTestface","author_email":"test@example.com","author_date":"2020-10-29T13:34:56+00:00","branches":["foo"],"parents":["f777ecc9b0db5ed372b2615695191a8a17f79f24"],"test_status":"passed"}
+        {"oid":"96d1c37a3d4363611c49f7e52186e189a04c531f","short_oid":"96d1c37","summary":"create test2.txt","author_name":"Testy McTestface","author_email":"test@example.com","author_date":"2020-10-29T14:34:56+00:00","branches":["master"],"parents":["62fc20d2a290daea0d52bdc2ed2ad4be6491010e"],"test_status":null}
+        "###);
+    }
+
+    {
+        let (stdout, stderr) = git.branchless_with_options(
+            "query",
+            &[".", "--format", "{oid} {message}"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @"unknown placeholder {message} in format template (expected one of: {oid}, {short_oid}, {summary}, {author_name}, {author_email}, {author_date}, {branches}, {parents}, {test_status})");
+        insta::assert_snapshot!(stdout, @"");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_query_explain() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.commit_file("test1", 1)?;
+    git.detach_head()?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+    git.run(&[
+        "config",
+        "branchless.revsets.alias.myDrafts",
+        "draft() & message($1)",
+    ])?;
+
+    {
+        let (stdout, stderr) = git.branchless("query", &["--explain", "myDrafts(test3) | .^"])?;
+        // Durations vary between runs, so omit them.
+        let stdout = stdout
+            .lines()
+            .map(|line| match line.rsplit_once(" in ") {
+                Some((line, _duration)) => line,
+                None => line,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        union(myDrafts(test3), parents.nth(., 1)): 2 commits
+          myDrafts(test3): 1 commit
+            intersection(draft(), message(test3)): 1 commit
+              draft(): 2 commits
+              message(test3): 1 commit
+          parents.nth(., 1): 1 commit
+            .: 1 commit
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_query_upstream() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _guard,
+        original_repo,
+        cloned_repo,
+    } = make_git_with_remote_repo()?;
+    if !original_repo.supports_reference_transactions()? {
+        return Ok(());
+    }
+
+    original_repo.init_repo()?;
+    original_repo.commit_file("test1", 1)?;
+    original_repo.commit_file("test2", 2)?;
+
+    original_repo.clone_repo_into(&cloned_repo, &["--branch", "master"])?;
+    cloned_repo.init_repo_with_options(&GitInitOptions {
+        make_initial_commit: false,
+        ..Default::default()
+    })?;
+    cloned_repo.run(&["branch", "no-upstream"])?;
+
+    original_repo.commit_file("test3", 3)?;
+    cloned_repo.run(&["fetch"])?;
+    cloned_repo.commit_file("test4", 4)?;
+
+    {
+        let (stdout, _stderr) = cloned_repo.branchless("query", &["remote_branches()"])?;
+        insta::assert_snapshot!(stdout, @"70deb1e create test3.txt");
+    }
+
+    {
+        let (stdout, _stderr) =
+            cloned_repo.branchless("query", &["remote_branches('glob:origin/m*')"])?;
+        insta::assert_snapshot!(stdout, @"70deb1e create test3.txt");
+    }
+
+    {
+        let (stdout, _stderr) = cloned_repo.branchless("query", &["upstream(branches())"])?;
+        insta::assert_snapshot!(stdout, @"70deb1e create test3.txt");
+    }
+
+    {
+        let (stdout, _stderr) = cloned_repo.branchless("query", &["ahead_of_upstream()"])?;
+        insta::assert_snapshot!(stdout, @"f57e36f create test4.txt");
+    }
+
+    {
+        let (stdout, _stderr) = cloned_repo.branchless("query", &["behind_upstream()"])?;
+        insta::assert_snapshot!(stdout, @"70deb1e create test3.txt");
+    }
+
+    // Commits which only exist on the remote should be kept when combined
+    // with other sets.
+    {
+        let (stdout, _stderr) =
+            cloned_repo.branchless("query", &["remote_branches() - branches()"])?;
+        insta::assert_snapshot!(stdout, @"70deb1e create test3.txt");
+    }
+
+    {
+        let (stdout, _stderr) =
+            cloned_repo.branchless("query", &["behind_upstream() | ahead_of_upstream()"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        f57e36f create test4.txt
+        70deb1e create test3.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_query_graph() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    let test1_oid = git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+    git.run(&["checkout", "-b", "release", &test1_oid.to_string()])?;
+    let test4_oid = git.commit_file("test4", 4)?;
+    git.commit_file("test5", 5)?;
+    let test6_oid = git.commit_file("test6", 6)?;
+
+    {
+        let (stdout, stderr) = git.branchless(
+            "query",
+            &["--graph", "ancestors(release) - ancestors(master)"],
+        )?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        :
+        o bf0d52a create test4.txt
+        |
+        o 848121c create test5.txt
+        |
+        @ f0abf64 (> release) create test6.txt
+        "###);
+    }
+
+    {
+        let (stdout, stderr) = git.branchless(
+            "query",
+            &[
+                "--graph",
+                &format!("roots(all()) + {test1_oid} + {test4_oid} + {test6_oid} + master"),
+            ],
+        )?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc create initial.txt
+        |
+        O 62fc20d create test1.txt
+        |\
+        | o bf0d52a create test4.txt
+        | :
+        | # 1 omitted commit
+        | :
+        | @ f0abf64 (> release) create test6.txt
+        :
+        # 1 omitted commit
+        :
+        O 70deb1e (master) create test3.txt
+        "###);
+    }
+
+    git.run(&["checkout", "master"])?;
+    git.run(&["merge", "--no-ff", "-m", "merge release", "release"])?;
+    {
+        let (stdout, stderr) =
+            git.branchless("query", &["--graph", &format!("{test1_oid} + master")])?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        :
+        O 62fc20d create test1.txt
+        |\
+        | # 2 omitted commits
+        | :
+        | | & (merge) 62fc20d create test1.txt
+        | | # 3 omitted commits
+        | |/
+        | @ 602c5ae (> master) merge release
+        & (merge) 602c5ae (> master) merge release
+        "###);
+    }
+
+    Ok(())
+}
@@ -29,6 +29,18 @@ impl Display for Expr<'_> {
 }
 
 impl<'input> Expr<'input> {
+    /// Convert this expression into one which doesn't borrow from the input
+    /// string.
+    pub fn into_owned(self) -> Expr<'static> {
+        match self {
+            Expr::Name(name) => Expr::Name(Cow::Owned(name.into_owned())),
+            Expr::FunctionCall(name, args) => Expr::FunctionCall(
+                Cow::Owned(name.into_owned()),
+                args.into_iter().map(Expr::into_owned).collect(),
+            ),
+        }
+    }
+
     /// Replace names in this expression with arbitrary expressions.
     ///
     /// Given a HashMap of names to Expr's, build a new Expr by crawling this
@@ -1,75 +1,114 @@
 use bstr::ByteSlice;
-use eden_dag::nameset::hints::Hints;
+use eden_dag::nameset::hints::{Flags, Hints};
 
+use lib::core::config::get_trailer_separators;
 use lib::core::dag::CommitSet;
-use lib::core::eventlog::{EventLogDb, EventReplayer};
+use lib::core::effects::Effects;
+use lib::core::eventlog::{Event, EventLogDb, EventReplayer};
 use lib::core::repo_ext::RepoExt;
 use lib::core::rewrite::find_rewrite_target;
 use lib::git::{
-    get_latest_test_command_path, get_test_tree_dir, CategorizedReferenceName, Commit,
-    MaybeZeroOid, Repo, SerializedNonZeroOid, SerializedTestResult, TEST_ABORT_EXIT_CODE,
-    TEST_INDETERMINATE_EXIT_CODE, TEST_SUCCESS_EXIT_CODE,
+    read_cached_test_results, read_latest_test_command, CategorizedReferenceName, Commit,
+    DiffStats, MaybeZeroOid, NonZeroOid, Repo, SerializedNonZeroOid, SerializedTestResult,
+    TEST_ABORT_EXIT_CODE, TEST_INDETERMINATE_EXIT_CODE, TEST_SUCCESS_EXIT_CODE,
 };
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use tracing::{instrument, warn};
 
 use eyre::Context as EyreContext;
 use lazy_static::lazy_static;
 
 use crate::eval::{
-    eval0, eval0_or_1, eval0_or_1_pattern, eval1, eval1_pattern, eval2, eval_number_rhs, Context,
-    EvalError, EvalResult,
+    eval0, eval0_or_1, eval0_or_1_pattern, eval1, eval1_pattern, eval2, eval_number_rhs,
+    make_arity_mismatch, Context, EvalError, EvalResult,
 };
-use crate::pattern::{make_pattern_matcher_set, Pattern};
+use crate::pattern::{make_pattern_matcher_set, parse_date, Pattern};
 use crate::pattern::{PatternError, PatternMatcher};
 use crate::Expr;
 
 type FnType = &'static (dyn Fn(&mut Context, &str, &[Expr]) -> EvalResult + Sync);
 lazy_static! {
-    pub(super) static ref FUNCTIONS: HashMap<&'static str, FnType> = {
-        let functions: &[(&'static str, FnType)] = &[
-            ("all", &fn_all),
-            ("none", &fn_none),
-            ("union", &fn_union),
-            ("intersection", &fn_intersection),
-            ("difference", &fn_difference),
-            ("only", &fn_only),
-            ("range", &fn_range),
-            ("not", &fn_not),
-            ("ancestors", &fn_ancestors),
-            ("ancestors.nth", &fn_nthancestor),
-            ("descendants", &fn_descendants),
-            ("parents", &fn_parents),
-            ("parents.nth", &fn_parents_nth),
-            ("children", &fn_children),
-            ("siblings", &fn_siblings),
-            ("roots", &fn_roots),
-            ("heads", &fn_heads),
-            ("branches", &fn_branches),
-            ("main", &fn_main),
-            ("public", &fn_public),
-            ("draft", &fn_draft),
-            ("stack", &fn_stack),
-            ("message", &fn_message),
-            ("paths.changed", &fn_path_changed),
-            ("author.name", &fn_author_name),
-            ("author.email", &fn_author_email),
-            ("author.date", &fn_author_date),
-            ("committer.name", &fn_committer_name),
-            ("committer.email", &fn_committer_email),
-            ("committer.date", &fn_committer_date),
-            ("exactly", &fn_exactly),
-            ("current", &fn_current),
-            ("merges", &fn_merges),
-            ("tests.passed", &fn_tests_passed),
-            ("tests.failed", &fn_tests_failed),
-            ("tests.fixable", &fn_tests_fixable),
+    /// The builtin functions, along with the numbers of arguments that each
+    /// accepts.
+    pub(super) static ref FUNCTIONS: HashMap<&'static str, (&'static [usize], FnType)> = {
+        let functions: &[(&'static str, &'static [usize], FnType)] = &[
+            ("all", &[0], &fn_all),
+            ("none", &[0], &fn_none),
+            ("union", &[2], &fn_union),
+            ("intersection", &[2], &fn_intersection),
+            ("difference", &[2], &fn_difference),
+            ("only", &[2], &fn_only),
+            ("range", &[2], &fn_range),
+            ("not", &[1], &fn_not),
+            ("ancestors", &[1], &fn_ancestors),
+            ("ancestors.nth", &[2], &fn_nthancestor),
+            ("descendants", &[1], &fn_descendants),
+            ("parents", &[1], &fn_parents),
+            ("parents.nth", &[2], &fn_parents_nth),
+            ("children", &[1], &fn_children),
+            ("siblings", &[1], &fn_siblings),
+            ("roots", &[1], &fn_roots),
+            ("heads", &[1], &fn_heads),
+            ("branches", &[0, 1], &fn_branches),
+            ("main", &[0], &fn_main),
+            ("public", &[0], &fn_public),
+            ("draft", &[0], &fn_draft),
+            ("stack", &[0, 1], &fn_stack),
+            ("message", &[1], &fn_message),
+            ("trailer", &[1, 2], &fn_trailer),
+            ("signed", &[0], &fn_signed),
+            ("paths.changed", &[1], &fn_path_changed),
+            ("lines_changed", &[1], &fn_lines_changed),
+            ("files_changed", &[1], &fn_files_changed),
+            ("touches_binary", &[0], &fn_touches_binary),
+            ("author.name", &[1], &fn_author_name),
+            ("author.email", &[1], &fn_author_email),
+            ("author.date", &[1], &fn_author_date),
+            ("committer.name", &[1], &fn_committer_name),
+            ("committer.email", &[1], &fn_committer_email),
+            ("committer.date", &[1], &fn_committer_date),
+            ("exactly", &[2], &fn_exactly),
+            ("current", &[1], &fn_current),
+            ("predecessors", &[1], &fn_predecessors),
+            ("successors", &[1], &fn_successors),
+            ("touched_since", &[1], &fn_touched_since),
+            ("merges", &[0], &fn_merges),
+            ("tests.passed", &[0, 1], &fn_tests_passed),
+            ("tests.failed", &[0, 1], &fn_tests_failed),
+            ("tests.fixable", &[0, 1], &fn_tests_fixable),
+            ("tests.flaky", &[0, 1], &fn_tests_flaky),
+            ("tests.slower_than", &[1, 2], &fn_tests_slower_than),
+            ("remote_branches", &[0, 1], &fn_remote_branches),
+            ("upstream", &[1], &fn_upstream),
+            ("ahead_of_upstream", &[0], &fn_ahead_of_upstream),
+            ("behind_upstream", &[0], &fn_behind_upstream),
         ];
-        functions.iter().cloned().collect()
+        functions
+            .iter()
+            .map(|(name, arities, function)| (*name, (*arities, *function)))
+            .collect()
     };
 }
 
+/// Builtin functions whose results aren't restricted to visible commits, since
+/// they're used to find earlier or later versions of rewritten commits, or
+/// commits which have only been fetched from a remote.
+pub(super) const UNFILTERED_FUNCTIONS: &[&str] = &[
+    "predecessors",
+    "successors",
+    "remote_branches",
+    "upstream",
+    "behind_upstream",
+];
+
+/// Builtin functions whose results only contain commits from the results of
+/// their arguments, so any commits from calls to [`UNFILTERED_FUNCTIONS`] in
+/// their arguments are kept in their results.
+pub(super) const SET_OPERATION_FUNCTIONS: &[&str] = &["union", "intersection", "difference"];
+
 #[instrument]
 fn fn_all(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     eval0(ctx, name, args)?;
@@ -100,7 +139,14 @@ fn fn_union(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
 #[instrument]
 fn fn_intersection(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     let (lhs, rhs) = eval2(ctx, name, args)?;
-    Ok(lhs.intersection(&rhs))
+    // The intersection enumerates its left-hand side and tests membership in
+    // its right-hand side, so put any lazily-evaluated filter (such as
+    // `message()`) on the right to only test it against the other set.
+    if lhs.hints().contains(Flags::FILTER) && !rhs.hints().contains(Flags::FILTER) {
+        Ok(rhs.intersection(&lhs))
+    } else {
+        Ok(lhs.intersection(&rhs))
+    }
 }
 
 #[instrument]
@@ -223,6 +269,111 @@ fn fn_branches(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     Ok(branch_commits)
 }
 
+#[instrument]
+fn fn_remote_branches(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let pattern = eval0_or_1_pattern(ctx, name, args)?;
+    let mut commit_oids = Vec::new();
+    for branch in ctx.repo.get_all_remote_branches()? {
+        let branch_name = branch.get_name().map_err(EvalError::OtherError)?;
+        // Skip symbolic references such as `origin/HEAD`, which don't point
+        // directly to a commit.
+        let branch_oid = match branch.get_oid()? {
+            Some(branch_oid) => branch_oid,
+            None => continue,
+        };
+        let is_match = match &pattern {
+            Some(pattern) => pattern.matches_text(branch_name),
+            None => true,
+        };
+        if is_match {
+            commit_oids.push(branch_oid);
+        }
+    }
+    sync_commits(ctx, commit_oids.into_iter().collect())
+}
+
+/// Add the given commits, which may not be reachable from any local
+/// reference, to the DAG.
+fn sync_commits(ctx: &mut Context, commits: CommitSet) -> EvalResult {
+    ctx.dag
+        .sync_from_oids(ctx.effects, ctx.repo, CommitSet::empty(), commits.clone())
+        .map_err(EvalError::OtherError)?;
+    Ok(commits)
+}
+
+/// Get the commit pointed to by each local branch which tracks an upstream
+/// branch, along with the commit pointed to by its upstream branch. The
+/// upstream commits are added to the DAG.
+fn get_upstream_commits(ctx: &mut Context) -> Result<Vec<(NonZeroOid, NonZeroOid)>, EvalError> {
+    let mut result = Vec::new();
+    for branch in ctx.repo.get_all_local_branches()? {
+        let branch_oid = match branch.get_oid()? {
+            Some(branch_oid) => branch_oid,
+            None => continue,
+        };
+        let upstream_oid = match branch
+            .get_upstream_branch_target()
+            .map_err(EvalError::OtherError)?
+        {
+            Some(upstream_oid) => upstream_oid,
+            None => continue,
+        };
+        result.push((branch_oid, upstream_oid));
+    }
+    sync_commits(
+        ctx,
+        result
+            .iter()
+            .map(|(_branch_oid, upstream_oid)| *upstream_oid)
+            .collect(),
+    )?;
+    Ok(result)
+}
+
+#[instrument]
+fn fn_upstream(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let commits = eval1(ctx, name, args)?;
+    let commits = commits.intersection(&ctx.dag.branch_commits);
+    let commit_oids: HashSet<NonZeroOid> = ctx
+        .dag
+        .commit_set_to_vec(&commits)
+        .map_err(EvalError::OtherError)?
+        .into_iter()
+        .collect();
+    let upstream_oids: CommitSet = get_upstream_commits(ctx)?
+        .into_iter()
+        .filter(|(branch_oid, _upstream_oid)| commit_oids.contains(branch_oid))
+        .map(|(_branch_oid, upstream_oid)| upstream_oid)
+        .collect();
+    Ok(upstream_oids)
+}
+
+#[instrument]
+fn fn_ahead_of_upstream(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    eval0(ctx, name, args)?;
+    let mut result = CommitSet::empty();
+    for (branch_oid, upstream_oid) in get_upstream_commits(ctx)? {
+        let commits = ctx
+            .dag
+            .query_only(CommitSet::from(branch_oid), CommitSet::from(upstream_oid))?;
+        result = result.union(&commits);
+    }
+    Ok(result)
+}
+
+#[instrument]
+fn fn_behind_upstream(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    eval0(ctx, name, args)?;
+    let mut result = CommitSet::empty();
+    for (branch_oid, upstream_oid) in get_upstream_commits(ctx)? {
+        let commits = ctx
+            .dag
+            .query_only(CommitSet::from(upstream_oid), CommitSet::from(branch_oid))?;
+        result = result.union(&commits);
+    }
+    Ok(result)
+}
+
 #[instrument]
 fn fn_parents_nth(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     let (lhs, n) = eval_number_rhs(ctx, name, args)?;
@@ -364,6 +515,52 @@ fn fn_message(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     )
 }
 
+#[instrument]
+fn fn_trailer(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let (key, pattern) = match args {
+        [Expr::Name(key)] => (key.to_string(), None),
+        [Expr::Name(key), pattern] => (
+            key.to_string(),
+            Some(eval1_pattern(ctx, name, std::slice::from_ref(pattern))?),
+        ),
+        [Expr::FunctionCall(function_name, _args)]
+        | [Expr::FunctionCall(function_name, _args), _] => {
+            return Err(EvalError::ExpectedStringNotFunction {
+                function_name: function_name.clone().into_owned(),
+            })
+        }
+        args => return Err(make_arity_mismatch(name, args)),
+    };
+    let separators = get_trailer_separators(ctx.repo).map_err(EvalError::OtherError)?;
+    make_pattern_matcher(
+        ctx,
+        name,
+        args,
+        Box::new(move |_repo, commit| {
+            let trailers = commit.get_trailers_with_separators(&separators);
+            let result = trailers.iter().any(|(trailer_key, trailer_value)| {
+                trailer_key.eq_ignore_ascii_case(&key)
+                    && match &pattern {
+                        Some(pattern) => pattern.matches_text(trailer_value),
+                        None => true,
+                    }
+            });
+            Ok(result)
+        }),
+    )
+}
+
+#[instrument]
+fn fn_signed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    eval0(ctx, name, args)?;
+    make_pattern_matcher(
+        ctx,
+        name,
+        args,
+        Box::new(|_repo, commit| Ok(commit.is_signed())),
+    )
+}
+
 #[instrument]
 fn fn_path_changed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     let pattern = eval1_pattern(ctx, name, args)?;
@@ -394,6 +591,132 @@ fn fn_path_changed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     )
 }
 
+/// A comparison against a number, such as `>500`.
+#[derive(Clone, Copy, Debug)]
+enum NumberComparison {
+    Less(usize),
+    LessOrEqual(usize),
+    Equal(usize),
+    GreaterOrEqual(usize),
+    Greater(usize),
+}
+
+impl NumberComparison {
+    fn parse(comparison: &str) -> Result<Self, EvalError> {
+        let comparison = comparison.trim();
+        let result = if let Some(n) = comparison.strip_prefix(">=") {
+            NumberComparison::GreaterOrEqual(n.trim().parse()?)
+        } else if let Some(n) = comparison.strip_prefix("<=") {
+            NumberComparison::LessOrEqual(n.trim().parse()?)
+        } else if let Some(n) = comparison.strip_prefix('>') {
+            NumberComparison::Greater(n.trim().parse()?)
+        } else if let Some(n) = comparison.strip_prefix('<') {
+            NumberComparison::Less(n.trim().parse()?)
+        } else if let Some(n) = comparison.strip_prefix('=') {
+            NumberComparison::Equal(n.trim().parse()?)
+        } else {
+            NumberComparison::Equal(comparison.parse()?)
+        };
+        Ok(result)
+    }
+
+    fn matches(&self, value: usize) -> bool {
+        match self {
+            NumberComparison::Less(n) => value < *n,
+            NumberComparison::LessOrEqual(n) => value <= *n,
+            NumberComparison::Equal(n) => value == *n,
+            NumberComparison::GreaterOrEqual(n) => value >= *n,
+            NumberComparison::Greater(n) => value > *n,
+        }
+    }
+}
+
+fn eval1_number_comparison(
+    function_name: &str,
+    args: &[Expr],
+) -> Result<NumberComparison, EvalError> {
+    match args {
+        [Expr::Name(comparison)] => NumberComparison::parse(comparison),
+
+        [Expr::FunctionCall(name, _args)] => Err(EvalError::ExpectedNumberNotFunction {
+            function_name: name.clone().into_owned(),
+        }),
+
+        args => Err(make_arity_mismatch(function_name, args)),
+    }
+}
+
+/// Diff statistics for commits which have already been examined while
+/// evaluating an expression, so that functions such as `lines_changed()` and
+/// `files_changed()` which appear in the same expression only compute the diff
+/// of each commit once.
+pub(super) type DiffStatsCache = Mutex<HashMap<NonZeroOid, DiffStats>>;
+
+fn get_diff_stats(
+    effects: &Effects,
+    cache: &DiffStatsCache,
+    repo: &Repo,
+    commit: &Commit,
+) -> Result<DiffStats, PatternError> {
+    let commit_oid = commit.get_oid();
+    if let Some(diff_stats) = cache.lock().unwrap().get(&commit_oid) {
+        return Ok(*diff_stats);
+    }
+    let diff_stats = repo
+        .get_diff_stats_for_commit(effects, commit)
+        .map_err(PatternError::ConstructMatcher)?;
+    cache.lock().unwrap().insert(commit_oid, diff_stats);
+    Ok(diff_stats)
+}
+
+#[instrument]
+fn fn_lines_changed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let comparison = eval1_number_comparison(name, args)?;
+    let effects = ctx.effects.clone();
+    let diff_stats_cache = Arc::clone(&ctx.diff_stats_cache);
+    make_pattern_matcher(
+        ctx,
+        name,
+        args,
+        Box::new(move |repo: &Repo, commit: &Commit| {
+            let diff_stats = get_diff_stats(&effects, &diff_stats_cache, repo, commit)?;
+            Ok(comparison.matches(diff_stats.lines_changed()))
+        }),
+    )
+}
+
+#[instrument]
+fn fn_files_changed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let comparison = eval1_number_comparison(name, args)?;
+    let effects = ctx.effects.clone();
+    let diff_stats_cache = Arc::clone(&ctx.diff_stats_cache);
+    make_pattern_matcher(
+        ctx,
+        name,
+        args,
+        Box::new(move |repo: &Repo, commit: &Commit| {
+            let diff_stats = get_diff_stats(&effects, &diff_stats_cache, repo, commit)?;
+            Ok(comparison.matches(diff_stats.files_changed))
+        }),
+    )
+}
+
+#[instrument]
+fn fn_touches_binary(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    eval0(ctx, name, args)?;
+    let effects = ctx.effects.clone();
+    let diff_stats_cache = Arc::clone(&ctx.diff_stats_cache);
+    make_pattern_matcher(
+        ctx,
+        name,
+        args,
+        Box::new(move |repo: &Repo, commit: &Commit| {
+            let diff_stats = get_diff_stats(&effects, &diff_stats_cache, repo, commit)?;
+            Ok(diff_stats.touches_binary)
+        }),
+    )
+}
+
 #[instrument]
 fn fn_author_name(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     let pattern = eval1_pattern(ctx, name, args)?;
@@ -502,29 +825,148 @@ fn fn_exactly(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     }
 }
 
+fn make_event_replayer(ctx: &Context) -> Result<EventReplayer, EvalError> {
+    let conn = ctx.repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)
+        .wrap_err("Connecting to event log")
+        .map_err(EvalError::OtherError)?;
+    let event_replayer = EventReplayer::from_event_log_db(ctx.effects, ctx.repo, &event_log_db)
+        .wrap_err("Retrieving event replayer")
+        .map_err(EvalError::OtherError)?;
+    Ok(event_replayer)
+}
+
+/// Get the pairs of old and new commit OIDs for every rewrite recorded in the
+/// event log, excluding commits which were deleted.
+fn get_rewritten_oids(event_replayer: &EventReplayer) -> Vec<(NonZeroOid, NonZeroOid)> {
+    event_replayer
+        .get_events_since_cursor(event_replayer.make_cursor(0))
+        .iter()
+        .filter_map(|event| match event {
+            Event::RewriteEvent {
+                timestamp: _,
+                event_tx_id: _,
+                old_commit_oid: MaybeZeroOid::NonZero(old_commit_oid),
+                new_commit_oid: MaybeZeroOid::NonZero(new_commit_oid),
+            } if old_commit_oid != new_commit_oid => Some((*old_commit_oid, *new_commit_oid)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Find all commits reachable from the given commits by following the
+/// provided edges, not including the given commits themselves (unless they
+/// are reachable from another one of them).
+fn follow_rewrites(
+    ctx: &mut Context,
+    commits: CommitSet,
+    edges: impl IntoIterator<Item = (NonZeroOid, NonZeroOid)>,
+) -> EvalResult {
+    let mut next_oids: HashMap<NonZeroOid, Vec<NonZeroOid>> = HashMap::new();
+    for (from_oid, to_oid) in edges {
+        next_oids.entry(from_oid).or_default().push(to_oid);
+    }
+
+    let mut result: HashSet<NonZeroOid> = HashSet::new();
+    let mut queue: Vec<NonZeroOid> = ctx
+        .dag
+        .commit_set_to_vec(&commits)
+        .map_err(EvalError::OtherError)?;
+    while let Some(oid) = queue.pop() {
+        for next_oid in next_oids.get(&oid).into_iter().flatten() {
+            if result.insert(*next_oid) {
+                queue.push(*next_oid);
+            }
+        }
+    }
+
+    // Commits which were garbage-collected or never observed by
+    // git-branchless may not be in the DAG.
+    let all_commits = ctx.dag.query_all().map_err(EvalError::OtherError)?;
+    Ok(result
+        .into_iter()
+        .collect::<CommitSet>()
+        .intersection(&all_commits))
+}
+
+#[instrument]
+fn fn_predecessors(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let expr = eval1(ctx, name, args)?;
+    let event_replayer = make_event_replayer(ctx)?;
+    let edges = get_rewritten_oids(&event_replayer)
+        .into_iter()
+        .map(|(old_oid, new_oid)| (new_oid, old_oid));
+    follow_rewrites(ctx, expr, edges)
+}
+
+#[instrument]
+fn fn_successors(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let expr = eval1(ctx, name, args)?;
+    let event_replayer = make_event_replayer(ctx)?;
+    let edges = get_rewritten_oids(&event_replayer);
+    follow_rewrites(ctx, expr, edges)
+}
+
+#[instrument]
+fn fn_touched_since(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let date = match args {
+        [Expr::Name(date)] => parse_date(date)?,
+        [Expr::FunctionCall(function_name, _args)] => {
+            return Err(EvalError::ExpectedStringNotFunction {
+                function_name: function_name.clone().into_owned(),
+            })
+        }
+        args => return Err(make_arity_mismatch(name, args)),
+    };
+    let since = SystemTime::from(date);
+
+    let event_replayer = make_event_replayer(ctx)?;
+    let commit_oids: CommitSet = event_replayer
+        .get_events_since_cursor(event_replayer.make_cursor(0))
+        .iter()
+        .filter(|event| event.get_timestamp() >= since)
+        .filter_map(|event| match event {
+            Event::CommitEvent {
+                timestamp: _,
+                event_tx_id: _,
+                commit_oid,
+            }
+            | Event::RewriteEvent {
+                timestamp: _,
+                event_tx_id: _,
+                old_commit_oid: _,
+                new_commit_oid: MaybeZeroOid::NonZero(commit_oid),
+            } => Some(*commit_oid),
+            _ => None,
+        })
+        .collect();
+    let all_commits = ctx.dag.query_all().map_err(EvalError::OtherError)?;
+    Ok(commit_oids.intersection(&all_commits))
+}
+
 #[instrument]
 fn fn_current(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     let mut dag = ctx
         .dag
         .clear_obsolete_commits(ctx.repo)
         .map_err(EvalError::OtherError)?;
-    let mut ctx = Context {
+    let mut inner_ctx = Context {
         effects: ctx.effects,
         repo: ctx.repo,
         dag: &mut dag,
+        matcher_cache: Arc::clone(&ctx.matcher_cache),
+        diff_stats_cache: Arc::clone(&ctx.diff_stats_cache),
+        unfiltered_commits: CommitSet::empty(),
+        explain_stack: ctx.explain_stack.take(),
     };
-    let expr = eval1(&mut ctx, name, args)?;
+    let expr = eval1(&mut inner_ctx, name, args);
+    ctx.explain_stack = inner_ctx.explain_stack.take();
+    let expr = expr?;
 
-    let conn = ctx.repo.get_db_conn()?;
-    let event_log_db = EventLogDb::new(&conn)
-        .wrap_err("Connecting to event log")
-        .map_err(EvalError::OtherError)?;
-    let event_replayer = EventReplayer::from_event_log_db(ctx.effects, ctx.repo, &event_log_db)
-        .wrap_err("Retrieving event replayer")
-        .map_err(EvalError::OtherError)?;
+    let event_replayer = make_event_replayer(&inner_ctx)?;
     let event_cursor = event_replayer.make_default_cursor();
 
-    let commit_oids = ctx
+    let commit_oids = inner_ctx
         .dag
         .commit_set_to_vec(&expr)
         .map_err(EvalError::OtherError)?;
@@ -560,27 +1002,6 @@ fn fn_merges(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
     )
 }
 
-fn read_all_test_results(repo: &Repo, commit: &Commit) -> Option<Vec<SerializedTestResult>> {
-    let commit_test_dir = get_test_tree_dir(repo, commit).ok()?;
-    let mut all_results = Vec::new();
-    for dir in std::fs::read_dir(commit_test_dir).ok()? {
-        let dir = dir.ok()?;
-        if dir.file_type().ok()?.is_dir() {
-            let result_path = dir.path().join("result");
-            let result_contents = std::fs::read_to_string(result_path).ok()?;
-            let result: SerializedTestResult = serde_json::from_str(&result_contents).ok()?;
-            all_results.push(result);
-        }
-    }
-    Some(all_results)
-}
-
-fn read_latest_test_command(repo: &Repo) -> Option<String> {
-    let latest_command_path = get_latest_test_command_path(repo).ok()?;
-    let latest_command = std::fs::read_to_string(latest_command_path).ok()?;
-    Some(latest_command)
-}
-
 fn eval_test_command_pattern(
     ctx: &mut Context,
     name: &str,
@@ -604,7 +1025,7 @@ fn fn_tests_passed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
         name,
         args,
         Box::new(move |repo: &Repo, commit: &Commit| {
-            let result = read_all_test_results(repo, commit)
+            let result = read_cached_test_results(repo, commit)
                 .unwrap_or_default()
                 .into_iter()
                 .any(|test_result| {
@@ -614,6 +1035,9 @@ fn fn_tests_passed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
                         head_commit_oid: _,
                         snapshot_tree_oid: _,
                         interactive: _,
+                        duration_secs: _,
+                        num_runs: _,
+                        flaky: _,
                     } = test_result;
                     exit_code == TEST_SUCCESS_EXIT_CODE
                         && pattern.matches_text(&command.to_string())
@@ -631,7 +1055,7 @@ fn fn_tests_failed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
         name,
         args,
         Box::new(move |repo: &Repo, commit: &Commit| {
-            let result = read_all_test_results(repo, commit)
+            let result = read_cached_test_results(repo, commit)
                 .unwrap_or_default()
                 .into_iter()
                 .any(|test_result| {
@@ -641,6 +1065,9 @@ fn fn_tests_failed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
                         head_commit_oid: _,
                         snapshot_tree_oid: _,
                         interactive: _,
+                        duration_secs: _,
+                        num_runs: _,
+                        flaky: _,
                     } = test_result;
                     exit_code != TEST_SUCCESS_EXIT_CODE
                         && exit_code != TEST_INDETERMINATE_EXIT_CODE
@@ -660,7 +1087,7 @@ fn fn_tests_fixable(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult
         name,
         args,
         Box::new(move |repo: &Repo, commit: &Commit| {
-            let result = read_all_test_results(repo, commit)
+            let result = read_cached_test_results(repo, commit)
                 .unwrap_or_default()
                 .into_iter()
                 .any(|test_result| {
@@ -670,6 +1097,9 @@ fn fn_tests_fixable(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult
                         head_commit_oid: _,
                         snapshot_tree_oid,
                         interactive: _,
+                        duration_secs: _,
+                        num_runs: _,
+                        flaky: _,
                     } = test_result;
                     exit_code == TEST_SUCCESS_EXIT_CODE
                         && pattern.matches_text(&command.to_string())
@@ -685,3 +1115,98 @@ fn fn_tests_fixable(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult
         }),
     )
 }
+
+#[instrument]
+fn fn_tests_flaky(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let pattern = eval_test_command_pattern(ctx, name, args)?;
+    make_pattern_matcher(
+        ctx,
+        name,
+        args,
+        Box::new(move |repo: &Repo, commit: &Commit| {
+            let result = read_cached_test_results(repo, commit)
+                .unwrap_or_default()
+                .into_iter()
+                .any(|test_result| {
+                    let SerializedTestResult {
+                        command,
+                        exit_code: _,
+                        head_commit_oid: _,
+                        snapshot_tree_oid: _,
+                        interactive: _,
+                        duration_secs: _,
+                        num_runs: _,
+                        flaky,
+                    } = test_result;
+                    flaky && pattern.matches_text(&command.to_string())
+                });
+            Ok(result)
+        }),
+    )
+}
+
+/// Parse a duration such as `30s`, `500ms`, `2m` or `1h`. A number without a
+/// unit is interpreted as seconds.
+fn parse_test_duration(duration: &str) -> Result<Duration, EvalError> {
+    let make_error = || EvalError::ParseDuration {
+        duration: duration.to_owned(),
+    };
+    let trimmed = duration.trim();
+    let unit_index = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (amount, unit) = trimmed.split_at(unit_index);
+    let amount: f64 = amount.parse().map_err(|_| make_error())?;
+    let seconds_per_unit = match unit.trim() {
+        "ms" => 0.001,
+        "" | "s" | "sec" | "secs" => 1.0,
+        "m" | "min" | "mins" => 60.0,
+        "h" | "hr" | "hrs" => 3600.0,
+        _ => return Err(make_error()),
+    };
+    Duration::try_from_secs_f64(amount * seconds_per_unit).map_err(|_| make_error())
+}
+
+#[instrument]
+fn fn_tests_slower_than(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let (threshold, pattern) = match args {
+        [Expr::Name(duration), pattern_args @ ..] if pattern_args.len() <= 1 => (
+            parse_test_duration(duration)?,
+            eval_test_command_pattern(ctx, name, pattern_args)?,
+        ),
+        [Expr::FunctionCall(function_name, _args), pattern_args @ ..]
+            if pattern_args.len() <= 1 =>
+        {
+            return Err(EvalError::ExpectedStringNotFunction {
+                function_name: function_name.clone().into_owned(),
+            })
+        }
+        args => return Err(make_arity_mismatch(name, args)),
+    };
+    make_pattern_matcher(
+        ctx,
+        name,
+        args,
+        Box::new(move |repo: &Repo, commit: &Commit| {
+            let result = read_cached_test_results(repo, commit)
+                .unwrap_or_default()
+                .into_iter()
+                .any(|test_result| {
+                    let SerializedTestResult {
+                        command,
+                        exit_code: _,
+                        head_commit_oid: _,
+                        snapshot_tree_oid: _,
+                        interactive: _,
+                        duration_secs,
+                        num_runs: _,
+                        flaky: _,
+                    } = test_result;
+                    duration_secs.map_or(false, |duration_secs| {
+                        duration_secs > threshold.as_secs_f64()
+                    }) && pattern.matches_text(&command.to_string())
+                });
+            Ok(result)
+        }),
+    )
+}
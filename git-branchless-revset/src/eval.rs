@@ -1,7 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::num::ParseIntError;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use eden_dag::errors::BackendError;
 use itertools::Itertools;
@@ -13,9 +14,9 @@ use lib::core::formatting::Pluralize;
 use lib::git::{ConfigRead, Repo, RepoError, ResolvedReferenceInfo};
 use tracing::instrument;
 
-use super::builtins::FUNCTIONS;
+use super::builtins::{DiffStatsCache, FUNCTIONS, SET_OPERATION_FUNCTIONS, UNFILTERED_FUNCTIONS};
 use super::parser::{parse, ParseError};
-use super::pattern::{Pattern, PatternError};
+use super::pattern::{MatcherCache, Pattern, PatternError, PATTERN_KINDS};
 use super::Expr;
 
 #[derive(Debug)]
@@ -23,6 +24,36 @@ pub(super) struct Context<'a> {
     pub effects: &'a Effects,
     pub repo: &'a Repo,
     pub dag: &'a mut Dag,
+    pub matcher_cache: Arc<MatcherCache>,
+    pub diff_stats_cache: Arc<DiffStatsCache>,
+
+    /// The commits returned by calls to [`UNFILTERED_FUNCTIONS`] in the
+    /// expression currently being evaluated, which may be kept in its result
+    /// even though they aren't visible.
+    pub unfiltered_commits: CommitSet,
+
+    /// If explaining the evaluation, the nodes evaluated so far at each level
+    /// of the expression currently being evaluated.
+    pub explain_stack: Option<Vec<Vec<ExplainNode>>>,
+}
+
+/// The result of evaluating one node of an expression, as reported by
+/// `git query --explain`.
+#[derive(Debug)]
+pub struct ExplainNode {
+    /// The expression, after the expansion of any aliases containing it.
+    pub expr: String,
+
+    /// The number of commits which the expression evaluated to.
+    pub num_commits: usize,
+
+    /// The time taken to evaluate the expression and count its commits,
+    /// including the time taken by its children.
+    pub duration: Duration,
+
+    /// The nodes evaluated as part of this one, such as function arguments or
+    /// alias expansions.
+    pub children: Vec<ExplainNode>,
 }
 
 #[derive(Debug, Error)]
@@ -63,8 +94,20 @@ pub enum EvalError {
         actual_len: usize,
     },
 
-    #[error("failed to parse alias expression '{alias}'\n{source}")]
-    ParseAlias { alias: String, source: ParseError },
+    #[error("failed to parse alias '{name}' (defined by {ALIAS_CONFIG_PREFIX}{name}): '{alias}'\n{source}")]
+    ParseAlias {
+        name: String,
+        alias: String,
+        source: ParseError,
+    },
+
+    #[error(
+        "alias '{}' (defined by {ALIAS_CONFIG_PREFIX}{}) expands to itself infinitely: {}",
+        cycle[0],
+        cycle[0],
+        cycle.join(" -> "),
+    )]
+    RecursiveAlias { cycle: Vec<String> },
 
     #[error("not an integer: {from}")]
     ParseInt {
@@ -72,12 +115,18 @@ pub enum EvalError {
         from: ParseIntError,
     },
 
+    #[error("not a duration: {duration} (expected a number followed by a unit, such as 30s, 500ms, 2m or 1h)")]
+    ParseDuration { duration: String },
+
     #[error("expected an integer, but got a call to function: {function_name}")]
     ExpectedNumberNotFunction { function_name: String },
 
     #[error("expected a text-matching pattern, but got a call to function: {function_name}")]
     ExpectedPatternNotFunction { function_name: String },
 
+    #[error("expected a string, but got a call to function: {function_name}")]
+    ExpectedStringNotFunction { function_name: String },
+
     #[error("there was no latest command run with `git test`; try running `git test` first")]
     NoLatestTestCommand,
 
@@ -115,21 +164,116 @@ pub fn eval(effects: &Effects, repo: &Repo, dag: &mut Dag, expr: &Expr) -> EvalR
         effects: &effects,
         repo,
         dag,
+        matcher_cache: Default::default(),
+        diff_stats_cache: Default::default(),
+        unfiltered_commits: CommitSet::empty(),
+        explain_stack: None,
     };
     let commits = eval_inner(&mut ctx, expr)?;
     Ok(commits)
 }
 
+/// Evaluate the provided revset expression, recording how many commits each
+/// node of the expression evaluated to and how long it took.
+///
+/// Counting the commits forces lazily-evaluated filters such as `message()` to
+/// be tested against every commit they could contain, so they may take longer
+/// than when evaluated as part of a larger expression without explaining it.
+#[instrument]
+pub fn eval_explain(
+    effects: &Effects,
+    repo: &Repo,
+    dag: &mut Dag,
+    expr: &Expr,
+) -> Result<ExplainNode, EvalError> {
+    let (effects, _progress) =
+        effects.start_operation(OperationType::EvaluateRevset(Arc::new(expr.to_string())));
+
+    let mut ctx = Context {
+        effects: &effects,
+        repo,
+        dag,
+        matcher_cache: Default::default(),
+        diff_stats_cache: Default::default(),
+        unfiltered_commits: CommitSet::empty(),
+        explain_stack: Some(vec![Vec::new()]),
+    };
+    eval_inner(&mut ctx, expr)?;
+    let root = ctx
+        .explain_stack
+        .and_then(|mut explain_stack| explain_stack.pop())
+        .and_then(|mut nodes| nodes.pop());
+    match root {
+        Some(root) => Ok(root),
+        None => Err(EvalError::OtherError(eyre::eyre!(
+            "BUG: no explanation recorded for expression: {expr}"
+        ))),
+    }
+}
+
 #[instrument]
 fn eval_inner(ctx: &mut Context, expr: &Expr) -> EvalResult {
+    match ctx.explain_stack.as_mut() {
+        None => eval_expr(ctx, expr),
+        Some(explain_stack) => {
+            explain_stack.push(Vec::new());
+            let start = Instant::now();
+            let commits = eval_expr(ctx, expr)?;
+            let num_commits = ctx.dag.set_count(&commits)?;
+            let duration = start.elapsed();
+            if let Some(explain_stack) = ctx.explain_stack.as_mut() {
+                let children = explain_stack.pop().unwrap_or_default();
+                if let Some(siblings) = explain_stack.last_mut() {
+                    siblings.push(ExplainNode {
+                        expr: expr.to_string(),
+                        num_commits,
+                        duration,
+                        children,
+                    });
+                }
+            }
+            Ok(commits)
+        }
+    }
+}
+
+fn eval_expr(ctx: &mut Context, expr: &Expr) -> EvalResult {
     match expr {
         Expr::Name(name) => eval_name(ctx, name),
         Expr::FunctionCall(name, args) => {
-            let result = eval_fn(ctx, name, args)?;
-            let result = ctx
-                .dag
-                .filter_visible_commits(result)
-                .map_err(EvalError::OtherError)?;
+            let outer_unfiltered_commits =
+                std::mem::replace(&mut ctx.unfiltered_commits, CommitSet::empty());
+            let result = eval_fn(ctx, name, args);
+            let inner_unfiltered_commits =
+                std::mem::replace(&mut ctx.unfiltered_commits, outer_unfiltered_commits);
+            let result = result?;
+
+            let name = name.as_ref();
+            let is_alias = !FUNCTIONS.contains_key(name);
+            let (result, unfiltered_commits) = if UNFILTERED_FUNCTIONS.contains(&name) {
+                (result.clone(), result)
+            } else if is_alias {
+                // The result of an alias is the result of its expansion, which
+                // has already been filtered as appropriate.
+                (result, inner_unfiltered_commits)
+            } else {
+                let visible_commits = ctx
+                    .dag
+                    .filter_visible_commits(result.clone())
+                    .map_err(EvalError::OtherError)?;
+                if SET_OPERATION_FUNCTIONS.contains(&name) {
+                    // Keep any commits from the arguments which weren't
+                    // filtered, such as in `predecessors(x) - x`.
+                    let unfiltered_commits = result.intersection(&inner_unfiltered_commits);
+                    (
+                        visible_commits.union(&unfiltered_commits),
+                        unfiltered_commits,
+                    )
+                } else {
+                    (visible_commits, CommitSet::empty())
+                }
+            };
+            ctx.unfiltered_commits = ctx.unfiltered_commits.union(&unfiltered_commits);
             Ok(result)
         }
     }
@@ -175,24 +319,102 @@ pub(super) fn eval_name(ctx: &mut Context, name: &str) -> EvalResult {
     Ok(commit_set)
 }
 
+/// The prefix of the config keys which define revset aliases.
+const ALIAS_CONFIG_PREFIX: &str = "branchless.revsets.alias.";
+
+/// Look up and parse the definition of the alias with the given name, if any.
+fn get_alias_expr(repo: &Repo, name: &str) -> Result<Option<Expr<'static>>, EvalError> {
+    let alias_template: Option<String> = repo
+        .get_readonly_config()
+        .map_err(EvalError::RepoError)?
+        .get(format!("{ALIAS_CONFIG_PREFIX}{name}"))
+        .map_err(EvalError::OtherError)?;
+    match alias_template {
+        Some(alias_template) => match parse(&alias_template) {
+            Ok(alias_expr) => Ok(Some(alias_expr.into_owned())),
+            Err(err) => Err(EvalError::ParseAlias {
+                name: name.to_owned(),
+                alias: alias_template,
+                source: err,
+            }),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Check whether the alias with the given name refers to itself, either
+/// directly or via other aliases. Since aliases can't be conditionally
+/// expanded, any such reference would cause the expansion to never terminate.
+fn check_alias_recursion(repo: &Repo, name: &str) -> Result<(), EvalError> {
+    fn visit(
+        repo: &Repo,
+        expr: &Expr,
+        path: &mut Vec<String>,
+        visited: &mut HashSet<String>,
+    ) -> Result<(), EvalError> {
+        let (function_name, args) = match expr {
+            Expr::Name(_) => return Ok(()),
+            Expr::FunctionCall(function_name, args) => (function_name, args),
+        };
+        for arg in args {
+            visit(repo, arg, path, visited)?;
+        }
+        if FUNCTIONS.contains_key(function_name.as_ref()) {
+            return Ok(());
+        }
+        if path.iter().any(|name| name == function_name.as_ref()) {
+            path.push(function_name.to_string());
+            return Err(EvalError::RecursiveAlias {
+                cycle: path.clone(),
+            });
+        }
+        if !visited.insert(function_name.to_string()) {
+            return Ok(());
+        }
+        if let Some(alias_expr) = get_alias_expr(repo, function_name)? {
+            path.push(function_name.to_string());
+            visit(repo, &alias_expr, path, visited)?;
+            path.pop();
+        }
+        Ok(())
+    }
+
+    if let Some(alias_expr) = get_alias_expr(repo, name)? {
+        visit(
+            repo,
+            &alias_expr,
+            &mut vec![name.to_owned()],
+            &mut HashSet::new(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Get the number of positional arguments (`$1`, `$2`, etc.) which are
+/// referred to by the given alias expression.
+fn get_alias_arity(expr: &Expr) -> usize {
+    match expr {
+        Expr::Name(name) => name
+            .strip_prefix('$')
+            .and_then(|n| n.parse().ok())
+            .unwrap_or_default(),
+        Expr::FunctionCall(_name, args) => {
+            args.iter().map(get_alias_arity).max().unwrap_or_default()
+        }
+    }
+}
+
 #[instrument]
 pub(super) fn eval_fn(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
-    if let Some(function) = FUNCTIONS.get(name) {
+    if let Some((arities, function)) = FUNCTIONS.get(name) {
+        if !arities.contains(&args.len()) {
+            return Err(make_arity_mismatch(name, args));
+        }
         return function(ctx, name, args);
     }
 
-    let alias_key = format!("branchless.revsets.alias.{name}");
-    let alias_template: Option<String> = ctx
-        .repo
-        .get_readonly_config()
-        .map_err(EvalError::RepoError)?
-        .get(alias_key)
-        .map_err(EvalError::OtherError)?;
-    if let Some(alias_template) = alias_template {
-        let alias_expr = parse(&alias_template).map_err(|err| EvalError::ParseAlias {
-            alias: alias_template.clone(),
-            source: err,
-        })?;
+    if let Some(alias_expr) = get_alias_expr(ctx.repo, name)? {
+        check_alias_recursion(ctx.repo, name)?;
         let arg_map: HashMap<String, Expr> = args
             .iter()
             .enumerate()
@@ -209,6 +431,92 @@ pub(super) fn eval_fn(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResul
     })
 }
 
+/// A function which can be called in a revset expression.
+#[derive(Debug)]
+pub enum FunctionInfo {
+    /// A function built into git-branchless.
+    Builtin {
+        /// The name of the function.
+        name: &'static str,
+
+        /// The numbers of arguments which the function accepts.
+        arities: &'static [usize],
+    },
+
+    /// A function defined by the user via `branchless.revsets.alias.*`.
+    Alias {
+        /// The name of the alias.
+        name: String,
+
+        /// The expression that the alias expands to.
+        alias: String,
+
+        /// The number of positional arguments (`$1`, `$2`, etc.) used by the
+        /// alias, or the reason that the alias is invalid.
+        arity: Result<usize, EvalError>,
+    },
+}
+
+/// List the builtin functions and user-defined aliases which can be called in
+/// revset expressions, sorted by name.
+#[instrument]
+pub fn list_functions(repo: &Repo) -> eyre::Result<Vec<FunctionInfo>> {
+    let mut result: Vec<FunctionInfo> = FUNCTIONS
+        .iter()
+        .map(|(name, (arities, _function))| FunctionInfo::Builtin { name, arities })
+        .collect();
+
+    let aliases = repo
+        .get_readonly_config()?
+        .list(format!("{ALIAS_CONFIG_PREFIX}*"))?;
+    for (key, alias) in aliases {
+        let name = match key.strip_prefix(ALIAS_CONFIG_PREFIX) {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        let arity = match get_alias_expr(repo, &name) {
+            Ok(Some(alias_expr)) => {
+                check_alias_recursion(repo, &name).map(|()| get_alias_arity(&alias_expr))
+            }
+            Ok(None) => continue,
+            Err(err) => Err(err),
+        };
+        result.push(FunctionInfo::Alias { name, alias, arity });
+    }
+
+    result.sort_by(|lhs, rhs| lhs.get_name().cmp(rhs.get_name()));
+    Ok(result)
+}
+
+impl FunctionInfo {
+    /// The name used to call the function.
+    pub fn get_name(&self) -> &str {
+        match self {
+            FunctionInfo::Builtin { name, arities: _ } => name,
+            FunctionInfo::Alias {
+                name,
+                alias: _,
+                arity: _,
+            } => name,
+        }
+    }
+}
+
+/// Report that the builtin function with the given name was called with the
+/// wrong number of arguments. The expected numbers of arguments are taken from
+/// [`FUNCTIONS`], so that they're only declared in one place.
+pub(super) fn make_arity_mismatch(function_name: &str, args: &[Expr]) -> EvalError {
+    let expected_arities = match FUNCTIONS.get(function_name) {
+        Some((arities, _function)) => arities.to_vec(),
+        None => Vec::new(),
+    };
+    EvalError::ArityMismatch {
+        function_name: function_name.to_owned(),
+        expected_arities,
+        actual_arity: args.len(),
+    }
+}
+
 #[instrument]
 pub(super) fn eval0(
     ctx: &mut Context,
@@ -218,11 +526,7 @@ pub(super) fn eval0(
     match args {
         [] => Ok(()),
 
-        args => Err(EvalError::ArityMismatch {
-            function_name: function_name.to_string(),
-            expected_arities: vec![0],
-            actual_arity: args.len(),
-        }),
+        args => Err(make_arity_mismatch(function_name, args)),
     }
 }
 
@@ -238,11 +542,7 @@ pub(super) fn eval0_or_1(
             let arg = eval_inner(ctx, expr)?;
             Ok(Some(arg))
         }
-        args => Err(EvalError::ArityMismatch {
-            function_name: function_name.to_string(),
-            expected_arities: vec![0, 1],
-            actual_arity: args.len(),
-        }),
+        args => Err(make_arity_mismatch(function_name, args)),
     }
 }
 
@@ -254,11 +554,7 @@ pub(super) fn eval1(ctx: &mut Context, function_name: &str, args: &[Expr]) -> Ev
             Ok(lhs)
         }
 
-        args => Err(EvalError::ArityMismatch {
-            function_name: function_name.to_string(),
-            expected_arities: vec![1],
-            actual_arity: args.len(),
-        }),
+        args => Err(make_arity_mismatch(function_name, args)),
     }
 }
 
@@ -271,11 +567,31 @@ pub(super) fn eval0_or_1_pattern(
     match args {
         [] => Ok(None),
         [_] => eval1_pattern(ctx, function_name, args).map(Some),
-        args => Err(EvalError::ArityMismatch {
-            function_name: function_name.to_string(),
-            expected_arities: vec![0, 1],
-            actual_arity: args.len(),
-        }),
+        args => Err(make_arity_mismatch(function_name, args)),
+    }
+}
+
+/// Recover the text of a pattern written without quoting its prefix, such as
+/// `regex:"^fix"` or `between:"last monday".."yesterday"`. Since `:` and `..`
+/// can't appear in unquoted names, these are parsed as calls to `range` and
+/// `only`.
+fn get_pattern_text(expr: &Expr) -> Option<String> {
+    let (name, args) = match expr {
+        Expr::Name(_) => return None,
+        Expr::FunctionCall(name, args) => (name, args),
+    };
+    match (name.as_ref(), args.as_slice()) {
+        ("range", [Expr::Name(kind), Expr::Name(text)])
+            if PATTERN_KINDS.contains(&kind.as_ref()) =>
+        {
+            Some(format!("{kind}:{text}"))
+        }
+        ("only", [Expr::Name(end), start @ Expr::FunctionCall(_, _)]) => {
+            let start = get_pattern_text(start)?;
+            let start = start.strip_prefix("between:")?;
+            Some(format!("between:{start}..{end}"))
+        }
+        _ => None,
     }
 }
 
@@ -288,15 +604,14 @@ pub(super) fn eval1_pattern(
     match args {
         [Expr::Name(pattern)] => Ok(Pattern::new(pattern)?),
 
-        [Expr::FunctionCall(name, _args)] => Err(EvalError::ExpectedPatternNotFunction {
-            function_name: name.clone().into_owned(),
-        }),
+        [expr @ Expr::FunctionCall(name, _args)] => match get_pattern_text(expr) {
+            Some(pattern) => Ok(Pattern::new(&pattern)?),
+            None => Err(EvalError::ExpectedPatternNotFunction {
+                function_name: name.clone().into_owned(),
+            }),
+        },
 
-        args => Err(EvalError::ArityMismatch {
-            function_name: function_name.to_string(),
-            expected_arities: vec![1],
-            actual_arity: args.len(),
-        }),
+        args => Err(make_arity_mismatch(function_name, args)),
     }
 }
 
@@ -313,11 +628,7 @@ pub(super) fn eval2(
             Ok((lhs, rhs))
         }
 
-        args => Err(EvalError::ArityMismatch {
-            function_name: function_name.to_string(),
-            expected_arities: vec![2],
-            actual_arity: args.len(),
-        }),
+        args => Err(make_arity_mismatch(function_name, args)),
     }
 }
 
@@ -338,11 +649,7 @@ pub(super) fn eval_number_rhs(
             function_name: name.clone().into_owned(),
         }),
 
-        args => Err(EvalError::ArityMismatch {
-            function_name: function_name.to_string(),
-            expected_arities: vec![2],
-            actual_arity: args.len(),
-        }),
+        args => Err(make_arity_mismatch(function_name, args)),
     }
 }
 
@@ -1099,6 +1406,82 @@ mod tests {
             "###);
         }
 
+        {
+            let expr = parse(r#"author.date(between:"2020-01-01"..today)"#)?;
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: f777ecc9b0db5ed372b2615695191a8a17f79f24,
+                            summary: "create initial.txt",
+                        },
+                    },
+                    Commit {
+                        inner: Commit {
+                            id: 9ee1994c0737c221efc07acd8d73590d336ee46d,
+                            summary: "test1",
+                        },
+                    },
+                    Commit {
+                        inner: Commit {
+                            id: 05ff2fc6b3e7917ac6800b18077c211e173e8fb4,
+                            summary: "test2",
+                        },
+                    },
+                ],
+            )
+            "###);
+        }
+
+        {
+            let expr = parse(r#"author.name(iregex:"^fo+$")"#)?;
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: 9ee1994c0737c221efc07acd8d73590d336ee46d,
+                            summary: "test1",
+                        },
+                    },
+                ],
+            )
+            "###);
+        }
+
+        {
+            let expr = parse("message(fuzzy:tst2)")?;
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: 05ff2fc6b3e7917ac6800b18077c211e173e8fb4,
+                            summary: "test2",
+                        },
+                    },
+                ],
+            )
+            "###);
+        }
+
+        {
+            let expr = parse("author.email(isubstr:BAR)")?;
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: 05ff2fc6b3e7917ac6800b18077c211e173e8fb4,
+                            summary: "test2",
+                        },
+                    },
+                ],
+            )
+            "###);
+        }
+
         Ok(())
     }
 
@@ -1184,16 +1567,21 @@ mod tests {
     }
 
     #[test]
-    fn test_eval_merges() -> eyre::Result<()> {
+    fn test_eval_rewrite_history() -> eyre::Result<()> {
         let git = make_git()?;
         git.init_repo()?;
 
         git.detach_head()?;
         let test1_oid = git.commit_file("test1", 1)?;
-        let test2_oid = git.commit_file("test2", 2)?;
-        git.run(&["checkout", "HEAD~2"])?;
-        git.run(&["merge", "--no-ff", &test1_oid.to_string()])?;
-        git.run(&["merge", "--no-ff", &test2_oid.to_string()])?;
+        let _test2_oid = git.commit_file("test2", 2)?;
+        let test3_oid = git.commit_file("test3", 3)?;
+        git.commit_file("test4", 4)?;
+
+        git.branchless(
+            "move",
+            &["-s", &test3_oid.to_string(), "-d", &test1_oid.to_string()],
+        )?;
+        git.branchless("reword", &["-m", "test4 has been rewritten twice"])?;
 
         let effects = Effects::new_suppress_for_test(Glyphs::text());
         let repo = git.get_repo()?;
@@ -1211,20 +1599,72 @@ mod tests {
         )?;
 
         {
-            let expr = Expr::FunctionCall(Cow::Borrowed("merges"), vec![]);
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("predecessors"),
+                vec![Expr::Name(Cow::Borrowed("HEAD"))],
+            );
+            // The predecessors aren't ancestors of each other, so sort them
+            // by OID to get a stable order.
+            let mut predecessor_oids: Vec<String> =
+                eval_and_sort(&effects, &repo, &mut dag, &expr)?
+                    .iter()
+                    .map(|commit| commit.get_oid().to_string())
+                    .collect();
+            predecessor_oids.sort();
+            insta::assert_debug_snapshot!(predecessor_oids, @r###"
+            [
+                "355e173bf9c5d2efac2e451da0cdad3fb82b869a",
+                "a248207402822b7396cabe0f1011d8a7ce7daf1b",
+            ]
+            "###);
+
+            // Hidden predecessors should be kept when combined with other
+            // sets, or when returned from an alias.
+            git.run(&[
+                "config",
+                "branchless.revsets.alias.previousVersions",
+                "predecessors($1)",
+            ])?;
+            for expr in [
+                "predecessors(HEAD) | none()",
+                "(predecessors(HEAD) | HEAD) - HEAD",
+                "previousVersions(HEAD)",
+            ] {
+                let expr = parse(expr)?;
+                let mut oids: Vec<String> = eval_and_sort(&effects, &repo, &mut dag, &expr)?
+                    .iter()
+                    .map(|commit| commit.get_oid().to_string())
+                    .collect();
+                oids.sort();
+                assert_eq!(oids, predecessor_oids, "{expr}");
+            }
+
+            let expr = format!("predecessors(HEAD) - {}", predecessor_oids[0]);
+            let expr = parse(&expr)?;
+            let oids: Vec<String> = eval_and_sort(&effects, &repo, &mut dag, &expr)?
+                .iter()
+                .map(|commit| commit.get_oid().to_string())
+                .collect();
+            assert_eq!(oids, predecessor_oids[1..]);
+
+            let expr = parse("predecessors(HEAD) & draft()")?;
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [],
+            )
+            "###);
+
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("successors"),
+                vec![Expr::Name(Cow::Owned(test3_oid.to_string()))],
+            );
             insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
             Ok(
                 [
                     Commit {
                         inner: Commit {
-                            id: f486a8b756cd3a928241576aa87827284f3e14d1,
-                            summary: "Merge commit '62fc20d2a290daea0d52bdc2ed2ad4be6491010e' into HEAD",
-                        },
-                    },
-                    Commit {
-                        inner: Commit {
-                            id: 0b75bdca271fdc188e68bca6e054013bbc2a373c,
-                            summary: "Merge commit '96d1c37a3d4363611c49f7e52186e189a04c531f' into HEAD",
+                            id: 4838e49b08954becdd17c0900c1179c2c654c627,
+                            summary: "create test3.txt",
                         },
                     },
                 ],
@@ -1232,47 +1672,52 @@ mod tests {
             "###);
 
             let expr = Expr::FunctionCall(
-                Cow::Borrowed("not"),
-                vec![Expr::FunctionCall(Cow::Borrowed("merges"), vec![])],
+                Cow::Borrowed("touched_since"),
+                vec![Expr::Name(Cow::Borrowed("1 hour ago"))],
             );
             insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
             Ok(
                 [
                     Commit {
                         inner: Commit {
-                            id: f777ecc9b0db5ed372b2615695191a8a17f79f24,
-                            summary: "create initial.txt",
-                        },
-                    },
-                    Commit {
-                        inner: Commit {
-                            id: 62fc20d2a290daea0d52bdc2ed2ad4be6491010e,
-                            summary: "create test1.txt",
+                            id: 4838e49b08954becdd17c0900c1179c2c654c627,
+                            summary: "create test3.txt",
                         },
                     },
                     Commit {
                         inner: Commit {
-                            id: 96d1c37a3d4363611c49f7e52186e189a04c531f,
-                            summary: "create test2.txt",
+                            id: 619162078182d2c6d80ff604b81e7c2afc3295b7,
+                            summary: "test4 has been rewritten twice",
                         },
                     },
                 ],
             )
             "###);
+
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("touched_since"),
+                vec![Expr::Name(Cow::Borrowed("tomorrow"))],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [],
+            )
+            "###);
         }
+
         Ok(())
     }
 
     #[test]
-    fn test_eval_branches_with_pattern() -> eyre::Result<()> {
+    fn test_eval_lazy_filters() -> eyre::Result<()> {
         let git = make_git()?;
         git.init_repo()?;
 
-        git.detach_head()?;
         git.commit_file("test1", 1)?;
-        git.run(&["branch", "test-1"])?;
         git.commit_file("test2", 2)?;
-        git.run(&["branch", "test-2"])?;
+        git.detach_head()?;
+        git.commit_file("test3", 3)?;
+        git.commit_file("test4", 4)?;
 
         let effects = Effects::new_suppress_for_test(Glyphs::text());
         let repo = git.get_repo()?;
@@ -1289,15 +1734,497 @@ mod tests {
             &references_snapshot,
         )?;
 
+        let message_expr = Expr::FunctionCall(
+            Cow::Borrowed("message"),
+            vec![Expr::Name(Cow::Borrowed("create"))],
+        );
+        let draft_expr = Expr::FunctionCall(Cow::Borrowed("draft"), vec![]);
+
         {
-            let expr = Expr::FunctionCall(Cow::Borrowed("branches"), vec![]);
-            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
-            Ok(
-                [
-                    Commit {
-                        inner: Commit {
-                            id: f777ecc9b0db5ed372b2615695191a8a17f79f24,
-                            summary: "create initial.txt",
+            let mut ctx = Context {
+                effects: &effects,
+                repo: &repo,
+                dag: &mut dag,
+                matcher_cache: Default::default(),
+                diff_stats_cache: Default::default(),
+                unfiltered_commits: CommitSet::empty(),
+                explain_stack: None,
+            };
+            let get_num_commits_matched = |ctx: &Context| -> usize {
+                ctx.matcher_cache
+                    .lock()
+                    .unwrap()
+                    .values()
+                    .map(|results| results.len())
+                    .sum()
+            };
+
+            // The filter should only be tested against the draft commits,
+            // regardless of which side of the intersection it's on.
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("intersection"),
+                vec![message_expr.clone(), draft_expr.clone()],
+            );
+            let commits = eval_inner(&mut ctx, &expr)?;
+            insta::assert_debug_snapshot!(ctx.dag.set_count(&commits)?, @"2");
+            insta::assert_debug_snapshot!(get_num_commits_matched(&ctx), @"2");
+
+            // The results of the filter should be reused.
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("intersection"),
+                vec![draft_expr.clone(), message_expr.clone()],
+            );
+            let commits = eval_inner(&mut ctx, &expr)?;
+            insta::assert_debug_snapshot!(ctx.dag.set_count(&commits)?, @"2");
+            insta::assert_debug_snapshot!(get_num_commits_matched(&ctx), @"2");
+        }
+
+        {
+            fn summarize(node: &ExplainNode, depth: usize, out: &mut Vec<String>) {
+                out.push(format!(
+                    "{}{}: {}",
+                    "  ".repeat(depth),
+                    node.expr,
+                    node.num_commits
+                ));
+                for child in node.children.iter() {
+                    summarize(child, depth + 1, out);
+                }
+            }
+
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("difference"),
+                vec![message_expr.clone(), draft_expr.clone()],
+            );
+            let explanation = eval_explain(&effects, &repo, &mut dag, &expr)?;
+            let mut summary = Vec::new();
+            summarize(&explanation, 0, &mut summary);
+            insta::assert_snapshot!(summary.join("\n"), @r###"
+            difference(message(create), draft()): 3
+              message(create): 5
+              draft(): 2
+            "###);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_merges() -> eyre::Result<()> {
+        let git = make_git()?;
+        git.init_repo()?;
+
+        git.detach_head()?;
+        let test1_oid = git.commit_file("test1", 1)?;
+        let test2_oid = git.commit_file("test2", 2)?;
+        git.run(&["checkout", "HEAD~2"])?;
+        git.run(&["merge", "--no-ff", &test1_oid.to_string()])?;
+        git.run(&["merge", "--no-ff", &test2_oid.to_string()])?;
+
+        let effects = Effects::new_suppress_for_test(Glyphs::text());
+        let repo = git.get_repo()?;
+        let conn = repo.get_db_conn()?;
+        let event_log_db = EventLogDb::new(&conn)?;
+        let event_replayer = EventReplayer::from_event_log_db(&effects, &repo, &event_log_db)?;
+        let event_cursor = event_replayer.make_default_cursor();
+        let references_snapshot = repo.get_references_snapshot()?;
+        let mut dag = Dag::open_and_sync(
+            &effects,
+            &repo,
+            &event_replayer,
+            event_cursor,
+            &references_snapshot,
+        )?;
+
+        {
+            let expr = Expr::FunctionCall(Cow::Borrowed("merges"), vec![]);
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: f486a8b756cd3a928241576aa87827284f3e14d1,
+                            summary: "Merge commit '62fc20d2a290daea0d52bdc2ed2ad4be6491010e' into HEAD",
+                        },
+                    },
+                    Commit {
+                        inner: Commit {
+                            id: 0b75bdca271fdc188e68bca6e054013bbc2a373c,
+                            summary: "Merge commit '96d1c37a3d4363611c49f7e52186e189a04c531f' into HEAD",
+                        },
+                    },
+                ],
+            )
+            "###);
+
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("not"),
+                vec![Expr::FunctionCall(Cow::Borrowed("merges"), vec![])],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: f777ecc9b0db5ed372b2615695191a8a17f79f24,
+                            summary: "create initial.txt",
+                        },
+                    },
+                    Commit {
+                        inner: Commit {
+                            id: 62fc20d2a290daea0d52bdc2ed2ad4be6491010e,
+                            summary: "create test1.txt",
+                        },
+                    },
+                    Commit {
+                        inner: Commit {
+                            id: 96d1c37a3d4363611c49f7e52186e189a04c531f,
+                            summary: "create test2.txt",
+                        },
+                    },
+                ],
+            )
+            "###);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_diff_stats() -> eyre::Result<()> {
+        let git = make_git()?;
+        git.init_repo()?;
+
+        git.detach_head()?;
+        git.commit_file("test1", 1)?;
+        git.commit_file_with_contents("test2", 2, "line 1\nline 2\nline 3\n")?;
+        git.write_file_txt("test3", "test3 contents\n")?;
+        git.write_file("test4.bin", "binary\0contents")?;
+        git.run(&["add", "."])?;
+        git.run(&["commit", "-m", "create test3.txt and test4.bin"])?;
+
+        let effects = Effects::new_suppress_for_test(Glyphs::text());
+        let repo = git.get_repo()?;
+        let conn = repo.get_db_conn()?;
+        let event_log_db = EventLogDb::new(&conn)?;
+        let event_replayer = EventReplayer::from_event_log_db(&effects, &repo, &event_log_db)?;
+        let event_cursor = event_replayer.make_default_cursor();
+        let references_snapshot = repo.get_references_snapshot()?;
+        let mut dag = Dag::open_and_sync(
+            &effects,
+            &repo,
+            &event_replayer,
+            event_cursor,
+            &references_snapshot,
+        )?;
+
+        {
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("lines_changed"),
+                vec![Expr::Name(Cow::Borrowed(">1"))],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: aed48ff5a877b0dc77138ac368e39b2b64768f25,
+                            summary: "create test2.txt",
+                        },
+                    },
+                ],
+            )
+            "###);
+
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("files_changed"),
+                vec![Expr::Name(Cow::Borrowed(">=2"))],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: c8c2b62d5d6aacede83e0b8c6e95ebb91d3a6cf3,
+                            summary: "create test3.txt and test4.bin",
+                        },
+                    },
+                ],
+            )
+            "###);
+
+            let expr = Expr::FunctionCall(Cow::Borrowed("touches_binary"), vec![]);
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: c8c2b62d5d6aacede83e0b8c6e95ebb91d3a6cf3,
+                            summary: "create test3.txt and test4.bin",
+                        },
+                    },
+                ],
+            )
+            "###);
+
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("lines_changed"),
+                vec![Expr::Name(Cow::Borrowed(">many"))],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Err(
+                ParseInt {
+                    from: ParseIntError {
+                        kind: InvalidDigit,
+                    },
+                },
+            )
+            "###);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_trailers_and_signed() -> eyre::Result<()> {
+        let git = make_git()?;
+        git.init_repo()?;
+
+        git.detach_head()?;
+        git.commit_file("test1", 1)?;
+        git.write_file_txt("test2", "test2 contents\n")?;
+        git.run(&["add", "test2.txt"])?;
+        git.run(&[
+            "commit",
+            "-m",
+            "create test2.txt",
+            "-m",
+            "Bug: 123\nReviewed-by: Jane Doe <jane@example.com>",
+        ])?;
+        git.write_file_txt("test3", "test3 contents\n")?;
+        git.run(&["add", "test3.txt"])?;
+        git.run(&["commit", "-m", "create test3.txt", "-m", "Bug #456"])?;
+
+        // Create a commit with a signature header. The signature doesn't have
+        // to be valid, since it's not verified.
+        let (commit_contents, _stderr) = git.run(&["cat-file", "commit", "HEAD"])?;
+        let commit_contents = commit_contents.replacen(
+            "\n\n",
+            "\ngpgsig -----BEGIN PGP SIGNATURE-----\n \n -----END PGP SIGNATURE-----\n\n",
+            1,
+        );
+        let (signed_oid, _stderr) = git.run_with_options(
+            &["hash-object", "-t", "commit", "-w", "--stdin"],
+            &GitRunOptions {
+                input: Some(commit_contents),
+                ..Default::default()
+            },
+        )?;
+        git.run(&["checkout", signed_oid.trim()])?;
+
+        let effects = Effects::new_suppress_for_test(Glyphs::text());
+        let repo = git.get_repo()?;
+        let conn = repo.get_db_conn()?;
+        let event_log_db = EventLogDb::new(&conn)?;
+        let event_replayer = EventReplayer::from_event_log_db(&effects, &repo, &event_log_db)?;
+        let event_cursor = event_replayer.make_default_cursor();
+        let references_snapshot = repo.get_references_snapshot()?;
+        let mut dag = Dag::open_and_sync(
+            &effects,
+            &repo,
+            &event_replayer,
+            event_cursor,
+            &references_snapshot,
+        )?;
+
+        {
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("trailer"),
+                vec![Expr::Name(Cow::Borrowed("Bug"))],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: 48d7bb1c23ef78d6206fbf8f31d69a516ac965d2,
+                            summary: "create test2.txt",
+                        },
+                    },
+                ],
+            )
+            "###);
+
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("trailer"),
+                vec![
+                    Expr::Name(Cow::Borrowed("reviewed-by")),
+                    Expr::Name(Cow::Borrowed("glob:Jane *")),
+                ],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: 48d7bb1c23ef78d6206fbf8f31d69a516ac965d2,
+                            summary: "create test2.txt",
+                        },
+                    },
+                ],
+            )
+            "###);
+
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("trailer"),
+                vec![
+                    Expr::Name(Cow::Borrowed("Bug")),
+                    Expr::Name(Cow::Borrowed("456")),
+                ],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [],
+            )
+            "###);
+
+            git.run(&["config", "trailer.separators", ":#"])?;
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("trailer"),
+                vec![
+                    Expr::Name(Cow::Borrowed("Bug")),
+                    Expr::Name(Cow::Borrowed("456")),
+                ],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: a2a0e94018dba31e530e51eff060c32b2923db77,
+                            summary: "create test3.txt",
+                        },
+                    },
+                    Commit {
+                        inner: Commit {
+                            id: d5fd5218687fe00fed2097bcfd8bbd34bc72885f,
+                            summary: "create test3.txt",
+                        },
+                    },
+                ],
+            )
+            "###);
+
+            let expr = Expr::FunctionCall(Cow::Borrowed("signed"), vec![]);
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: a2a0e94018dba31e530e51eff060c32b2923db77,
+                            summary: "create test3.txt",
+                        },
+                    },
+                ],
+            )
+            "###);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_trailer_non_utf8_message() -> eyre::Result<()> {
+        let git = make_git()?;
+        git.init_repo()?;
+
+        git.detach_head()?;
+        git.write_file_txt("test1", "test1 contents\n")?;
+        git.run(&["add", "test1.txt"])?;
+        let message_path = git.repo_path.join(".git").join("COMMIT_MESSAGE");
+        std::fs::write(&message_path, b"create test1.txt \xe9\n\nBug: 123\n")?;
+        git.run(&[
+            "commit",
+            "-F",
+            message_path
+                .to_str()
+                .expect("Converting message path to string"),
+        ])?;
+
+        let effects = Effects::new_suppress_for_test(Glyphs::text());
+        let repo = git.get_repo()?;
+        let conn = repo.get_db_conn()?;
+        let event_log_db = EventLogDb::new(&conn)?;
+        let event_replayer = EventReplayer::from_event_log_db(&effects, &repo, &event_log_db)?;
+        let event_cursor = event_replayer.make_default_cursor();
+        let references_snapshot = repo.get_references_snapshot()?;
+        let mut dag = Dag::open_and_sync(
+            &effects,
+            &repo,
+            &event_replayer,
+            event_cursor,
+            &references_snapshot,
+        )?;
+
+        {
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("trailer"),
+                vec![
+                    Expr::Name(Cow::Borrowed("Bug")),
+                    Expr::Name(Cow::Borrowed("123")),
+                ],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: 69d2634abdd9d47248644a1d6fdc52a29cfcef0d,
+                            summary: "create test1.txt é",
+                        },
+                    },
+                ],
+            )
+            "###);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_branches_with_pattern() -> eyre::Result<()> {
+        let git = make_git()?;
+        git.init_repo()?;
+
+        git.detach_head()?;
+        git.commit_file("test1", 1)?;
+        git.run(&["branch", "test-1"])?;
+        git.commit_file("test2", 2)?;
+        git.run(&["branch", "test-2"])?;
+
+        let effects = Effects::new_suppress_for_test(Glyphs::text());
+        let repo = git.get_repo()?;
+        let conn = repo.get_db_conn()?;
+        let event_log_db = EventLogDb::new(&conn)?;
+        let event_replayer = EventReplayer::from_event_log_db(&effects, &repo, &event_log_db)?;
+        let event_cursor = event_replayer.make_default_cursor();
+        let references_snapshot = repo.get_references_snapshot()?;
+        let mut dag = Dag::open_and_sync(
+            &effects,
+            &repo,
+            &event_replayer,
+            event_cursor,
+            &references_snapshot,
+        )?;
+
+        {
+            let expr = Expr::FunctionCall(Cow::Borrowed("branches"), vec![]);
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: f777ecc9b0db5ed372b2615695191a8a17f79f24,
+                            summary: "create initial.txt",
                         },
                     },
                     Commit {
@@ -1342,6 +2269,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_eval_builtin_arities() -> eyre::Result<()> {
+        let git = make_git()?;
+        git.init_repo()?;
+
+        let effects = Effects::new_suppress_for_test(Glyphs::text());
+        let repo = git.get_repo()?;
+        let conn = repo.get_db_conn()?;
+        let event_log_db = EventLogDb::new(&conn)?;
+        let event_replayer = EventReplayer::from_event_log_db(&effects, &repo, &event_log_db)?;
+        let event_cursor = event_replayer.make_default_cursor();
+        let references_snapshot = repo.get_references_snapshot()?;
+        let mut dag = Dag::open_and_sync(
+            &effects,
+            &repo,
+            &event_replayer,
+            event_cursor,
+            &references_snapshot,
+        )?;
+
+        // Every builtin should accept exactly the numbers of arguments listed
+        // in `FUNCTIONS`. Calls with valid arities may still fail for other
+        // reasons, such as the arguments not being numbers.
+        for (name, (arities, _function)) in FUNCTIONS.iter().sorted_by_key(|(name, _)| **name) {
+            for arity in 0..=3 {
+                let args = vec![Expr::Name(Cow::Borrowed("HEAD")); arity];
+                let expr = Expr::FunctionCall(Cow::Borrowed(name), args);
+                let result = eval(&effects, &repo, &mut dag, &expr);
+                match result {
+                    Err(EvalError::ArityMismatch {
+                        function_name,
+                        expected_arities,
+                        actual_arity,
+                    }) => {
+                        assert!(
+                            !arities.contains(&arity),
+                            "{name} should accept {arity} arguments"
+                        );
+                        assert_eq!(function_name, *name);
+                        assert_eq!(expected_arities, arities.to_vec());
+                        assert_eq!(actual_arity, arity);
+                    }
+                    _ => assert!(
+                        arities.contains(&arity),
+                        "{name} should not accept {arity} arguments"
+                    ),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_eval_aliases() -> eyre::Result<()> {
         let git = make_git()?;
@@ -1429,7 +2409,7 @@ mod tests {
                 },
             )?;
             insta::assert_snapshot!(_stderr, @r###"
-            Evaluation error for expression 'parseError()': failed to parse alias expression 'foo('
+            Evaluation error for expression 'parseError()': failed to parse alias 'parseError' (defined by branchless.revsets.alias.parseError): 'foo('
             parse error: Unrecognized EOF found at 4
             Expected one of "(", ")", "..", ":", "::", a commit/branch/tag or a string literal
             "###);
@@ -1474,6 +2454,57 @@ mod tests {
             "###);
         }
 
+        {
+            git.run(&[
+                "config",
+                "branchless.revsets.alias.recursiveAlias",
+                "parents(mutualAlias($1))",
+            ])?;
+            git.run(&[
+                "config",
+                "branchless.revsets.alias.mutualAlias",
+                "recursiveAlias($1) | stack()",
+            ])?;
+
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("recursiveAlias"),
+                vec![Expr::FunctionCall(Cow::Borrowed("stack"), vec![])],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Err(
+                RecursiveAlias {
+                    cycle: [
+                        "recursiveAlias",
+                        "mutualAlias",
+                        "recursiveAlias",
+                    ],
+                },
+            )
+            "###);
+
+            // Passing an alias call as an argument to the same alias is not
+            // recursive.
+            let expr = Expr::FunctionCall(
+                Cow::Borrowed("simpleAlias"),
+                vec![Expr::FunctionCall(
+                    Cow::Borrowed("simpleAlias"),
+                    vec![Expr::FunctionCall(Cow::Borrowed("stack"), vec![])],
+                )],
+            );
+            insta::assert_debug_snapshot!(eval_and_sort(&effects, &repo, &mut dag, &expr), @r###"
+            Ok(
+                [
+                    Commit {
+                        inner: Commit {
+                            id: 62fc20d2a290daea0d52bdc2ed2ad4be6491010e,
+                            summary: "create test1.txt",
+                        },
+                    },
+                ],
+            )
+            "###);
+        }
+
         Ok(())
     }
 }
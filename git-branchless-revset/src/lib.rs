@@ -18,9 +18,11 @@ mod pattern;
 mod resolve;
 
 pub use ast::Expr;
-pub use eval::eval;
+pub use eval::{eval, list_functions, ExplainNode, FunctionInfo};
 pub use parser::parse;
-pub use resolve::{check_revset_syntax, resolve_commits, resolve_default_smartlog_commits};
+pub use resolve::{
+    check_revset_syntax, explain_revset, resolve_commits, resolve_default_smartlog_commits,
+};
 
 use lalrpop_util::lalrpop_mod;
 lalrpop_mod!(
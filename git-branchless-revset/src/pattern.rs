@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
 
@@ -11,7 +12,7 @@ use lib::core::effects::{Effects, OperationType};
 use lib::core::rewrite::RepoResource;
 use lib::git::{Commit, NonZeroOid, Repo, RepoError, Time};
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 use thiserror::Error;
 
 use crate::eval::make_dag_backend_error;
@@ -20,12 +21,36 @@ use crate::eval::{Context, EvalError};
 pub(super) enum Pattern {
     Exact(String),
     Substring(String),
+    /// Stored in lowercase.
+    CaseInsensitiveSubstring(String),
     Glob(glob::Pattern),
+    CaseInsensitiveGlob(glob::Pattern),
     Regex(regex::Regex),
+    /// Whitespace-separated terms, stored in lowercase, each of whose
+    /// characters must appear in order in the subject.
+    Fuzzy(Vec<String>),
     Before(DateTime<Local>),
     After(DateTime<Local>),
+    Between(DateTime<Local>, DateTime<Local>),
 }
 
+/// The prefixes which select the kind of a pattern, such as `glob:`.
+pub(super) const PATTERN_KINDS: &[&str] = &[
+    "exact",
+    "substring",
+    "substr",
+    "isubstring",
+    "isubstr",
+    "glob",
+    "iglob",
+    "regex",
+    "iregex",
+    "fuzzy",
+    "before",
+    "after",
+    "between",
+];
+
 #[derive(Debug, Error)]
 pub enum PatternError {
     #[error("failed to compile glob: {0}")]
@@ -53,25 +78,44 @@ impl Pattern {
         match self {
             Pattern::Exact(pattern) => pattern == subject,
             Pattern::Substring(pattern) => subject.contains(pattern),
+            Pattern::CaseInsensitiveSubstring(pattern) => subject.to_lowercase().contains(pattern),
             Pattern::Glob(pattern) => pattern.matches(subject),
+            Pattern::CaseInsensitiveGlob(pattern) => pattern.matches_with(
+                subject,
+                glob::MatchOptions {
+                    case_sensitive: false,
+                    ..Default::default()
+                },
+            ),
             Pattern::Regex(pattern) => pattern.is_match(subject),
-            Pattern::Before(_) | Pattern::After(_) => false,
+            Pattern::Fuzzy(terms) => {
+                let subject = subject.to_lowercase();
+                terms.iter().all(|term| {
+                    let mut subject_chars = subject.chars();
+                    term.chars()
+                        .all(|c| subject_chars.any(|subject_char| subject_char == c))
+                })
+            }
+            Pattern::Before(_) | Pattern::After(_) | Pattern::Between(_, _) => false,
         }
     }
 
     pub fn matches_date(&self, time: &Time) -> bool {
+        let time = match time.to_date_time() {
+            Some(time) => time,
+            None => return false,
+        };
         match self {
-            Pattern::Exact(_) | Pattern::Substring(_) | Pattern::Glob(_) | Pattern::Regex(_) => {
-                false
-            }
-            Pattern::Before(date) => match time.to_date_time() {
-                Some(time) => &time <= date,
-                None => false,
-            },
-            Pattern::After(date) => match time.to_date_time() {
-                Some(time) => &time >= date,
-                None => false,
-            },
+            Pattern::Exact(_)
+            | Pattern::Substring(_)
+            | Pattern::CaseInsensitiveSubstring(_)
+            | Pattern::Glob(_)
+            | Pattern::CaseInsensitiveGlob(_)
+            | Pattern::Regex(_)
+            | Pattern::Fuzzy(_) => false,
+            Pattern::Before(date) => &time <= date,
+            Pattern::After(date) => &time >= date,
+            Pattern::Between(start, end) => start <= &time && &time <= end,
         }
     }
 
@@ -85,31 +129,34 @@ impl Pattern {
         if let Some(pattern) = pattern.strip_prefix("substr:") {
             return Ok(Pattern::Substring(pattern.to_owned()));
         }
+        if let Some(pattern) = pattern
+            .strip_prefix("isubstring:")
+            .or_else(|| pattern.strip_prefix("isubstr:"))
+        {
+            return Ok(Pattern::CaseInsensitiveSubstring(pattern.to_lowercase()));
+        }
         if let Some(pattern) = pattern.strip_prefix("glob:") {
             let pattern = glob::Pattern::new(pattern)?;
             return Ok(Pattern::Glob(pattern));
         }
+        if let Some(pattern) = pattern.strip_prefix("iglob:") {
+            let pattern = glob::Pattern::new(pattern)?;
+            return Ok(Pattern::CaseInsensitiveGlob(pattern));
+        }
         if let Some(pattern) = pattern.strip_prefix("regex:") {
             let pattern = Regex::new(pattern)?;
             return Ok(Pattern::Regex(pattern));
         }
-
-        fn parse_date(pattern: &str) -> Result<DateTime<Local>, PatternError> {
-            if let Ok(date) = parse_date_string(pattern, Local::now(), Dialect::Us) {
-                return Ok(date.with_timezone(&Local));
-            }
-            if let Ok(interval) = parse_duration(pattern) {
-                let delta = match interval {
-                    Interval::Seconds(seconds) => RelativeDuration::seconds(seconds.into()),
-                    Interval::Days(days) => RelativeDuration::days(days.into()),
-                    Interval::Months(months) => RelativeDuration::months(months),
-                };
-                let date = Local::now() + delta;
-                return Ok(date);
-            }
-            Err(PatternError::ConstructMatcher(eyre::eyre!(
-                "cannot parse date: {pattern}"
-            )))
+        if let Some(pattern) = pattern.strip_prefix("iregex:") {
+            let pattern = RegexBuilder::new(pattern).case_insensitive(true).build()?;
+            return Ok(Pattern::Regex(pattern));
+        }
+        if let Some(pattern) = pattern.strip_prefix("fuzzy:") {
+            let terms = pattern
+                .split_whitespace()
+                .map(|term| term.to_lowercase())
+                .collect();
+            return Ok(Pattern::Fuzzy(terms));
         }
 
         if let Some(pattern) = pattern.strip_prefix("before:") {
@@ -120,16 +167,65 @@ impl Pattern {
             let date = parse_date(pattern)?;
             return Ok(Pattern::After(date));
         }
+        if let Some(pattern) = pattern.strip_prefix("between:") {
+            let (start, end) = pattern.split_once("..").ok_or_else(|| {
+                PatternError::ConstructMatcher(eyre::eyre!(
+                    "expected a date range of the form <start>..<end>: {pattern}"
+                ))
+            })?;
+            let unquote = |date: &str| -> String {
+                let date = date.trim();
+                for quote in ['"', '\''] {
+                    if let Some(date) = date
+                        .strip_prefix(quote)
+                        .and_then(|date| date.strip_suffix(quote))
+                    {
+                        return date.to_owned();
+                    }
+                }
+                date.to_owned()
+            };
+            let start = parse_date(&unquote(start))?;
+            let end = parse_date(&unquote(end))?;
+            return Ok(Pattern::Between(start, end));
+        }
 
         Ok(Pattern::Substring(pattern.to_owned()))
     }
 }
 
+/// Parse a date, either absolute or relative to now, such as `2 days ago`.
+pub(super) fn parse_date(pattern: &str) -> Result<DateTime<Local>, PatternError> {
+    if let Ok(date) = parse_date_string(pattern, Local::now(), Dialect::Us) {
+        return Ok(date.with_timezone(&Local));
+    }
+    if let Ok(interval) = parse_duration(pattern) {
+        let delta = match interval {
+            Interval::Seconds(seconds) => RelativeDuration::seconds(seconds.into()),
+            Interval::Days(days) => RelativeDuration::days(days.into()),
+            Interval::Months(months) => RelativeDuration::months(months),
+        };
+        let date = Local::now() + delta;
+        return Ok(date);
+    }
+    Err(PatternError::ConstructMatcher(eyre::eyre!(
+        "cannot parse date: {pattern}"
+    )))
+}
+
 pub(super) trait PatternMatcher: Sync + Send {
     fn get_description(&self) -> &str;
     fn matches_commit(&self, repo: &Repo, commit: &Commit) -> Result<bool, PatternError>;
 }
 
+/// The result of each pattern matcher for each commit that it has been tested
+/// against, keyed by the matcher's description and then by commit. This is
+/// shared by all the matchers created while evaluating an expression, so that
+/// a function call which appears several times (such as via aliases), or which
+/// is both enumerated and tested for membership, only examines each commit
+/// once.
+pub(super) type MatcherCache = Mutex<HashMap<String, HashMap<NonZeroOid, bool>>>;
+
 pub(super) fn make_pattern_matcher_set(
     ctx: &mut Context,
     repo: &Repo,
@@ -139,11 +235,44 @@ pub(super) fn make_pattern_matcher_set(
     struct MatcherNameSetQuery {
         effects: Effects,
         matcher: Box<dyn PatternMatcher>,
+        cache: Arc<MatcherCache>,
         repo: Arc<Mutex<Repo>>,
         commits_to_match: CommitSet,
     }
 
     impl MatcherNameSetQuery {
+        fn matches_commit_oid(
+            &self,
+            repo: &Repo,
+            commit_oid: NonZeroOid,
+        ) -> eden_dag::Result<bool> {
+            let description = self.matcher.get_description();
+            let cached_result = self
+                .cache
+                .lock()
+                .unwrap()
+                .get(description)
+                .and_then(|results| results.get(&commit_oid).copied());
+            if let Some(result) = cached_result {
+                return Ok(result);
+            }
+
+            let commit = repo
+                .find_commit_or_fail(commit_oid)
+                .map_err(make_dag_backend_error)?;
+            let result = self
+                .matcher
+                .matches_commit(repo, &commit)
+                .map_err(make_dag_backend_error)?;
+            self.cache
+                .lock()
+                .unwrap()
+                .entry(description.to_owned())
+                .or_default()
+                .insert(commit_oid, result);
+            Ok(result)
+        }
+
         async fn evaluate(&self) -> eden_dag::Result<CommitSet> {
             let (effects, progress) =
                 self.effects
@@ -168,14 +297,7 @@ pub(super) fn make_pattern_matcher_set(
                         let commit_oid =
                             NonZeroOid::try_from(commit_oid).map_err(make_dag_backend_error)?;
                         let repo = repo_pool.try_create().map_err(make_dag_backend_error)?;
-                        let commit = repo
-                            .find_commit_or_fail(commit_oid)
-                            .map_err(make_dag_backend_error)?;
-                        if self
-                            .matcher
-                            .matches_commit(&repo, &commit)
-                            .map_err(make_dag_backend_error)?
-                        {
+                        if self.matches_commit_oid(&repo, commit_oid)? {
                             acc.push(commit_oid);
                         }
                         progress.notify_progress_inc(1);
@@ -197,14 +319,7 @@ pub(super) fn make_pattern_matcher_set(
 
             let oid = NonZeroOid::try_from(name.clone()).map_err(make_dag_backend_error)?;
             let repo = self.repo.lock().unwrap();
-            let commit = repo
-                .find_commit_or_fail(oid)
-                .map_err(make_dag_backend_error)?;
-            let result = self
-                .matcher
-                .matches_commit(&repo, &commit)
-                .map_err(make_dag_backend_error)?;
-            Ok(result)
+            self.matches_commit_oid(&repo, oid)
         }
     }
 
@@ -222,6 +337,7 @@ pub(super) fn make_pattern_matcher_set(
     let matcher = Arc::new(MatcherNameSetQuery {
         effects: ctx.effects.clone(),
         matcher,
+        cache: Arc::clone(&ctx.matcher_cache),
         repo: Arc::new(Mutex::new(repo)),
         commits_to_match,
     });
@@ -266,6 +382,24 @@ mod tests {
         assert!(Pattern::new("regex:.*b.*r.*")?.matches_text("foo bar baz"));
         assert!(!Pattern::new("regex:^b.*r$")?.matches_text("foo bar baz"));
         assert!(Pattern::new("regex:[").is_err());
+        assert!(!Pattern::new("regex:BAR")?.matches_text("foo bar baz"));
+        assert!(Pattern::new("iregex:^FOO B.r")?.matches_text("foo bar baz"));
+
+        assert!(!Pattern::new("substr:BAR")?.matches_text("foo bar baz"));
+        assert!(Pattern::new("isubstr:BAR")?.matches_text("foo bar baz"));
+        assert!(Pattern::new("isubstring:Bar Baz")?.matches_text("foo bar baz"));
+
+        assert!(!Pattern::new("glob:*B?R*")?.matches_text("foo bar baz"));
+        assert!(Pattern::new("iglob:*B?R*")?.matches_text("foo bar baz"));
+
+        assert!(Pattern::new("fuzzy:fbb")?.matches_text("foo bar baz"));
+        assert!(Pattern::new("fuzzy:FoBa z")?.matches_text("foo bar baz"));
+        assert!(!Pattern::new("fuzzy:bf")?.matches_text("foo bar baz"));
+        assert!(!Pattern::new("fuzzy:foo qux")?.matches_text("foo bar baz"));
+
+        assert!(Pattern::new("between:2020-01-01..today").is_ok());
+        assert!(Pattern::new("between:\"last monday\"..'yesterday'").is_ok());
+        assert!(Pattern::new("between:yesterday").is_err());
 
         Ok(())
     }
@@ -1,4 +1,5 @@
 use std::fmt::Write;
+use std::time::Instant;
 
 use eyre::WrapErr;
 use git_branchless_opts::{ResolveRevsetOptions, Revset};
@@ -9,7 +10,7 @@ use lib::git::Repo;
 use thiserror::Error;
 use tracing::instrument;
 
-use crate::eval::EvalError;
+use crate::eval::{eval_explain, EvalError, ExplainNode};
 use crate::parser::ParseError;
 use crate::Expr;
 use crate::{eval, parse};
@@ -116,6 +117,48 @@ pub fn resolve_commits(
     Ok(commit_sets)
 }
 
+/// Evaluate the provided revset as `resolve_commits` would, but report how
+/// many commits each part of it evaluated to and how long each part took,
+/// rather than the resulting commits.
+#[instrument]
+pub fn explain_revset(
+    effects: &Effects,
+    repo: &Repo,
+    dag: &mut Dag,
+    revset: &Revset,
+    options: &ResolveRevsetOptions,
+) -> Result<ExplainNode, ResolveError> {
+    let mut dag_with_obsolete = if options.show_hidden_commits {
+        Some(
+            dag.clear_obsolete_commits(repo)
+                .map_err(|err| ResolveError::OtherError { source: err })?,
+        )
+    } else {
+        None
+    };
+    let dag = dag_with_obsolete.as_mut().unwrap_or(dag);
+
+    let Revset(revset) = revset;
+    let start = Instant::now();
+    if let Ok(Some(_)) = repo.revparse_single_commit(revset) {
+        return Ok(ExplainNode {
+            expr: revset.clone(),
+            num_commits: 1,
+            duration: start.elapsed(),
+            children: Vec::new(),
+        });
+    }
+
+    let expr = parse(revset).map_err(|err| ResolveError::ParseError {
+        expr: revset.clone(),
+        source: err,
+    })?;
+    eval_explain(effects, repo, dag, &expr).map_err(|err| ResolveError::EvalError {
+        expr: revset.clone(),
+        source: err,
+    })
+}
+
 /// Resolve the set of commits that would appear in the smartlog by default (if
 /// the user doesn't specify a revset).
 pub fn resolve_default_smartlog_commits(
@@ -34,11 +34,11 @@ use lib::core::formatting::Pluralize;
 use lib::core::node_descriptors::{
     BranchesDescriptor, CommitMessageDescriptor, CommitOidDescriptor,
     DifferentialRevisionDescriptor, ObsolescenceExplanationDescriptor, Redactor,
-    RelativeTimeDescriptor,
+    RelativeTimeDescriptor, ReviewStatusDescriptor,
 };
 use lib::git::{GitRunInfo, Repo};
 
-pub use graph::{make_smartlog_graph, SmartlogGraph};
+pub use graph::{make_exact_graph, make_smartlog_graph, SmartlogGraph};
 pub use render::{render_graph, SmartlogOptions};
 
 use git_branchless_revset::resolve_commits;
@@ -86,6 +86,11 @@ mod graph {
         /// the smartlog commit graph.
         pub ancestor_info: Option<AncestorInfo>,
 
+        /// Information about any further non-immediate ancestor nodes, such as
+        /// those reached through a merge parent. Each is rendered like a merge
+        /// parent, with its own count of omitted commits.
+        pub merge_ancestor_infos: Vec<AncestorInfo>,
+
         /// The OIDs of any non-immediate descendant nodes in the smartlog commit graph.
         pub descendants: Vec<ChildInfo>,
 
@@ -191,6 +196,7 @@ mod graph {
                             parents: Vec::new(),  // populated below
                             children: Vec::new(), // populated below
                             ancestor_info: None,
+                            merge_ancestor_infos: Vec::new(),
                             descendants: Vec::new(), // populated below
                             is_main: dag.is_public_commit(oid)?,
                             is_obsolete: dag.set_contains(&dag.query_obsolete_commits(), oid)?,
@@ -328,12 +334,14 @@ mod graph {
             })
             .collect();
         for node in graph.nodes.values_mut() {
-            node.children.sort_by_key(
-                |ChildInfo {
-                     oid,
-                     is_merge_child,
-                 }| (&commit_times[oid], *is_merge_child, oid.to_string()),
-            );
+            for children in [&mut node.children, &mut node.descendants] {
+                children.sort_by_key(
+                    |ChildInfo {
+                         oid,
+                         is_merge_child,
+                     }| (&commit_times[oid], *is_merge_child, oid.to_string()),
+                );
+            }
         }
     }
 
@@ -371,6 +379,102 @@ mod graph {
         sort_children(&mut graph);
         Ok(graph)
     }
+
+    /// Construct a graph containing exactly the provided commits, including
+    /// public ones, without adding any context such as HEAD, the main branch
+    /// or merge bases. Commits which are linked only through omitted commits
+    /// are connected with an elision marker.
+    #[instrument]
+    pub fn make_exact_graph<'repo>(
+        effects: &Effects,
+        repo: &'repo Repo,
+        dag: &Dag,
+        commits: &CommitSet,
+    ) -> eyre::Result<SmartlogGraph<'repo>> {
+        let (effects, _progress) = effects.start_operation(OperationType::MakeGraph);
+        let _effects = effects;
+
+        let mut nodes: HashMap<NonZeroOid, Node> = HashMap::new();
+        let commit_oids = dag.commit_set_to_vec(commits)?;
+        for oid in commit_oids.iter().copied() {
+            let object = match repo.find_commit(oid)? {
+                Some(commit) => NodeObject::Commit { commit },
+                None => NodeObject::GarbageCollected { oid },
+            };
+            nodes.insert(
+                oid,
+                Node {
+                    object,
+                    parents: Vec::new(),              // populated below
+                    children: Vec::new(),             // populated below
+                    ancestor_info: None,              // populated below
+                    merge_ancestor_infos: Vec::new(), // populated below
+                    descendants: Vec::new(),          // populated below
+                    is_main: dag.is_public_commit(oid)?,
+                    is_obsolete: dag.set_contains(&dag.query_obsolete_commits(), oid)?,
+                    num_omitted_descendants: 0,
+                },
+            );
+        }
+
+        for child_oid in commit_oids {
+            let parent_vertices = dag.query_parent_names(CommitVertex::from(child_oid))?;
+            for (parent_idx, parent_vertex) in parent_vertices.into_iter().enumerate() {
+                if dag.set_contains(commits, parent_vertex.clone())? {
+                    let parent_oid = NonZeroOid::try_from(parent_vertex)?;
+                    nodes.get_mut(&child_oid).unwrap().parents.push(parent_oid);
+                    nodes
+                        .get_mut(&parent_oid)
+                        .unwrap()
+                        .children
+                        .push(ChildInfo {
+                            oid: child_oid,
+                            is_merge_child: parent_idx > 0,
+                        });
+                    continue;
+                }
+
+                // The parent isn't in the graph, so link to the nearest
+                // ancestors which are, eliding the commits in between.
+                let parent_ancestors = dag.query_ancestors(CommitSet::from(parent_vertex))?;
+                let nearest_ancestors = dag.query_heads(parent_ancestors.intersection(commits))?;
+                for ancestor_oid in dag.commit_set_to_vec(&nearest_ancestors)? {
+                    // Only count the commits on the path from the ancestor to
+                    // this parent, and not those on other branches merged into
+                    // the child.
+                    let omitted_commits = parent_ancestors
+                        .difference(&dag.query_ancestors(CommitSet::from(ancestor_oid))?)
+                        .intersection(&dag.query_descendants(CommitSet::from(ancestor_oid))?);
+                    let ancestor_info = AncestorInfo {
+                        oid: ancestor_oid,
+                        distance: dag.set_count(&omitted_commits)?,
+                    };
+
+                    // The first parent's first elided ancestor is drawn as the
+                    // child's own line, and any others as merge parents.
+                    let child_node = nodes.get_mut(&child_oid).unwrap();
+                    let is_merge_link = parent_idx > 0 || child_node.ancestor_info.is_some();
+                    if is_merge_link {
+                        child_node.merge_ancestor_infos.push(ancestor_info);
+                    } else {
+                        child_node.ancestor_info = Some(ancestor_info);
+                    }
+                    nodes
+                        .get_mut(&ancestor_oid)
+                        .unwrap()
+                        .descendants
+                        .push(ChildInfo {
+                            oid: child_oid,
+                            is_merge_child: is_merge_link,
+                        });
+                }
+            }
+        }
+
+        let mut graph = SmartlogGraph { nodes };
+        sort_children(&mut graph);
+        Ok(graph)
+    }
 }
 
 mod render {
@@ -482,31 +586,56 @@ mod render {
             lines.push(StyledString::plain(glyphs.vertical_ellipsis));
         };
 
-        if let [_, merge_parents @ ..] = current_node.parents.as_slice() {
-            if !merge_parents.is_empty() {
-                for merge_parent_oid in merge_parents {
-                    let merge_parent_node = &graph.nodes[merge_parent_oid];
-                    lines.push(
-                        StyledStringBuilder::new()
-                            .append_plain(last_child_line_char.unwrap_or(glyphs.line))
-                            .append_plain(" ")
-                            .append_styled(
-                                format!("{} (merge) ", glyphs.commit_merge),
-                                BaseColor::Blue.dark(),
-                            )
-                            .append(render_node_descriptors(
-                                glyphs,
-                                &merge_parent_node.object,
-                                commit_descriptors,
-                            )?)
-                            .build(),
-                    );
-                }
-                lines.push(StyledString::plain(format!(
-                    "{}{}",
-                    glyphs.line_with_offshoot, glyphs.merge,
-                )));
+        let merge_parents = match current_node.parents.as_slice() {
+            [_, merge_parents @ ..] => merge_parents,
+            [] => &[],
+        };
+        if !merge_parents.is_empty() || !current_node.merge_ancestor_infos.is_empty() {
+            let merge_line_char = last_child_line_char.unwrap_or(glyphs.line);
+            let render_merge_parent = |oid: &NonZeroOid,
+                                       commit_descriptors: &mut [&mut dyn NodeDescriptor]|
+             -> eyre::Result<StyledString> {
+                Ok(StyledStringBuilder::new()
+                    .append_plain(merge_line_char)
+                    .append_plain(" ")
+                    .append_styled(
+                        format!("{} (merge) ", glyphs.commit_merge),
+                        BaseColor::Blue.dark(),
+                    )
+                    .append(render_node_descriptors(
+                        glyphs,
+                        &graph.nodes[oid].object,
+                        commit_descriptors,
+                    )?)
+                    .build())
+            };
+            for merge_parent_oid in merge_parents {
+                lines.push(render_merge_parent(merge_parent_oid, commit_descriptors)?);
             }
+            for AncestorInfo { oid, distance } in current_node.merge_ancestor_infos.iter() {
+                lines.push(render_merge_parent(oid, commit_descriptors)?);
+                lines.push(
+                    StyledStringBuilder::new()
+                        .append_plain(merge_line_char)
+                        .append_plain(" ")
+                        .append_plain(glyphs.commit_omitted)
+                        .append_plain(" ")
+                        .append_styled(
+                            Pluralize {
+                                determiner: None,
+                                amount: *distance,
+                                unit: ("omitted commit", "omitted commits"),
+                            }
+                            .to_string(),
+                            Effect::Dim,
+                        )
+                        .build(),
+                );
+            }
+            lines.push(StyledString::plain(format!(
+                "{}{}",
+                glyphs.line_with_offshoot, glyphs.merge,
+            )));
         }
 
         lines.push({
@@ -563,15 +692,13 @@ mod render {
             )
             .cloned()
             .collect();
-        let descendants: HashSet<ChildInfo> = current_node
+        let mut seen_descendants: HashSet<&ChildInfo> = HashSet::new();
+        let descendants: Vec<ChildInfo> = current_node
             .descendants
             .iter()
-            .filter(
-                |ChildInfo {
-                     oid,
-                     is_merge_child: _,
-                 }| graph.nodes.contains_key(oid),
-            )
+            .filter(|child_info| {
+                graph.nodes.contains_key(&child_info.oid) && seen_descendants.insert(child_info)
+            })
             .cloned()
             .collect();
         for (child_idx, child_info) in children.iter().chain(descendants.iter()).enumerate() {
@@ -845,6 +972,7 @@ pub fn smartlog(
                 &Redactor::Disabled,
             )?,
             &mut DifferentialRevisionDescriptor::new(&repo, &Redactor::Disabled)?,
+            &mut ReviewStatusDescriptor::new(&repo, &Redactor::Disabled)?,
             &mut CommitMessageDescriptor::new(&Redactor::Disabled)?,
         ],
     )?
@@ -1,21 +1,103 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::Write;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use itertools::Itertools;
 use lib::core::config::get_main_branch_name;
 use lib::core::dag::{CommitSet, Dag};
 use lib::core::effects::{Effects, OperationType};
-use lib::core::eventlog::EventLogDb;
+use lib::core::eventlog::{EventLogDb, EventTransactionId, STACK_REF_PREFIX};
+use lib::core::formatting::Pluralize;
 use lib::core::repo_ext::{RepoExt, RepoReferencesSnapshot};
+use lib::core::rewrite::RepoResource;
 use lib::git::{
-    Branch, BranchType, CategorizedReferenceName, GitRunInfo, NonZeroOid, ReferenceName, Repo,
+    Branch, BranchType, CategorizedReferenceName, ConfigRead, GitRunInfo, GitRunOpts, GitRunResult,
+    MaybeZeroOid, NonZeroOid, ReferenceName, Repo, ReviewInfo,
 };
 use lib::try_exit_code;
 use lib::util::{ExitCode, EyreExitOr};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 use tracing::{instrument, warn};
 
-use crate::{CommitStatus, CreateStatus, Forge, SubmitOptions, SubmitStatus};
+use crate::{CommitStatus, CreateStatus, Forge, LandSupport, SubmitOptions, SubmitStatus};
+
+/// Get the reference which records the parent branch of the given branch.
+fn get_stack_reference_name(branch_name: &str) -> ReferenceName {
+    ReferenceName::from(format!("{STACK_REF_PREFIX}{branch_name}"))
+}
+
+/// The config key which enables recording the parent branch of each submitted
+/// branch under [`STACK_REF_PREFIX`].
+const RECORD_STACKS_CONFIG_KEY: &str = "branchless.submit.branch.recordStacks";
+
+/// Whether the parent branch of each submitted branch should be recorded, and
+/// the recorded stacks reconstructed by `git sync`.
+pub fn should_record_stacks(repo: &Repo) -> eyre::Result<bool> {
+    let record_stacks = repo
+        .get_readonly_config()?
+        .get_or(RECORD_STACKS_CONFIG_KEY, false)?;
+    Ok(record_stacks)
+}
+
+/// The information recorded about a submitted branch under
+/// [`STACK_REF_PREFIX`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackRecord {
+    /// The name of the branch which the branch was on top of.
+    pub parent_branch_name: String,
+
+    /// The commit which the branch pointed to when it was submitted.
+    pub head_oid: NonZeroOid,
+}
+
+impl StackRecord {
+    fn serialize(&self) -> String {
+        let Self {
+            parent_branch_name,
+            head_oid,
+        } = self;
+        format!("parent {parent_branch_name}\nhead {head_oid}\n")
+    }
+
+    fn deserialize(contents: &[u8]) -> Option<Self> {
+        let contents = std::str::from_utf8(contents).ok()?;
+        let mut lines = contents.lines();
+        let parent_branch_name = lines.next()?.strip_prefix("parent ")?.to_owned();
+        let head_oid = lines.next()?.strip_prefix("head ")?.parse().ok()?;
+        Some(Self {
+            parent_branch_name,
+            head_oid,
+        })
+    }
+}
+
+/// Read the record of each branch under [`STACK_REF_PREFIX`], such as written
+/// by `git submit` in another clone, keyed by branch name. Records which can't
+/// be parsed are skipped.
+pub fn read_stack_records(repo: &Repo) -> eyre::Result<HashMap<String, StackRecord>> {
+    let mut result = HashMap::new();
+    for reference in repo.get_all_references()? {
+        let reference_name = reference.get_name()?;
+        let branch_name = match reference_name.as_str().strip_prefix(STACK_REF_PREFIX) {
+            Some(branch_name) => branch_name.to_owned(),
+            None => continue,
+        };
+        let blob = match repo.reference_name_to_oid(&reference_name)? {
+            MaybeZeroOid::NonZero(oid) => repo.find_blob(oid)?,
+            MaybeZeroOid::Zero => None,
+        };
+        match blob.and_then(|blob| StackRecord::deserialize(blob.get_content())) {
+            Some(stack_record) => {
+                result.insert(branch_name, stack_record);
+            }
+            None => {
+                warn!(?reference_name, "Could not parse stack record");
+            }
+        }
+    }
+    Ok(result)
+}
 
 #[derive(Debug)]
 pub struct BranchForge<'a> {
@@ -190,7 +272,7 @@ impl Forge for BranchForge<'_> {
     fn create(
         &mut self,
         commits: HashMap<NonZeroOid, CommitStatus>,
-        _options: &SubmitOptions,
+        options: &SubmitOptions,
     ) -> EyreExitOr<HashMap<NonZeroOid, CreateStatus>> {
         let unsubmitted_branch_names = commits
             .values()
@@ -236,17 +318,38 @@ These remotes are available: {}",
         } else {
             // This will fail if somebody else created the branch on the remote and we don't
             // know about it.
-            let mut args = vec!["push", "--set-upstream", &push_remote];
-            args.extend(unsubmitted_branch_names.iter().map(|s| s.as_str()));
+            let branches = commits
+                .iter()
+                .filter_map(|(commit_oid, commit_status)| {
+                    commit_status
+                        .local_commit_name
+                        .clone()
+                        .map(|local_commit_name| (local_commit_name, *commit_oid))
+                })
+                .collect::<BTreeSet<_>>();
             let event_tx_id = self
                 .event_log_db
                 .make_transaction_id(SystemTime::now(), "submit unsubmitted commits")?;
-            let (effects, progress) = self.effects.start_operation(OperationType::PushCommits);
-            let _effects = effects;
-            progress.notify_progress(0, unsubmitted_branch_names.len());
-            try_exit_code!(self
-                .git_run_info
-                .run(self.effects, Some(event_tx_id), &args)?);
+            // Git sets the upstreams of the branches which were pushed even if
+            // others failed, so that they're considered submitted next time.
+            let push_outcomes = self.push_branches(
+                event_tx_id,
+                &push_remote,
+                &["--set-upstream"],
+                &branches,
+                options.num_jobs,
+            )?;
+            if any_push_failed(&push_outcomes) {
+                return Ok(Err(ExitCode(1)));
+            }
+            try_exit_code!(self.push_stack_references(
+                self.effects,
+                event_tx_id,
+                &push_remote,
+                branches
+                    .iter()
+                    .map(|(branch_name, commit_oid)| (*commit_oid, branch_name.as_str())),
+            )?);
             Ok(Ok(commits
                 .into_iter()
                 .filter_map(|(commit_oid, commit_status)| {
@@ -268,17 +371,17 @@ These remotes are available: {}",
     fn update(
         &mut self,
         commits: HashMap<NonZeroOid, CommitStatus>,
-        _options: &SubmitOptions,
+        options: &SubmitOptions,
     ) -> EyreExitOr<()> {
-        let branches_by_remote: BTreeMap<String, BTreeSet<String>> = commits
-            .into_values()
-            .flat_map(|commit_status| match commit_status {
+        let branches_by_remote: BTreeMap<String, BTreeSet<(String, NonZeroOid)>> = commits
+            .into_iter()
+            .flat_map(|(commit_oid, commit_status)| match commit_status {
                 CommitStatus {
                     submit_status: _,
                     remote_name: Some(remote_name),
                     local_commit_name: Some(local_commit_name),
                     remote_commit_name: _,
-                } => Some((remote_name, local_commit_name)),
+                } => Some((remote_name, (local_commit_name, commit_oid))),
                 commit_status => {
                     warn!(
                         ?commit_status,
@@ -294,29 +397,725 @@ These remotes are available: {}",
 
         let now = SystemTime::now();
         let event_tx_id = self.event_log_db.make_transaction_id(now, "submit")?;
+        for (remote_name, branches) in branches_by_remote {
+            let push_outcomes = self.push_branches(
+                event_tx_id,
+                &remote_name,
+                &["--force-with-lease"],
+                &branches,
+                options.num_jobs,
+            )?;
+            if any_push_failed(&push_outcomes) {
+                return Ok(Err(ExitCode(1)));
+            }
+            try_exit_code!(self.push_stack_references(
+                self.effects,
+                event_tx_id,
+                &remote_name,
+                branches
+                    .iter()
+                    .map(|(branch_name, commit_oid)| (*commit_oid, branch_name.as_str())),
+            )?);
+        }
+
+        Ok(Ok(()))
+    }
+
+    #[instrument]
+    fn query_review_infos(
+        &mut self,
+        _commit_set: CommitSet,
+    ) -> EyreExitOr<Option<HashMap<NonZeroOid, ReviewInfo>>> {
+        Ok(Ok(None))
+    }
+
+    fn land_support(&self) -> LandSupport {
+        LandSupport::Supported
+    }
+}
+
+/// The outcome of pushing a single branch, as reported by `git push --porcelain`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PushOutcome {
+    Pushed,
+    Failed {
+        /// A human-readable explanation of the failure.
+        reason: String,
+
+        /// Whether pushing the branch again might succeed.
+        retryable: bool,
+    },
+}
+
+/// Determine whether a branch which failed to push with the given `git push
+/// --porcelain` summary might succeed if pushed again.
+///
+/// Branches rejected by the client (such as due to a stale
+/// `--force-with-lease`) or by the remote (such as by a hook or a branch
+/// protection policy) will keep getting rejected, so they aren't retried,
+/// unless they were only rejected because another branch in the same atomic
+/// push was.
+fn is_push_failure_retryable(summary: &str) -> bool {
+    if summary.contains("(atomic push failed)") || summary.contains("(atomic push failure)") {
+        true
+    } else {
+        !(summary.starts_with("[rejected]") || summary.starts_with("[remote rejected]"))
+    }
+}
+
+/// Parse the status lines of `git push --porcelain`, keyed by the local
+/// reference name.
+fn parse_push_status_lines(stdout: &str) -> HashMap<&str, PushOutcome> {
+    let mut ref_outcomes = HashMap::new();
+    for line in stdout.lines() {
+        let (flag, refspec, summary) = match line.split('\t').collect_vec().as_slice() {
+            [flag, refspec, summary] => (*flag, *refspec, *summary),
+            _ => continue,
+        };
+        let local_ref_name = match refspec.split_once(':') {
+            Some((local_ref_name, _remote_ref_name)) => local_ref_name,
+            None => continue,
+        };
+        let outcome = if flag == "!" {
+            PushOutcome::Failed {
+                reason: summary.to_owned(),
+                retryable: is_push_failure_retryable(summary),
+            }
+        } else {
+            PushOutcome::Pushed
+        };
+        ref_outcomes.insert(local_ref_name, outcome);
+    }
+    ref_outcomes
+}
+
+/// Parse the per-ref results of `git push --porcelain` for the provided
+/// branches. Branches which aren't mentioned in the output (such as if the
+/// remote couldn't be contacted at all) are considered to have failed with
+/// the last line of `stderr` as the reason.
+fn parse_push_outcomes<'a>(
+    stdout: &str,
+    stderr: &str,
+    branch_names: impl IntoIterator<Item = &'a str>,
+) -> BTreeMap<String, PushOutcome> {
+    let ref_outcomes = parse_push_status_lines(stdout);
+    let fallback_reason = stderr
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .last()
+        .unwrap_or("unknown error");
+    branch_names
+        .into_iter()
+        .map(|branch_name| {
+            let outcome = ref_outcomes
+                .get(format!("refs/heads/{branch_name}").as_str())
+                .cloned()
+                .unwrap_or_else(|| PushOutcome::Failed {
+                    reason: fallback_reason.to_owned(),
+                    retryable: true,
+                });
+            (branch_name.to_owned(), outcome)
+        })
+        .collect()
+}
+
+/// Get the lines of `git push --porcelain` output which aren't part of the
+/// porcelain format, such as Git's messages about configured upstreams.
+fn get_push_messages(stdout: &str) -> String {
+    stdout
+        .lines()
+        .filter(|line| !(line.starts_with("To ") || line.contains('\t') || *line == "Done"))
+        .flat_map(|line| [line, "\n"])
+        .collect()
+}
+
+/// Remove Git's own error messages from the stderr of `git push` if every
+/// branch has a status line, since the status lines already explain any
+/// failures. Messages from the remote and hints are kept.
+fn filter_push_stderr<'a>(
+    stdout: &str,
+    stderr: &str,
+    branch_names: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let ref_outcomes = parse_push_status_lines(stdout);
+    let all_reported = branch_names
+        .into_iter()
+        .all(|branch_name| ref_outcomes.contains_key(format!("refs/heads/{branch_name}").as_str()));
+    if !all_reported {
+        return stderr.to_owned();
+    }
+    stderr
+        .lines()
+        .filter(|line| !(line.starts_with("error: ") || line.starts_with("fatal: ")))
+        .flat_map(|line| [line, "\n"])
+        .collect()
+}
+
+/// Whether any of the provided branches failed to push.
+fn any_push_failed(outcomes: &BTreeMap<String, PushOutcome>) -> bool {
+    outcomes
+        .values()
+        .any(|outcome| matches!(outcome, PushOutcome::Failed { .. }))
+}
+
+/// The result of a single invocation of `git push`.
+#[derive(Debug)]
+struct PushResult {
+    /// The arguments which Git was invoked with.
+    args: Vec<String>,
+
+    /// Git's messages which aren't part of the porcelain output, such as
+    /// which upstreams were configured.
+    messages: String,
+
+    /// The output that Git wrote to stderr, such as messages from the remote.
+    stderr: String,
+
+    /// The outcome for each branch, or `None` if an atomic push was requested
+    /// but the remote doesn't support atomic pushes.
+    outcomes: Option<BTreeMap<String, PushOutcome>>,
+}
+
+/// Run a single `git push` for the provided branches.
+fn run_push(
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    event_tx_id: EventTransactionId,
+    remote_name: &str,
+    extra_args: &[&str],
+    branch_names: &[String],
+    atomic: bool,
+) -> eyre::Result<PushResult> {
+    let mut args = vec!["push", "--porcelain"];
+    if atomic {
+        args.push("--atomic");
+    }
+    args.extend(extra_args);
+    args.push(remote_name);
+    args.extend(branch_names.iter().map(|branch_name| branch_name.as_str()));
+    let GitRunResult {
+        exit_code,
+        stdout,
+        stderr,
+    } = git_run_info.run_silent(
+        repo,
+        Some(event_tx_id),
+        &args,
+        GitRunOpts {
+            treat_git_failure_as_error: false,
+            stdin: None,
+        },
+    )?;
+    let stdout = String::from_utf8_lossy(&stdout);
+    let stderr = String::from_utf8_lossy(&stderr);
+    let branch_names = || branch_names.iter().map(|branch_name| branch_name.as_str());
+    let outcomes =
+        if atomic && !exit_code.is_success() && stderr.contains("does not support --atomic") {
+            None
+        } else {
+            Some(parse_push_outcomes(&stdout, &stderr, branch_names()))
+        };
+    Ok(PushResult {
+        args: args.into_iter().map(|arg| arg.to_owned()).collect(),
+        messages: get_push_messages(&stdout),
+        stderr: filter_push_stderr(&stdout, &stderr, branch_names()),
+        outcomes,
+    })
+}
+
+impl BranchForge<'_> {
+    /// Push the provided branches to the remote, printing a summary of which
+    /// commits were pushed and which failed. Returns the outcome for each
+    /// branch.
+    ///
+    /// The branches are pushed with a single `git push --atomic` if the remote
+    /// supports it, or else in up to `num_jobs` concurrent batches. Branches
+    /// which fail to push are retried up to `branchless.submit.pushRetries`
+    /// times.
+    fn push_branches(
+        &self,
+        event_tx_id: EventTransactionId,
+        remote_name: &str,
+        extra_args: &[&str],
+        branches: &BTreeSet<(String, NonZeroOid)>,
+        num_jobs: usize,
+    ) -> eyre::Result<BTreeMap<String, PushOutcome>> {
+        let num_retries: i32 = self
+            .repo
+            .get_readonly_config()?
+            .get_or("branchless.submit.pushRetries", 2)?;
+        let max_attempts = usize::try_from(num_retries).unwrap_or_default() + 1;
+
         let (effects, progress) = self.effects.start_operation(OperationType::PushCommits);
-        let total_num_branches = branches_by_remote
-            .values()
-            .map(|branch_names| branch_names.len())
-            .sum();
-        progress.notify_progress(0, total_num_branches);
-        for (remote_name, branch_names) in branches_by_remote {
-            let mut args = vec!["push", "--force-with-lease", &remote_name];
-            args.extend(branch_names.iter().map(|s| s.as_str()));
-            match self.git_run_info.run(&effects, Some(event_tx_id), &args)? {
-                Ok(()) => {}
-                Err(exit_code) => {
+        progress.notify_progress(0, branches.len());
+        let mut outcomes: BTreeMap<String, PushOutcome> = BTreeMap::new();
+        let mut pending_branch_names: Vec<String> = branches
+            .iter()
+            .map(|(branch_name, _commit_oid)| branch_name.clone())
+            .collect();
+        let mut use_atomic = true;
+        for attempt in 1..=max_attempts {
+            if pending_branch_names.is_empty() {
+                break;
+            }
+            if attempt > 1 {
+                writeln!(
+                    effects.get_output_stream(),
+                    "Retrying push of {} to {remote_name} (attempt {attempt} of {max_attempts})...",
+                    Pluralize {
+                        determiner: None,
+                        amount: pending_branch_names.len(),
+                        unit: ("branch", "branches"),
+                    },
+                )?;
+                std::thread::sleep(Duration::from_millis(500) * u32::try_from(attempt - 1)?);
+            }
+
+            let mut push_results = Vec::new();
+            if use_atomic {
+                let push_result = run_push(
+                    self.git_run_info,
+                    self.repo,
+                    event_tx_id,
+                    remote_name,
+                    extra_args,
+                    &pending_branch_names,
+                    true,
+                )?;
+                if push_result.outcomes.is_some() {
+                    push_results.push(push_result);
+                } else {
+                    use_atomic = false;
+                }
+            }
+            if !use_atomic {
+                push_results.extend(self.run_push_batches(
+                    event_tx_id,
+                    remote_name,
+                    extra_args,
+                    &pending_branch_names,
+                    num_jobs,
+                )?);
+            }
+
+            pending_branch_names.clear();
+            for push_result in push_results {
+                let PushResult {
+                    args,
+                    messages,
+                    stderr,
+                    outcomes: attempt_outcomes,
+                } = push_result;
+                writeln!(
+                    effects.get_output_stream(),
+                    "branchless: running command: {} {}",
+                    self.git_run_info.path_to_git.to_string_lossy(),
+                    args.join(" "),
+                )?;
+                write!(effects.get_output_stream(), "{messages}")?;
+                write!(effects.get_error_stream(), "{stderr}")?;
+
+                for (branch_name, outcome) in attempt_outcomes.into_iter().flatten() {
+                    match outcome {
+                        PushOutcome::Pushed => progress.notify_progress_inc(1),
+                        PushOutcome::Failed {
+                            reason: _,
+                            retryable: true,
+                        } => pending_branch_names.push(branch_name.clone()),
+                        PushOutcome::Failed {
+                            reason: _,
+                            retryable: false,
+                        } => {}
+                    }
+                    outcomes.insert(branch_name, outcome);
+                }
+            }
+        }
+        drop(progress);
+
+        for (branch_name, commit_oid) in branches {
+            let commit = self.repo.find_commit_or_fail(*commit_oid)?;
+            let commit_description = effects
+                .get_glyphs()
+                .render(commit.friendly_describe(effects.get_glyphs())?)?;
+            match outcomes.get(branch_name) {
+                Some(PushOutcome::Pushed) => {
                     writeln!(
                         effects.get_output_stream(),
-                        "Failed to push branches: {}",
-                        branch_names.into_iter().join(", ")
+                        "Pushed branch {branch_name} ({commit_description})"
                     )?;
-                    return Ok(Err(exit_code));
+                }
+                Some(PushOutcome::Failed {
+                    reason,
+                    retryable: _,
+                }) => {
+                    writeln!(
+                        effects.get_output_stream(),
+                        "Failed to push branch {branch_name} ({commit_description}): {reason}"
+                    )?;
+                }
+                None => {
+                    eyre::bail!("BUG: No push outcome for branch {branch_name:?}");
                 }
             }
-            progress.notify_progress_inc(branch_names.len());
         }
 
-        Ok(Ok(()))
+        Ok(outcomes)
+    }
+
+    /// Push the provided branches in concurrent batches, for remotes which
+    /// don't support atomic pushes.
+    fn run_push_batches(
+        &self,
+        event_tx_id: EventTransactionId,
+        remote_name: &str,
+        extra_args: &[&str],
+        branch_names: &[String],
+        num_jobs: usize,
+    ) -> eyre::Result<Vec<PushResult>> {
+        let num_jobs = num_jobs.clamp(1, branch_names.len().max(1));
+        let batch_size = (branch_names.len() + num_jobs - 1) / num_jobs;
+        let batches = branch_names.chunks(batch_size.max(1)).collect_vec();
+
+        let pool = ThreadPoolBuilder::new().num_threads(num_jobs).build()?;
+        let repo_pool = RepoResource::new_pool(self.repo)?;
+        let git_run_info = self.git_run_info;
+        pool.install(|| {
+            batches
+                .into_par_iter()
+                .map(|batch| -> eyre::Result<_> {
+                    let repo = repo_pool.try_create()?;
+                    let push_result = run_push(
+                        git_run_info,
+                        &repo,
+                        event_tx_id,
+                        remote_name,
+                        extra_args,
+                        batch,
+                        false,
+                    )?;
+                    if push_result.outcomes.is_none() {
+                        eyre::bail!(
+                            "BUG: Non-atomic push reported that atomic pushes are unsupported"
+                        );
+                    }
+                    Ok(push_result)
+                })
+                .collect::<eyre::Result<Vec<_>>>()
+        })
+    }
+
+    /// Record the parent branch of each of the provided branches under
+    /// [`STACK_REF_PREFIX`] and push the records to the remote, if enabled.
+    /// Records for branches which no longer exist locally or on the remote
+    /// are deleted, both locally and on the remote.
+    fn push_stack_references<'b>(
+        &self,
+        effects: &Effects,
+        event_tx_id: EventTransactionId,
+        remote_name: &str,
+        branches: impl IntoIterator<Item = (NonZeroOid, &'b str)>,
+    ) -> EyreExitOr<()> {
+        if !should_record_stacks(self.repo)? {
+            return Ok(Ok(()));
+        }
+
+        let mut stack_reference_names = BTreeSet::new();
+        for (commit_oid, branch_name) in branches {
+            let parent_branch_name = match self.get_parent_branch_name(commit_oid)? {
+                Some(parent_branch_name) => parent_branch_name,
+                None => {
+                    warn!(
+                        ?branch_name,
+                        "Could not determine a unique parent branch, so not recording it"
+                    );
+                    continue;
+                }
+            };
+            let stack_record = StackRecord {
+                parent_branch_name,
+                head_oid: commit_oid,
+            };
+            let blob_oid = self
+                .repo
+                .create_blob_from_contents(stack_record.serialize().as_bytes())?;
+            let stack_reference_name = get_stack_reference_name(branch_name);
+            self.repo
+                .create_reference(&stack_reference_name, blob_oid, true, "submit")?;
+            stack_reference_names.insert(stack_reference_name.as_str().to_owned());
+        }
+        let stale_reference_names =
+            try_exit_code!(self.delete_stale_stack_references(event_tx_id, remote_name)?);
+        if stack_reference_names.is_empty() && stale_reference_names.is_empty() {
+            return Ok(Ok(()));
+        }
+
+        let mut args = vec![
+            "push".to_owned(),
+            "--force".to_owned(),
+            remote_name.to_owned(),
+        ];
+        args.extend(stack_reference_names);
+        args.extend(
+            stale_reference_names
+                .into_iter()
+                .map(|reference_name| format!(":{reference_name}")),
+        );
+        self.git_run_info.run(effects, Some(event_tx_id), &args)
+    }
+
+    /// Delete the local stack records for branches which exist neither locally
+    /// nor on the given remote, such as branches which have been landed.
+    /// Returns the names of the deleted records which also exist on the
+    /// remote, and so need to be deleted there as well.
+    fn delete_stale_stack_references(
+        &self,
+        event_tx_id: EventTransactionId,
+        remote_name: &str,
+    ) -> EyreExitOr<BTreeSet<String>> {
+        let mut stale_reference_names = BTreeSet::new();
+        for branch_name in read_stack_records(self.repo)?.into_keys() {
+            let is_stale = self
+                .repo
+                .find_branch(&branch_name, BranchType::Local)?
+                .is_none()
+                && self
+                    .repo
+                    .find_branch(&format!("{remote_name}/{branch_name}"), BranchType::Remote)?
+                    .is_none();
+            if is_stale {
+                let stack_reference_name = get_stack_reference_name(&branch_name);
+                if let Some(mut reference) = self.repo.find_reference(&stack_reference_name)? {
+                    reference.delete()?;
+                }
+                stale_reference_names.insert(stack_reference_name.as_str().to_owned());
+            }
+        }
+        if stale_reference_names.is_empty() {
+            return Ok(Ok(stale_reference_names));
+        }
+
+        // Deleting a reference which doesn't exist on the remote would fail
+        // the whole push.
+        let mut args = vec!["ls-remote", "--refs", remote_name];
+        args.extend(stale_reference_names.iter().map(|s| s.as_str()));
+        let GitRunResult {
+            exit_code,
+            stdout,
+            stderr,
+        } = self.git_run_info.run_silent(
+            self.repo,
+            Some(event_tx_id),
+            &args,
+            GitRunOpts {
+                treat_git_failure_as_error: false,
+                stdin: None,
+            },
+        )?;
+        if !exit_code.is_success() {
+            writeln!(
+                self.effects.get_output_stream(),
+                "Failed to list stack records on remote {remote_name}:\n{}",
+                String::from_utf8_lossy(&stderr)
+            )?;
+            return Ok(Err(exit_code));
+        }
+        let remote_reference_names: BTreeSet<String> = String::from_utf8_lossy(&stdout)
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(1))
+            .map(|reference_name| reference_name.to_owned())
+            .collect();
+        Ok(Ok(stale_reference_names
+            .intersection(&remote_reference_names)
+            .cloned()
+            .collect()))
+    }
+
+    /// Find the branch which the branch at the given commit is stacked on top
+    /// of: the nearest ancestor branch which isn't on the main branch, or
+    /// the main branch itself. Returns `None` if there are several candidates.
+    fn get_parent_branch_name(&self, commit_oid: NonZeroOid) -> eyre::Result<Option<String>> {
+        let main_branch_name = get_main_branch_name(self.repo)?;
+        let branch_commits: CommitSet = self
+            .references_snapshot
+            .branch_oid_to_names
+            .keys()
+            .copied()
+            .collect();
+        let ancestors = self.dag.query_only(
+            self.dag.query_parents(CommitSet::from(commit_oid))?,
+            self.dag.main_branch_commit.clone(),
+        )?;
+        let parent_commits = self
+            .dag
+            .query_heads(ancestors.intersection(&branch_commits))?;
+        let parent_commit_oid = match self
+            .dag
+            .commit_set_to_vec(&parent_commits)?
+            .into_iter()
+            .exactly_one()
+        {
+            Ok(parent_commit_oid) => parent_commit_oid,
+            Err(parent_commit_oids) if parent_commit_oids.len() == 0 => {
+                return Ok(Some(main_branch_name))
+            }
+            Err(_) => return Ok(None),
+        };
+
+        let parent_branch_names = self
+            .references_snapshot
+            .branch_oid_to_names
+            .get(&parent_commit_oid)
+            .into_iter()
+            .flatten()
+            .filter_map(
+                |reference_name| match CategorizedReferenceName::new(reference_name) {
+                    name @ CategorizedReferenceName::LocalBranch { .. } => {
+                        Some(name.render_suffix())
+                    }
+                    CategorizedReferenceName::RemoteBranch { .. }
+                    | CategorizedReferenceName::OtherRef { .. } => None,
+                },
+            )
+            .collect_vec();
+        Ok(parent_branch_names.into_iter().exactly_one().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{filter_push_stderr, get_push_messages, parse_push_outcomes, PushOutcome};
+
+    #[test]
+    fn test_parse_push_outcomes() {
+        let stdout = "\
+To file:///path/to/remote
+*\trefs/heads/foo:refs/heads/foo\t[new branch]
++\trefs/heads/bar:refs/heads/bar\t355e173...f57e36f (forced update)
+=\trefs/heads/baz:refs/heads/baz\t[up to date]
+!\trefs/heads/qux:refs/heads/qux\t[rejected] (stale info)
+Done
+";
+        let outcomes = parse_push_outcomes(stdout, "", ["foo", "bar", "baz", "qux"]);
+        assert_eq!(
+            outcomes.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("bar".to_owned(), PushOutcome::Pushed),
+                ("baz".to_owned(), PushOutcome::Pushed),
+                ("foo".to_owned(), PushOutcome::Pushed),
+                (
+                    "qux".to_owned(),
+                    PushOutcome::Failed {
+                        reason: "[rejected] (stale info)".to_owned(),
+                        retryable: false,
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_push_outcomes_atomic_failure() {
+        let stdout = "\
+To file:///path/to/remote
+!\trefs/heads/foo:refs/heads/foo\t[remote rejected] (hook declined)
+!\trefs/heads/bar:refs/heads/bar\t[remote rejected] (atomic push failure)
+!\trefs/heads/baz:refs/heads/baz\t[rejected] (atomic push failed)
+Done
+";
+        let stderr = "error: failed to push some refs to 'file:///path/to/remote'\n";
+        let outcomes = parse_push_outcomes(stdout, stderr, ["foo", "bar", "baz"]);
+        assert_eq!(
+            outcomes.into_iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    "bar".to_owned(),
+                    PushOutcome::Failed {
+                        reason: "[remote rejected] (atomic push failure)".to_owned(),
+                        retryable: true,
+                    }
+                ),
+                (
+                    "baz".to_owned(),
+                    PushOutcome::Failed {
+                        reason: "[rejected] (atomic push failed)".to_owned(),
+                        retryable: true,
+                    }
+                ),
+                (
+                    "foo".to_owned(),
+                    PushOutcome::Failed {
+                        reason: "[remote rejected] (hook declined)".to_owned(),
+                        retryable: false,
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_push_outcomes_no_status() {
+        let stderr = "\
+send-pack: unexpected disconnect while reading sideband packet
+fatal: the remote end hung up unexpectedly
+";
+        let outcomes = parse_push_outcomes("", stderr, ["foo"]);
+        assert_eq!(
+            outcomes.into_iter().collect::<Vec<_>>(),
+            vec![(
+                "foo".to_owned(),
+                PushOutcome::Failed {
+                    reason: "fatal: the remote end hung up unexpectedly".to_owned(),
+                    retryable: true,
+                }
+            )]
+        );
+
+        let outcomes = parse_push_outcomes("", "", ["foo"]);
+        assert_eq!(
+            outcomes.into_iter().collect::<Vec<_>>(),
+            vec![(
+                "foo".to_owned(),
+                PushOutcome::Failed {
+                    reason: "unknown error".to_owned(),
+                    retryable: true,
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_get_push_messages() {
+        let stdout = "\
+To file:///path/to/remote
+*\trefs/heads/foo:refs/heads/foo\t[new branch]
+branch 'foo' set up to track 'origin/foo'.
+Done
+";
+        assert_eq!(
+            get_push_messages(stdout),
+            "branch 'foo' set up to track 'origin/foo'.\n"
+        );
+    }
+
+    #[test]
+    fn test_filter_push_stderr() {
+        let stdout = "\
+To file:///path/to/remote
+!\trefs/heads/foo:refs/heads/foo\t[rejected] (fetch first)
+Done
+";
+        let stderr = "\
+remote: Resolving deltas: 100% (1/1)
+error: atomic push failed for ref refs/heads/foo. status: 5
+fatal: the remote end hung up unexpectedly
+error: failed to push some refs to 'file:///path/to/remote'
+";
+        assert_eq!(
+            filter_push_stderr(stdout, stderr, ["foo"]),
+            "remote: Resolving deltas: 100% (1/1)\n"
+        );
+        assert_eq!(filter_push_stderr(stdout, stderr, ["foo", "bar"]), stderr);
     }
 }
@@ -0,0 +1,961 @@
+//! Gerrit backend for submitting patch stacks.
+
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fmt::{Debug, Write};
+use std::time::SystemTime;
+
+use itertools::Itertools;
+use lib::core::check_out::CheckOutCommitOptions;
+use lib::core::config::get_restack_preserve_timestamps;
+use lib::core::dag::{CommitSet, Dag};
+use lib::core::effects::{Effects, OperationType};
+use lib::core::eventlog::EventLogDb;
+use lib::core::repo_ext::RepoExt;
+use lib::core::rewrite::{
+    execute_rebase_plan, BuildRebasePlanOptions, ExecuteRebasePlanOptions, ExecuteRebasePlanResult,
+    RebasePlanBuilder, RebasePlanPermissions, RepoResource,
+};
+use lib::git::{
+    CategorizedReferenceName, Commit, ConfigRead, GitRunInfo, MaybeZeroOid, NonZeroOid, Repo,
+    ReviewInfo,
+};
+use lib::try_exit_code;
+use lib::util::{ExitCode, EyreExitOr};
+use rayon::ThreadPoolBuilder;
+use tracing::{debug, instrument, warn};
+
+use crate::{CommitStatus, CreateStatus, Forge, LandSupport, SubmitOptions, SubmitStatus};
+
+/// Testing environment variable. When this is set, the executable will use the
+/// mock Gerrit implementation. This should be set to the path of an existing
+/// repository that represents the remote/Gerrit.
+pub const MOCK_REMOTE_REPO_PATH_ENV_KEY: &str = "BRANCHLESS_SUBMIT_GERRIT_MOCK_REMOTE_REPO_PATH";
+
+/// The commit message trailer which Gerrit uses to associate commits with
+/// changes.
+pub const CHANGE_ID_TRAILER: &str = "Change-Id";
+
+/// Get the Gerrit Change-Id of the given commit, if it has one.
+pub fn get_change_id(commit: &Commit) -> eyre::Result<Option<String>> {
+    let change_id = commit
+        .get_trailers()?
+        .into_iter()
+        .filter(|(key, _value)| key == CHANGE_ID_TRAILER)
+        .map(|(_key, value)| value.trim().to_owned())
+        .last();
+    Ok(change_id)
+}
+
+/// Get the name of the branch on the remote which changes should target, i.e.
+/// the main branch without any remote name prefix.
+fn get_target_branch_name(repo: &Repo) -> eyre::Result<String> {
+    let main_branch_reference_name = repo.get_main_branch()?.get_reference_name()?;
+    let target_branch_name = match CategorizedReferenceName::new(&main_branch_reference_name) {
+        name @ CategorizedReferenceName::RemoteBranch { .. } => {
+            let name = name.render_suffix();
+            match name.split_once('/') {
+                Some((_remote_name, branch_name)) => branch_name.to_owned(),
+                None => name,
+            }
+        }
+        name @ (CategorizedReferenceName::LocalBranch { .. }
+        | CategorizedReferenceName::OtherRef { .. }) => name.render_suffix(),
+    };
+    Ok(target_branch_name)
+}
+
+/// The [Gerrit](https://www.gerritcodereview.com/) code review system. Commits
+/// are pushed to the magic `refs/for/<branch>` reference, and Gerrit creates
+/// one change per commit, keyed by the `Change-Id` trailer in the commit
+/// message. Pushing a new version of a commit with the same `Change-Id`
+/// uploads a new patch set to the existing change.
+///
+/// This forge queries Gerrit using its SSH interface.
+#[allow(missing_docs)]
+#[derive(Debug)]
+pub struct GerritForge<'a> {
+    pub effects: &'a Effects,
+    pub git_run_info: &'a GitRunInfo,
+    pub repo: &'a Repo,
+    pub dag: &'a mut Dag,
+    pub event_log_db: &'a EventLogDb<'a>,
+    pub client: Box<dyn client::GerritClient>,
+}
+
+impl Forge for GerritForge<'_> {
+    #[instrument]
+    fn query_status(
+        &mut self,
+        commit_set: CommitSet,
+    ) -> EyreExitOr<HashMap<NonZeroOid, CommitStatus>> {
+        let effects = self.effects;
+        let commit_oids = self.dag.commit_set_to_vec(&commit_set)?;
+        let commit_change_ids: HashMap<NonZeroOid, Option<String>> = commit_oids
+            .iter()
+            .map(|commit_oid| -> eyre::Result<_> {
+                let commit = self.repo.find_commit_or_fail(*commit_oid)?;
+                Ok((*commit_oid, get_change_id(&commit)?))
+            })
+            .try_collect()?;
+
+        let change_ids: BTreeSet<String> = commit_change_ids.values().flatten().cloned().collect();
+        let change_infos = if change_ids.is_empty() {
+            Default::default()
+        } else {
+            let target_branch_name = get_target_branch_name(self.repo)?;
+            try_exit_code!(self.client.query_change_infos(
+                effects,
+                &target_branch_name,
+                &change_ids.into_iter().collect_vec(),
+            )?)
+        };
+        let remote_name = self.repo.get_default_push_remote()?;
+
+        let mut result = HashMap::new();
+        for commit_oid in commit_oids {
+            let change_id = commit_change_ids.get(&commit_oid).cloned().flatten();
+            let change_info = change_id
+                .as_ref()
+                .and_then(|change_id| change_infos.get(change_id));
+            let submit_status = match change_info {
+                None => SubmitStatus::Unsubmitted,
+                Some(change_info) => {
+                    debug!(?commit_oid, ?change_info, "Comparing change info");
+                    if change_info.current_patch_set.revision.0 == commit_oid {
+                        SubmitStatus::UpToDate
+                    } else {
+                        SubmitStatus::NeedsUpdate
+                    }
+                }
+            };
+            let local_commit_name = match change_id {
+                Some(change_id) => change_id,
+                None => {
+                    let commit = self.repo.find_commit_or_fail(commit_oid)?;
+                    String::from_utf8_lossy(&commit.get_summary()?).into_owned()
+                }
+            };
+            result.insert(
+                commit_oid,
+                CommitStatus {
+                    submit_status,
+                    remote_name: remote_name.clone(),
+                    local_commit_name: Some(local_commit_name),
+                    remote_commit_name: change_info.map(|change_info| change_info.url.clone()),
+                },
+            );
+        }
+        Ok(Ok(result))
+    }
+
+    #[instrument]
+    fn create(
+        &mut self,
+        commits: HashMap<NonZeroOid, CommitStatus>,
+        options: &SubmitOptions,
+    ) -> EyreExitOr<HashMap<NonZeroOid, CreateStatus>> {
+        let commit_oids = self.dag.sort(&commits.keys().copied().collect())?;
+        if commit_oids.is_empty() {
+            return Ok(Ok(Default::default()));
+        }
+
+        let rewritten_oids = try_exit_code!(self.add_change_ids(&commit_oids)?);
+        let mut create_statuses = HashMap::new();
+        for commit_oid in commit_oids {
+            let final_commit_oid = rewritten_oids
+                .get(&commit_oid)
+                .copied()
+                .unwrap_or(commit_oid);
+            let final_commit = self.repo.find_commit_or_fail(final_commit_oid)?;
+            let change_id = match get_change_id(&final_commit)? {
+                Some(change_id) => change_id,
+                None => {
+                    eyre::bail!(
+                        "BUG: Commit {final_commit_oid} does not have a Change-Id after adding Change-Ids"
+                    );
+                }
+            };
+            create_statuses.insert(
+                commit_oid,
+                CreateStatus {
+                    final_commit_oid,
+                    local_commit_name: change_id,
+                },
+            );
+        }
+
+        let final_commit_oids: CommitSet = create_statuses
+            .values()
+            .map(|create_status| {
+                let CreateStatus {
+                    final_commit_oid,
+                    local_commit_name: _,
+                } = create_status;
+                *final_commit_oid
+            })
+            .collect();
+        try_exit_code!(self.push_for_review(&final_commit_oids, options)?);
+        Ok(Ok(create_statuses))
+    }
+
+    #[instrument]
+    fn update(
+        &mut self,
+        commits: HashMap<NonZeroOid, CommitStatus>,
+        options: &SubmitOptions,
+    ) -> EyreExitOr<()> {
+        let commit_set: CommitSet = commits.keys().copied().collect();
+        if self.dag.set_is_empty(&commit_set)? {
+            return Ok(Ok(()));
+        }
+        self.push_for_review(&commit_set, options)
+    }
+
+    #[instrument]
+    fn query_review_infos(
+        &mut self,
+        _commit_set: CommitSet,
+    ) -> EyreExitOr<Option<HashMap<NonZeroOid, ReviewInfo>>> {
+        Ok(Ok(None))
+    }
+
+    fn land_support(&self) -> LandSupport {
+        // Changes are merged by submitting them in Gerrit.
+        LandSupport::Unsupported {
+            forge_name: "Gerrit",
+        }
+    }
+}
+
+impl GerritForge<'_> {
+    /// Construct a real or mock Gerrit client according to the environment.
+    ///
+    /// The real client connects to the SSH address in the
+    /// `branchless.submit.gerrit.sshUrl` config key, or else the URL of the
+    /// default push remote.
+    pub fn client(
+        git_run_info: GitRunInfo,
+        repo: &Repo,
+    ) -> eyre::Result<Box<dyn client::GerritClient>> {
+        if let Ok(path) = env::var(MOCK_REMOTE_REPO_PATH_ENV_KEY) {
+            return Ok(Box::new(client::MockGerritClient {
+                remote_repo_path: path.into(),
+            }));
+        }
+
+        let config = repo.get_readonly_config()?;
+        let ssh_url = match config.get::<String, _>("branchless.submit.gerrit.sshUrl")? {
+            Some(ssh_url) => Some(ssh_url),
+            None => match repo.get_default_push_remote()? {
+                Some(remote_name) => config.get(format!("remote.{remote_name}.url"))?,
+                None => None,
+            },
+        };
+        let (ssh_args, project) = match ssh_url.as_deref().and_then(parse_gerrit_ssh_url) {
+            Some(parsed) => parsed,
+            None => eyre::bail!(
+                "Could not determine the Gerrit SSH address from {ssh_url:?}. Configure one with: git config branchless.submit.gerrit.sshUrl ssh://<user>@<host>:29418/<project>"
+            ),
+        };
+        let GitRunInfo {
+            path_to_git: _,
+            working_directory,
+            env,
+        } = git_run_info;
+        let ssh_run_info = GitRunInfo {
+            path_to_git: "ssh".into(),
+            working_directory,
+            env,
+        };
+        Ok(Box::new(client::RealGerritClient {
+            ssh_run_info,
+            ssh_args,
+            project,
+        }))
+    }
+
+    /// Add a `Change-Id` trailer to each of the given commits which doesn't
+    /// already have one, rewriting descendant commits as necessary. Returns a
+    /// mapping from the original commit OIDs to the rewritten commit OIDs.
+    #[instrument]
+    fn add_change_ids(
+        &mut self,
+        commit_oids: &[NonZeroOid],
+    ) -> EyreExitOr<HashMap<NonZeroOid, NonZeroOid>> {
+        let effects = self.effects;
+        let mut commits_to_amend = Vec::new();
+        for commit_oid in commit_oids {
+            let commit = self.repo.find_commit_or_fail(*commit_oid)?;
+            if get_change_id(&commit)?.is_none() {
+                commits_to_amend.push(commit);
+            }
+        }
+        if commits_to_amend.is_empty() {
+            return Ok(Ok(Default::default()));
+        }
+
+        let now = SystemTime::now();
+        let event_tx_id = self
+            .event_log_db
+            .make_transaction_id(now, "gerrit add change IDs")?;
+        let build_options = BuildRebasePlanOptions {
+            force_rewrite_public_commits: false,
+            dump_rebase_constraints: false,
+            dump_rebase_plan: false,
+            detect_duplicate_commits_via_patch_id: false,
+        };
+        let execute_options = ExecuteRebasePlanOptions {
+            now,
+            event_tx_id,
+            preserve_timestamps: get_restack_preserve_timestamps(self.repo)?,
+            force_in_memory: true,
+            force_on_disk: false,
+            resolve_merge_conflicts: false,
+            check_out_commit_options: CheckOutCommitOptions {
+                render_smartlog: false,
+                ..Default::default()
+            },
+        };
+        let commit_set: CommitSet = commits_to_amend
+            .iter()
+            .map(|commit| commit.get_oid())
+            .collect();
+        let permissions = match RebasePlanPermissions::verify_rewrite_set(
+            self.dag,
+            build_options,
+            &commit_set,
+        )? {
+            Ok(permissions) => permissions,
+            Err(err) => {
+                err.describe(effects, self.repo, self.dag)?;
+                return Ok(Err(ExitCode(1)));
+            }
+        };
+
+        let rebase_plan = {
+            let pool = ThreadPoolBuilder::new().build()?;
+            let repo_pool = RepoResource::new_pool(self.repo)?;
+            let mut builder = RebasePlanBuilder::new(self.dag, permissions);
+            for commit in commits_to_amend.iter() {
+                let message = add_change_id_trailer(commit)?;
+                let replacement_oid =
+                    commit.amend_commit(None, None, None, Some(message.as_str()), None)?;
+                builder.move_subtree(commit.get_oid(), commit.get_parent_oids())?;
+                builder.replace_commit(commit.get_oid(), replacement_oid)?;
+            }
+            match builder.build(effects, &pool, &repo_pool)? {
+                Ok(Some(rebase_plan)) => rebase_plan,
+                Ok(None) => {
+                    eyre::bail!(
+                        "BUG: rebase plan indicates nothing to do, but adding Change-Ids should always do something."
+                    );
+                }
+                Err(err) => {
+                    err.describe(effects, self.repo, self.dag)?;
+                    return Ok(Err(ExitCode(1)));
+                }
+            }
+        };
+
+        let rewritten_oids = match execute_rebase_plan(
+            effects,
+            self.git_run_info,
+            self.repo,
+            self.event_log_db,
+            &rebase_plan,
+            &execute_options,
+        )? {
+            ExecuteRebasePlanResult::Succeeded {
+                rewritten_oids: Some(rewritten_oids),
+            } => rewritten_oids,
+            ExecuteRebasePlanResult::Succeeded {
+                rewritten_oids: None,
+            } => {
+                warn!("No rewritten commit OIDs were produced by rebase plan execution");
+                Default::default()
+            }
+            ExecuteRebasePlanResult::DeclinedToMerge {
+                failed_merge_info: _,
+            } => {
+                writeln!(
+                    effects.get_error_stream(),
+                    "BUG: Merge failed, but adding Change-Ids shouldn't cause any merge failures."
+                )?;
+                return Ok(Err(ExitCode(1)));
+            }
+            ExecuteRebasePlanResult::Failed { exit_code } => {
+                return Ok(Err(exit_code));
+            }
+        };
+
+        let rewritten_oids: HashMap<NonZeroOid, NonZeroOid> = rewritten_oids
+            .into_iter()
+            .filter_map(|(old_oid, new_oid)| match new_oid {
+                MaybeZeroOid::NonZero(new_oid) => Some((old_oid, new_oid)),
+                MaybeZeroOid::Zero => {
+                    warn!(?old_oid, "Commit was rewritten to the zero OID");
+                    None
+                }
+            })
+            .collect();
+        self.dag.sync_from_oids(
+            effects,
+            self.repo,
+            CommitSet::empty(),
+            rewritten_oids.values().copied().collect(),
+        )?;
+        Ok(Ok(rewritten_oids))
+    }
+
+    /// Push the given commits (and their ancestors) to `refs/for/<branch>`,
+    /// which creates a change or a new patch set for each commit. Gerrit
+    /// processes all the commits in a stack in a single push, so only the
+    /// heads of the commit set are pushed.
+    #[instrument]
+    fn push_for_review(&self, commit_set: &CommitSet, options: &SubmitOptions) -> EyreExitOr<()> {
+        let effects = self.effects;
+        let SubmitOptions {
+            create: _,
+            draft,
+            execution_strategy: _,
+            num_jobs: _,
+            message: _,
+        } = options;
+
+        let push_remote = match self.repo.get_default_push_remote()? {
+            Some(push_remote) => push_remote,
+            None => {
+                writeln!(
+                    effects.get_output_stream(),
+                    "\
+No upstream repository was associated with {} and no value was
+specified for `remote.pushDefault`, so cannot push to Gerrit.
+Configure a value with: git config remote.pushDefault <remote>
+These remotes are available: {}",
+                    CategorizedReferenceName::new(
+                        &self.repo.get_main_branch()?.get_reference_name()?,
+                    )
+                    .friendly_describe(),
+                    self.repo.get_all_remote_names()?.join(", "),
+                )?;
+                return Ok(Err(ExitCode(1)));
+            }
+        };
+        let target_ref = {
+            let target_branch_name = get_target_branch_name(self.repo)?;
+            // Marking a change as work-in-progress is Gerrit's equivalent of
+            // a draft. Omitting the option leaves the existing state as-is.
+            let push_options = if *draft { "%wip" } else { "" };
+            format!("refs/for/{target_branch_name}{push_options}")
+        };
+
+        let head_oids = self
+            .dag
+            .commit_set_to_vec(&self.dag.query_heads(commit_set.clone())?)?;
+        let event_tx_id = self
+            .event_log_db
+            .make_transaction_id(SystemTime::now(), "submit to gerrit")?;
+        let (effects, progress) = effects.start_operation(OperationType::PushCommits);
+        progress.notify_progress(0, head_oids.len());
+        for head_oid in head_oids {
+            let refspec = format!("{head_oid}:{target_ref}");
+            let args = ["push", push_remote.as_str(), refspec.as_str()];
+            match self.git_run_info.run(&effects, Some(event_tx_id), &args)? {
+                Ok(()) => {}
+                Err(exit_code) => {
+                    writeln!(
+                        effects.get_output_stream(),
+                        "Failed to push commit to Gerrit: {}",
+                        effects.get_glyphs().render(
+                            self.repo.friendly_describe_commit_from_oid(
+                                effects.get_glyphs(),
+                                head_oid
+                            )?
+                        )?,
+                    )?;
+                    return Ok(Err(exit_code));
+                }
+            }
+            progress.notify_progress_inc(1);
+        }
+        Ok(Ok(()))
+    }
+}
+
+/// Produce the message for the given commit with a `Change-Id` trailer added.
+///
+/// Gerrit's `commit-msg` hook derives the Change-Id by hashing the commit's
+/// tree, parents, identities, and message. The commit's own OID is already
+/// such a hash, so it's used directly.
+fn add_change_id_trailer(commit: &Commit) -> eyre::Result<String> {
+    let message = commit.get_message_raw();
+    let message = String::from_utf8_lossy(&message);
+    let message = message.trim_end();
+    let change_id = format!("I{}", commit.get_oid());
+    let separator = if commit.get_trailers()?.is_empty() {
+        "\n\n"
+    } else {
+        "\n"
+    };
+    Ok(format!(
+        "{message}{separator}{CHANGE_ID_TRAILER}: {change_id}\n"
+    ))
+}
+
+/// Extract the SSH arguments (port and destination) and the project name
+/// from a Gerrit SSH URL such as `ssh://user@host:29418/project` or
+/// `user@host:project`.
+fn parse_gerrit_ssh_url(url: &str) -> Option<(Vec<String>, String)> {
+    let (ssh_args, path) = match url.strip_prefix("ssh://") {
+        Some(rest) => {
+            let (authority, path) = rest.split_once('/')?;
+            let ssh_args = match authority.rsplit_once(':') {
+                Some((destination, port)) => {
+                    vec!["-p".to_owned(), port.to_owned(), destination.to_owned()]
+                }
+                None => vec![authority.to_owned()],
+            };
+            (ssh_args, path)
+        }
+        None => {
+            if url.contains("://") {
+                return None;
+            }
+            let (destination, path) = url.split_once(':')?;
+            if destination.contains('/') {
+                return None;
+            }
+            (vec![destination.to_owned()], path)
+        }
+    };
+    let project = path.trim_matches('/');
+    let project = project.strip_suffix(".git").unwrap_or(project);
+    if project.is_empty() || ssh_args.last().map_or(true, |s| s.is_empty()) {
+        None
+    } else {
+        Some((ssh_args, project.to_owned()))
+    }
+}
+
+#[allow(missing_docs)]
+mod client {
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt::{Debug, Write};
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use std::sync::Arc;
+
+    use eyre::Context;
+    use itertools::Itertools;
+    use lib::core::dag::{CommitSet, Dag};
+    use lib::core::effects::{Effects, OperationType};
+    use lib::core::eventlog::{EventLogDb, EventReplayer};
+    use lib::core::formatting::Glyphs;
+    use lib::core::repo_ext::RepoExt;
+    use lib::git::{BranchType, GitRunInfo, NonZeroOid, Repo, SerializedNonZeroOid};
+    use lib::try_exit_code;
+    use lib::util::{ExitCode, EyreExitOr};
+    use serde::{Deserialize, Serialize};
+    use tracing::{debug, instrument};
+
+    use super::get_change_id;
+
+    /// The subset of a patch set in Gerrit's query output that we use.
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct PatchSetInfo {
+        pub number: usize,
+        pub revision: SerializedNonZeroOid,
+    }
+
+    /// The subset of a change in Gerrit's query output that we use.
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ChangeInfo {
+        /// The Change-Id.
+        pub id: String,
+        pub number: usize,
+        pub url: String,
+        pub branch: String,
+        pub status: String,
+        #[serde(default)]
+        pub wip: bool,
+        pub current_patch_set: PatchSetInfo,
+    }
+
+    pub trait GerritClient: Debug {
+        /// Get the details of the open changes targeting the given branch
+        /// with any of the given Change-Ids. The resulting map is keyed by
+        /// Change-Id.
+        fn query_change_infos(
+            &self,
+            effects: &Effects,
+            branch: &str,
+            change_ids: &[String],
+        ) -> EyreExitOr<HashMap<String, ChangeInfo>>;
+    }
+
+    #[derive(Debug)]
+    pub struct RealGerritClient {
+        pub ssh_run_info: GitRunInfo,
+
+        /// The arguments to `ssh` which identify the Gerrit server, such as
+        /// `["-p", "29418", "user@host"]`.
+        pub ssh_args: Vec<String>,
+
+        /// The name of the Gerrit project.
+        pub project: String,
+    }
+
+    impl RealGerritClient {
+        #[instrument]
+        fn run_gerrit(&self, effects: &Effects, args: &[&str]) -> EyreExitOr<Vec<u8>> {
+            let GitRunInfo {
+                path_to_git: exe,
+                working_directory,
+                env,
+            } = &self.ssh_run_info;
+            let args = self
+                .ssh_args
+                .iter()
+                .map(|arg| arg.as_str())
+                .chain(["gerrit"])
+                .chain(args.iter().copied())
+                .collect_vec();
+            let exe_invocation = format!("{} {}", exe.to_string_lossy(), args.join(" "));
+            debug!(?exe_invocation, "Invoking gerrit");
+            let (effects, progress) =
+                effects.start_operation(OperationType::RunTests(Arc::new(exe_invocation.clone())));
+            let _progress = progress;
+
+            let child = Command::new(exe)
+                .args(args)
+                .current_dir(working_directory)
+                .envs(env.iter())
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .context("Invoking `ssh` command-line executable")?;
+            let output = child
+                .wait_with_output()
+                .context("Waiting for `ssh` invocation")?;
+            if !output.status.success() {
+                writeln!(
+                    effects.get_output_stream(),
+                    "Call to `{exe_invocation}` failed",
+                )?;
+                writeln!(effects.get_output_stream(), "Stdout:")?;
+                writeln!(
+                    effects.get_output_stream(),
+                    "{}",
+                    String::from_utf8_lossy(&output.stdout)
+                )?;
+                writeln!(effects.get_output_stream(), "Stderr:")?;
+                writeln!(
+                    effects.get_output_stream(),
+                    "{}",
+                    String::from_utf8_lossy(&output.stderr)
+                )?;
+                return Ok(Err(ExitCode::try_from(output.status)?));
+            }
+            Ok(Ok(output.stdout))
+        }
+    }
+
+    impl GerritClient for RealGerritClient {
+        #[instrument]
+        fn query_change_infos(
+            &self,
+            effects: &Effects,
+            branch: &str,
+            change_ids: &[String],
+        ) -> EyreExitOr<HashMap<String, ChangeInfo>> {
+            let query = format!(
+                "status:open project:{} branch:{branch} ({})",
+                self.project,
+                change_ids
+                    .iter()
+                    .map(|change_id| format!("change:{change_id}"))
+                    .join(" OR ")
+            );
+            let mut args = vec!["query", "--format=JSON", "--current-patch-set"];
+            args.extend(query.split_whitespace());
+            let output = try_exit_code!(self.run_gerrit(effects, &args)?);
+
+            // The output has one JSON object per line, followed by a
+            // statistics object.
+            let mut change_infos = HashMap::new();
+            for line in output.split(|c| *c == b'\n') {
+                if line.iter().all(|c| c.is_ascii_whitespace()) {
+                    continue;
+                }
+                let value: serde_json::Value = serde_json::from_slice(line)
+                    .wrap_err("Deserializing output from gerrit query")?;
+                match value.get("type").and_then(|type_| type_.as_str()) {
+                    Some("stats") => continue,
+                    Some("error") => {
+                        writeln!(
+                            effects.get_output_stream(),
+                            "Gerrit query failed: {}",
+                            value
+                                .get("message")
+                                .and_then(|message| message.as_str())
+                                .unwrap_or_default()
+                        )?;
+                        return Ok(Err(ExitCode(1)));
+                    }
+                    _ => {}
+                }
+                let change_info: ChangeInfo = serde_json::from_value(value)
+                    .wrap_err("Deserializing change from gerrit query")?;
+                change_infos.insert(change_info.id.clone(), change_info);
+            }
+            Ok(Ok(change_infos))
+        }
+    }
+
+    /// The mock state on disk, representing the remote Gerrit server.
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    pub struct MockState {
+        /// The number to assign the next newly-created change.
+        pub change_index: usize,
+
+        /// Information about all changes for the repository, keyed by
+        /// Change-Id. Sorted for determinism when dumping state for testing.
+        pub changes: BTreeMap<String, ChangeInfo>,
+    }
+
+    impl MockState {
+        fn load(path: &Path) -> eyre::Result<Self> {
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(Default::default());
+                }
+                Err(err) => return Err(err).wrap_err("Opening mock Gerrit client state file"),
+            };
+            let state = serde_json::from_reader(file)?;
+            Ok(state)
+        }
+
+        /// Emulate Gerrit's processing of pushes to `refs/for/<branch>`: each
+        /// pushed commit creates a change or a new patch set on an existing
+        /// change, and the magic reference itself is discarded. Afterwards,
+        /// changes are marked as merged once their current patch set has been
+        /// incorporated into their target branch.
+        fn restore_invariants(&mut self, remote_repo: &Repo) -> eyre::Result<()> {
+            let effects = Effects::new_suppress_for_test(Glyphs::text());
+            let conn = remote_repo.get_db_conn()?;
+            let event_log_db = EventLogDb::new(&conn)?;
+            let event_replayer =
+                EventReplayer::from_event_log_db(&effects, remote_repo, &event_log_db)?;
+            let event_cursor = event_replayer.make_default_cursor();
+            let references_snapshot = remote_repo.get_references_snapshot()?;
+            let mut dag = Dag::open_and_sync(
+                &effects,
+                remote_repo,
+                &event_replayer,
+                event_cursor,
+                &references_snapshot,
+            )?;
+
+            let mut pushed_references = Vec::new();
+            for reference in remote_repo.get_all_references()? {
+                let reference_name = reference.get_name()?;
+                if let Some(target) = reference_name.as_str().strip_prefix("refs/for/") {
+                    let (branch, push_options) = match target.split_once('%') {
+                        Some((branch, push_options)) => (branch.to_owned(), push_options),
+                        None => (target.to_owned(), ""),
+                    };
+                    let wip = push_options.split(',').contains(&"wip");
+                    pushed_references.push((reference, branch, wip));
+                }
+            }
+
+            for (mut reference, branch, wip) in pushed_references {
+                let head_oid = match reference.peel_to_commit()? {
+                    Some(commit) => commit.get_oid(),
+                    None => eyre::bail!("Pushed reference does not point to a commit"),
+                };
+                let branch_oid = match remote_repo.find_branch(&branch, BranchType::Local)? {
+                    Some(branch) => match branch.get_oid()? {
+                        Some(branch_oid) => branch_oid,
+                        None => eyre::bail!("Target branch {branch:?} does not point to a commit"),
+                    },
+                    None => eyre::bail!("Could not find target branch {branch:?}"),
+                };
+                dag.sync_from_oids(
+                    &effects,
+                    remote_repo,
+                    CommitSet::empty(),
+                    CommitSet::from(head_oid),
+                )?;
+                let commit_oids = dag.sort(
+                    &dag.query_only(CommitSet::from(head_oid), CommitSet::from(branch_oid))?,
+                )?;
+                for commit_oid in commit_oids {
+                    let commit = remote_repo.find_commit_or_fail(commit_oid)?;
+                    let change_id = match get_change_id(&commit)? {
+                        Some(change_id) => change_id,
+                        None => eyre::bail!("Commit {commit_oid} is missing a Change-Id"),
+                    };
+                    match self.changes.get_mut(&change_id) {
+                        Some(change_info) => {
+                            let patch_set = &mut change_info.current_patch_set;
+                            if patch_set.revision.0 != commit_oid {
+                                patch_set.number += 1;
+                                patch_set.revision = SerializedNonZeroOid(commit_oid);
+                            }
+                        }
+                        None => {
+                            self.change_index += 1;
+                            let change_info = ChangeInfo {
+                                id: change_id.clone(),
+                                number: self.change_index,
+                                url: format!(
+                                    "https://gerrit.example.com/c/{}/+/{}",
+                                    MockGerritClient::project(),
+                                    self.change_index
+                                ),
+                                branch: branch.clone(),
+                                status: "NEW".to_owned(),
+                                wip,
+                                current_patch_set: PatchSetInfo {
+                                    number: 1,
+                                    revision: SerializedNonZeroOid(commit_oid),
+                                },
+                            };
+                            self.changes.insert(change_id, change_info);
+                        }
+                    }
+                }
+                reference.delete()?;
+            }
+
+            let branches: HashMap<String, NonZeroOid> = remote_repo
+                .get_all_local_branches()?
+                .into_iter()
+                .map(|branch| -> eyre::Result<_> {
+                    let branch_name = branch.get_name()?.to_owned();
+                    let branch_oid = branch.get_oid()?.unwrap();
+                    Ok((branch_name, branch_oid))
+                })
+                .try_collect()?;
+            for change_info in self.changes.values_mut() {
+                let branch_oid = match branches.get(&change_info.branch) {
+                    Some(branch_oid) => *branch_oid,
+                    None => {
+                        eyre::bail!(
+                            "Could not find target branch {:?} for change: {change_info:?}",
+                            change_info.branch
+                        );
+                    }
+                };
+                let SerializedNonZeroOid(revision) = change_info.current_patch_set.revision;
+                if dag.query_is_ancestor(revision, branch_oid)? {
+                    change_info.status = "MERGED".to_owned();
+                }
+            }
+            Ok(())
+        }
+
+        fn save(&self, path: &Path) -> eyre::Result<()> {
+            let state = serde_json::to_string_pretty(self)?;
+            fs::write(path, state)?;
+            Ok(())
+        }
+    }
+
+    /// A mock client representing the remote Gerrit server.
+    #[derive(Debug)]
+    pub struct MockGerritClient {
+        /// The path to the remote repository on disk.
+        pub remote_repo_path: PathBuf,
+    }
+
+    impl GerritClient for MockGerritClient {
+        fn query_change_infos(
+            &self,
+            _effects: &Effects,
+            branch: &str,
+            change_ids: &[String],
+        ) -> EyreExitOr<HashMap<String, ChangeInfo>> {
+            let change_infos = self.with_state_mut(|state| {
+                let change_infos = change_ids
+                    .iter()
+                    .filter_map(|change_id| state.changes.get(change_id))
+                    .filter(|change_info| {
+                        change_info.status == "NEW" && change_info.branch == branch
+                    })
+                    .map(|change_info| (change_info.id.clone(), change_info.clone()))
+                    .collect();
+                Ok(change_infos)
+            })?;
+            Ok(Ok(change_infos))
+        }
+    }
+
+    impl MockGerritClient {
+        fn project() -> &'static str {
+            "mock-gerrit-project"
+        }
+
+        /// Get the path on disk where the mock state is stored.
+        pub fn state_path(&self) -> PathBuf {
+            self.remote_repo_path.join("mock-gerrit-client-state.json")
+        }
+
+        /// Load the mock state from disk, run the given function, and then save
+        /// the state back to disk. Pushes to the remote repository are
+        /// processed before and after running the function.
+        pub fn with_state_mut<T>(
+            &self,
+            f: impl FnOnce(&mut MockState) -> eyre::Result<T>,
+        ) -> eyre::Result<T> {
+            let repo = Repo::from_dir(&self.remote_repo_path)?;
+            let state_path = self.state_path();
+            let mut state = MockState::load(&state_path)?;
+            state.restore_invariants(&repo)?;
+            let result = f(&mut state)?;
+            state.restore_invariants(&repo)?;
+            state.save(&state_path)?;
+            Ok(result)
+        }
+    }
+}
+
+/// Testing utilities.
+pub mod testing {
+    pub use super::client::MockGerritClient;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_gerrit_ssh_url;
+
+    #[test]
+    fn test_parse_gerrit_ssh_url() {
+        assert_eq!(
+            parse_gerrit_ssh_url("ssh://jdoe@review.example.com:29418/tools/gerrit"),
+            Some((
+                vec![
+                    "-p".to_owned(),
+                    "29418".to_owned(),
+                    "jdoe@review.example.com".to_owned()
+                ],
+                "tools/gerrit".to_owned()
+            ))
+        );
+        assert_eq!(
+            parse_gerrit_ssh_url("review.example.com:project.git"),
+            Some((vec!["review.example.com".to_owned()], "project".to_owned()))
+        );
+        assert_eq!(
+            parse_gerrit_ssh_url("https://review.example.com/project"),
+            None
+        );
+        assert_eq!(parse_gerrit_ssh_url("/path/to/project"), None);
+    }
+}
@@ -22,7 +22,7 @@ use lib::git::GitErrorCode;
 use lib::git::GitRunInfo;
 use lib::git::RepoError;
 use lib::git::{BranchType, ConfigRead};
-use lib::git::{NonZeroOid, Repo};
+use lib::git::{NonZeroOid, Repo, ReviewInfo};
 use lib::try_exit_code;
 use lib::util::ExitCode;
 use lib::util::EyreExitOr;
@@ -33,14 +33,14 @@ use tracing::warn;
 
 use crate::branch_forge::BranchForge;
 use crate::SubmitStatus;
-use crate::{CommitStatus, CreateStatus, Forge, SubmitOptions};
+use crate::{CommitStatus, CreateStatus, Forge, LandSupport, SubmitOptions};
 
 /// Testing environment variable. When this is set, the executable will use the
 /// mock Github implementation. This should be set to the path of an existing
 /// repository that represents the remote/Github.
 pub const MOCK_REMOTE_REPO_PATH_ENV_KEY: &str = "BRANCHLESS_SUBMIT_GITHUB_MOCK_REMOTE_REPO_PATH";
 
-fn commit_summary_slug(summary: &str) -> String {
+pub(crate) fn commit_summary_slug(summary: &str) -> String {
     let summary_slug: String = summary
         .chars()
         .map(|c| if c.is_alphanumeric() { c } else { '-' })
@@ -58,7 +58,7 @@ fn commit_summary_slug(summary: &str) -> String {
     }
 }
 
-fn singleton<K: Debug + Eq + Hash, V: Clone>(
+pub(crate) fn singleton<K: Debug + Eq + Hash, V: Clone>(
     map: &HashMap<K, V>,
     key: K,
     f: impl Fn(V) -> V,
@@ -75,6 +75,42 @@ fn singleton<K: Debug + Eq + Hash, V: Clone>(
     result
 }
 
+/// Marks the start of the generated block in a pull request body which lists
+/// the pull requests in the stack.
+const STACK_BLOCK_START: &str = "<!-- git-branchless: stack start -->";
+
+/// Marks the end of the generated block in a pull request body which lists the
+/// pull requests in the stack.
+const STACK_BLOCK_END: &str = "<!-- git-branchless: stack end -->";
+
+/// Replace the generated stack block in the given pull request body with
+/// `stack_block`, leaving any text outside of the block as-is. If the body
+/// doesn't have a stack block yet, then one is added to the top.
+fn replace_stack_block(body: &str, stack_block: &str) -> String {
+    if let Some(start_index) = body.find(STACK_BLOCK_START) {
+        if let Some(end_offset) = body[start_index..].find(STACK_BLOCK_END) {
+            let end_index = start_index + end_offset + STACK_BLOCK_END.len();
+            return format!(
+                "{}{stack_block}{}",
+                &body[..start_index],
+                &body[end_index..]
+            );
+        }
+    }
+
+    // Pull requests created by previous versions start with an unmarked stack
+    // list, separated from the rest of the body by a horizontal rule.
+    let body = match body.strip_prefix("**Stack:**") {
+        Some(rest) => match rest.split_once("\n---") {
+            Some((_stack_list, rest)) => rest,
+            None => body,
+        },
+        None => body,
+    };
+    let body = body.trim_start_matches(['\r', '\n']);
+    format!("{stack_block}\n\n{body}")
+}
+
 /// Get the name of the remote repository to push to in the course of creating
 /// pull requests.
 ///
@@ -103,8 +139,70 @@ pub fn github_push_remote(repo: &Repo) -> eyre::Result<Option<String>> {
     Ok(None)
 }
 
+/// Get the token to use when calling the GitHub API directly, if any. This is
+/// read from the `branchless.submit.github.token` config key, or else from the
+/// `GITHUB_TOKEN` or `GH_TOKEN` environment variables.
+pub fn github_api_token(repo: &Repo) -> eyre::Result<Option<String>> {
+    let config = repo.get_readonly_config()?;
+    if let Some(token) = config.get::<String, _>("branchless.submit.github.token")? {
+        return Ok(Some(token));
+    }
+    for env_key in ["GITHUB_TOKEN", "GH_TOKEN"] {
+        if let Ok(token) = env::var(env_key) {
+            if !token.is_empty() {
+                return Ok(Some(token));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Get the base URL for GitHub REST API calls. This is read from the
+/// `branchless.submit.github.apiUrl` config key, or else from the
+/// `GITHUB_API_URL` environment variable, and defaults to the public GitHub
+/// API. For GitHub Enterprise, this is typically `https://<host>/api/v3`.
+pub fn github_api_base_url(repo: &Repo) -> eyre::Result<String> {
+    let config = repo.get_readonly_config()?;
+    let api_base_url = match config.get::<String, _>("branchless.submit.github.apiUrl")? {
+        Some(api_base_url) => api_base_url,
+        None => env::var("GITHUB_API_URL").unwrap_or_else(|_| "https://api.github.com".to_owned()),
+    };
+    Ok(api_base_url.trim_end_matches('/').to_owned())
+}
+
+/// Determine the owner and name of the GitHub repository associated with the
+/// push remote, based on its URL.
+pub fn github_repository(repo: &Repo) -> eyre::Result<Option<(String, String)>> {
+    let remote_name = match github_push_remote(repo)? {
+        Some(remote_name) => remote_name,
+        None => match repo.get_default_push_remote()? {
+            Some(remote_name) => remote_name,
+            None => return Ok(None),
+        },
+    };
+    let config = repo.get_readonly_config()?;
+    let remote_url: Option<String> = config.get(format!("remote.{remote_name}.url"))?;
+    Ok(remote_url.as_deref().and_then(parse_github_repository))
+}
+
+/// Extract the owner and repository name from a remote URL such as
+/// `git@github.com:owner/repo.git` or `https://github.com/owner/repo`.
+fn parse_github_repository(remote_url: &str) -> Option<(String, String)> {
+    let path = remote_url.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let mut components = path.rsplit(|c| c == '/' || c == ':');
+    let repo_name = components.next()?;
+    let owner = components.next()?;
+    if owner.is_empty() || repo_name.is_empty() {
+        None
+    } else {
+        Some((owner.to_owned(), repo_name.to_owned()))
+    }
+}
+
 /// The [GitHub](https://en.wikipedia.org/wiki/GitHub) code hosting platform.
-/// This forge integrates specifically with the `gh` command-line utility.
+/// This forge integrates either with the `gh` command-line utility or directly
+/// with the GitHub API; see [`GithubForge::client`].
 #[allow(missing_docs)]
 #[derive(Debug)]
 pub struct GithubForge<'a> {
@@ -351,6 +449,7 @@ impl Forge for GithubForge<'_> {
                 client::CreatePullRequestArgs {
                     head_ref_oid: commit_oid,
                     head_ref_name: local_branch_name.clone(),
+                    base_ref_name: get_main_branch_name(self.repo)?,
                     title,
                     body,
                 },
@@ -358,6 +457,30 @@ impl Forge for GithubForge<'_> {
             )?);
         }
 
+        // Adding pull requests to a stack changes the stack block in the bodies
+        // of the other pull requests in the stack, so update those as well.
+        // Pull requests with local commits which haven't been pushed yet are
+        // left alone, since the caller didn't ask to update them.
+        let mut commit_statuses = commit_statuses;
+        let created_commit_set: CommitSet = commit_statuses.keys().copied().collect();
+        let stack_commit_set = self.dag.query_stack_commits(created_commit_set.clone())?;
+        let pull_request_infos =
+            try_exit_code!(self.client.query_repo_pull_request_infos(effects)?);
+        for (commit_oid, commit_status) in try_exit_code!(self.query_status(stack_commit_set)?) {
+            if commit_statuses.contains_key(&commit_oid) {
+                continue;
+            }
+            let is_pushed = commit_status
+                .remote_commit_name
+                .as_ref()
+                .and_then(|remote_branch_name| pull_request_infos.get(remote_branch_name))
+                .map_or(false, |pull_request_info| {
+                    pull_request_info.head_ref_oid.0 == commit_oid
+                });
+            if matches!(commit_status.submit_status, SubmitStatus::NeedsUpdate) && is_pushed {
+                commit_statuses.insert(commit_oid, commit_status);
+            }
+        }
         try_exit_code!(self.update(commit_statuses, options)?);
 
         Ok(Ok(created_branches))
@@ -437,14 +560,15 @@ impl Forge for GithubForge<'_> {
                         &pull_request_infos,
                         commit_oid
                     )?);
-                let updated_fields = {
-                    let fields = updated_pull_request_info.fields_to_update(pull_request_info);
-                    if fields.is_empty() {
-                        "none (this should not happen)".to_owned()
-                    } else {
-                        fields.join(", ")
-                    }
-                };
+                let updated_fields = updated_pull_request_info.fields_to_update(pull_request_info);
+                if updated_fields.is_empty() {
+                    // This can happen if the pull request was already updated
+                    // while creating other pull requests in its stack.
+                    debug!(?commit_oid, "Pull request is already up-to-date");
+                    progress.notify_progress_inc(1);
+                    continue;
+                }
+                let updated_fields = updated_fields.join(", ");
                 let client::UpdatePullRequestArgs {
                     head_ref_oid: _, // Updated by `branch_forge.update`.
                     base_ref_name,
@@ -490,29 +614,82 @@ impl Forge for GithubForge<'_> {
 
         Ok(Ok(()))
     }
+
+    #[instrument]
+    fn query_review_infos(
+        &mut self,
+        commit_set: CommitSet,
+    ) -> EyreExitOr<Option<HashMap<NonZeroOid, ReviewInfo>>> {
+        let pull_request_infos =
+            try_exit_code!(self.client.query_repo_pull_request_infos(self.effects)?);
+        let mut result = HashMap::new();
+        for branch in self.repo.get_all_local_branches()? {
+            let local_branch_oid = match branch.get_oid()? {
+                Some(branch_oid) => branch_oid,
+                None => continue,
+            };
+            if !self.dag.set_contains(&commit_set, local_branch_oid)? {
+                continue;
+            }
+            if let Some(pull_request_info) = branch
+                .get_upstream_branch_name_without_push_remote_name()?
+                .and_then(|remote_branch_name| pull_request_infos.get(&remote_branch_name))
+                .filter(|pull_request_info| !pull_request_info.closed)
+            {
+                result.insert(local_branch_oid, pull_request_info.to_review_info());
+            }
+        }
+        Ok(Ok(Some(result)))
+    }
+
+    fn land_support(&self) -> LandSupport {
+        LandSupport::Supported
+    }
 }
 
 impl GithubForge<'_> {
     /// Construct a real or mock GitHub client according to the environment.
-    pub fn client(git_run_info: GitRunInfo) -> Box<dyn client::GithubClient> {
-        match env::var(MOCK_REMOTE_REPO_PATH_ENV_KEY) {
-            Ok(path) => Box::new(client::MockGithubClient {
+    ///
+    /// If a GitHub API token is configured (see [`github_api_token`]), the
+    /// client will call the GitHub API directly. Otherwise, it will invoke the
+    /// `gh` command-line utility.
+    pub fn client(
+        git_run_info: GitRunInfo,
+        repo: &Repo,
+    ) -> eyre::Result<Box<dyn client::GithubClient>> {
+        if let Ok(path) = env::var(MOCK_REMOTE_REPO_PATH_ENV_KEY) {
+            return Ok(Box::new(client::MockGithubClient {
                 remote_repo_path: path.into(),
-            }),
-            Err(_) => {
-                let GitRunInfo {
-                    path_to_git: _,
-                    working_directory,
-                    env,
-                } = git_run_info;
-                let gh_run_info = GitRunInfo {
-                    path_to_git: "gh".into(),
-                    working_directory: working_directory.clone(),
-                    env: env.clone(),
-                };
-                Box::new(client::RealGithubClient { gh_run_info })
+            }));
+        }
+
+        if let Some(token) = github_api_token(repo)? {
+            match github_repository(repo)? {
+                Some((owner, repo_name)) => {
+                    return Ok(Box::new(client::RestGithubClient {
+                        api_base_url: github_api_base_url(repo)?,
+                        owner,
+                        repo_name,
+                        token,
+                    }));
+                }
+                None => {
+                    warn!("Could not determine GitHub repository from push remote URL, falling back to `gh`");
+                }
             }
         }
+
+        let GitRunInfo {
+            path_to_git: _,
+            working_directory,
+            env,
+        } = git_run_info;
+        let gh_run_info = GitRunInfo {
+            path_to_git: "gh".into(),
+            working_directory: working_directory.clone(),
+            env: env.clone(),
+        };
+        Ok(Box::new(client::RealGithubClient { gh_run_info }))
     }
 
     #[instrument]
@@ -637,13 +814,24 @@ impl GithubForge<'_> {
             }
         };
 
-        let stack_list = {
+        let stack_block = {
             let mut result = String::new();
-            for stack_pull_request_info in stack_pull_request_infos.values() {
+            writeln!(result, "{STACK_BLOCK_START}")?;
+            writeln!(result, "**Stack:**")?;
+            writeln!(result)?;
+            for (stack_commit_oid, stack_pull_request_info) in stack_pull_request_infos.iter() {
                 // Github will render a lone pull request URL as a title and
                 // open/closed status.
-                writeln!(result, "* {}", stack_pull_request_info.url)?;
+                let marker = if *stack_commit_oid == commit_oid {
+                    "➡️ "
+                } else {
+                    ""
+                };
+                writeln!(result, "* {marker}{}", stack_pull_request_info.url)?;
             }
+            writeln!(result)?;
+            writeln!(result, "---")?;
+            write!(result, "{STACK_BLOCK_END}")?;
             result
         };
 
@@ -651,19 +839,17 @@ impl GithubForge<'_> {
         let commit_summary = commit.get_summary()?;
         let commit_summary = String::from_utf8_lossy(&commit_summary).into_owned();
         let title = format!("[{stack_index}/{stack_size}] {commit_summary}");
-        let commit_message = commit.get_message_pretty();
-        let commit_message = String::from_utf8_lossy(&commit_message);
-        let body = format!(
-            "\
-**Stack:**
-
-{stack_list}
-
----
-
-{commit_message}
-"
-        );
+        // The rest of the body is initialized from the commit message, but
+        // belongs to the author afterwards, so only the stack block is kept up
+        // to date.
+        let body = match get_pull_request_info(commit_oid)? {
+            Some(pull_request_info) => replace_stack_block(&pull_request_info.body, &stack_block),
+            None => {
+                let commit_message = commit.get_message_pretty();
+                let commit_message = String::from_utf8_lossy(&commit_message);
+                replace_stack_block(&commit_message, &stack_block)
+            }
+        };
 
         let stack_ancestor_oids = {
             let main_branch_oid = CommitSet::from(references_snapshot.main_branch_oid);
@@ -697,6 +883,7 @@ impl GithubForge<'_> {
     }
 }
 
+#[allow(missing_docs)]
 mod client {
     use std::collections::{BTreeMap, HashMap};
     use std::fmt::{Debug, Write};
@@ -712,9 +899,12 @@ mod client {
     use lib::core::eventlog::{EventLogDb, EventReplayer};
     use lib::core::formatting::Glyphs;
     use lib::core::repo_ext::RepoExt;
-    use lib::git::{GitRunInfo, NonZeroOid, Repo, SerializedNonZeroOid};
+    use lib::git::{
+        ChecksState, GitRunInfo, NonZeroOid, Repo, ReviewInfo, ReviewState, SerializedNonZeroOid,
+    };
     use lib::try_exit_code;
     use lib::util::{ExitCode, EyreExitOr};
+    use serde::de::DeserializeOwned;
     use serde::{Deserialize, Serialize};
     use tempfile::NamedTempFile;
     use tracing::{debug, instrument};
@@ -741,12 +931,69 @@ mod client {
         pub title: String,
         #[serde(rename = "body")]
         pub body: String,
+        #[serde(
+            rename = "reviewDecision",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub review_decision: Option<String>,
+        /// The combined state of the CI checks for the head commit: one of
+        /// `SUCCESS`, `FAILURE`, or `PENDING`.
+        #[serde(
+            rename = "checksState",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub checks_state: Option<String>,
+    }
+
+    impl PullRequestInfo {
+        /// Convert into the forge-independent representation stored in the
+        /// review info cache.
+        pub fn to_review_info(&self) -> ReviewInfo {
+            let review_state = if self.is_draft {
+                Some(ReviewState::Draft)
+            } else {
+                match self.review_decision.as_deref() {
+                    Some("APPROVED") => Some(ReviewState::Approved),
+                    Some("CHANGES_REQUESTED") => Some(ReviewState::ChangesRequested),
+                    Some("REVIEW_REQUIRED") => Some(ReviewState::ReviewRequired),
+                    // GitHub only reports a review decision if the base
+                    // branch requires reviews.
+                    None => Some(ReviewState::NotRequired),
+                    Some(_) => None,
+                }
+            };
+            let checks_state = match self.checks_state.as_deref() {
+                Some("SUCCESS") => Some(ChecksState::Success),
+                Some("FAILURE") => Some(ChecksState::Failure),
+                Some("PENDING") => Some(ChecksState::Pending),
+                _ => None,
+            };
+            ReviewInfo {
+                name: format!("#{}", self.number),
+                url: Some(self.url.clone()),
+                review_state,
+                checks_state,
+            }
+        }
+    }
+
+    /// Normalize a GitHub `StatusState` or `CheckConclusionState` into one of
+    /// `SUCCESS`, `FAILURE`, or `PENDING`.
+    fn normalize_checks_state(state: &str) -> &'static str {
+        match state {
+            "SUCCESS" | "NEUTRAL" | "SKIPPED" => "SUCCESS",
+            "PENDING" | "EXPECTED" => "PENDING",
+            _ => "FAILURE",
+        }
     }
 
     #[derive(Debug)]
     pub struct CreatePullRequestArgs {
         pub head_ref_oid: NonZeroOid,
         pub head_ref_name: String,
+        pub base_ref_name: String,
         pub title: String,
         pub body: String,
     }
@@ -771,6 +1018,8 @@ mod client {
                 is_draft: _,
                 title: old_title,
                 body: old_body,
+                review_decision: _,
+                checks_state: _,
             } = pull_request_info;
             let Self {
                 head_ref_oid: new_head_ref_oid,
@@ -909,14 +1158,21 @@ mod client {
                     "--author",
                     "@me",
                     "--json",
-                    "number,url,headRefName,headRefOid,baseRefName,closed,isDraft,title,body",
+                    "number,url,headRefName,headRefOid,baseRefName,closed,isDraft,title,body,reviewDecision,statusCheckRollup",
                 ]
             )?);
-            let pull_request_infos: Vec<PullRequestInfo> =
+            let pull_request_infos: Vec<GhPullRequestInfo> =
                 serde_json::from_slice(&output).wrap_err("Deserializing output from gh pr list")?;
             let pull_request_infos = pull_request_infos
                 .into_iter()
-                .map(|item| (item.head_ref_name.clone(), item))
+                .map(|item| {
+                    let GhPullRequestInfo {
+                        mut info,
+                        status_check_rollup,
+                    } = item;
+                    info.checks_state = GhStatusCheck::rollup(&status_check_rollup);
+                    (info.head_ref_name.clone(), info)
+                })
                 .collect();
             Ok(Ok(pull_request_infos))
         }
@@ -931,6 +1187,7 @@ mod client {
             let CreatePullRequestArgs {
                 head_ref_oid: _,
                 head_ref_name,
+                base_ref_name,
                 title,
                 body,
             } = args;
@@ -940,6 +1197,8 @@ mod client {
                 "create",
                 "--head",
                 &head_ref_name,
+                "--base",
+                &base_ref_name,
                 "--title",
                 &title,
                 "--body-file",
@@ -1004,6 +1263,373 @@ mod client {
         }
     }
 
+    /// The version of the GitHub REST API that requests are written against.
+    const GITHUB_API_VERSION: &str = "2022-11-28";
+
+    /// Query all open pull requests authored by the current user in the given
+    /// repository with a single (paginated) GraphQL search, rather than looking
+    /// up each pull request individually.
+    const SEARCH_PULL_REQUESTS_QUERY: &str = "\
+query($query: String!, $cursor: String) {
+  search(query: $query, type: ISSUE, first: 100, after: $cursor) {
+    pageInfo { hasNextPage endCursor }
+    nodes {
+      ... on PullRequest {
+        number url headRefName headRefOid baseRefName closed isDraft title body
+        reviewDecision
+        commits(last: 1) { nodes { commit { statusCheckRollup { state } } } }
+      }
+    }
+  }
+}";
+
+    /// A pull request as returned by `gh pr list`, which reports each CI check
+    /// individually.
+    #[derive(Debug, Deserialize)]
+    struct GhPullRequestInfo {
+        #[serde(flatten)]
+        info: PullRequestInfo,
+        #[serde(rename = "statusCheckRollup", default)]
+        status_check_rollup: Vec<GhStatusCheck>,
+    }
+
+    /// Either a `CheckRun` (with `status` and `conclusion`) or a
+    /// `StatusContext` (with `state`).
+    #[derive(Debug, Deserialize)]
+    struct GhStatusCheck {
+        #[serde(default)]
+        status: Option<String>,
+        #[serde(default)]
+        conclusion: Option<String>,
+        #[serde(default)]
+        state: Option<String>,
+    }
+
+    impl GhStatusCheck {
+        fn rollup(checks: &[GhStatusCheck]) -> Option<String> {
+            let states: Vec<&str> = checks
+                .iter()
+                .map(|check| {
+                    match (
+                        check.status.as_deref(),
+                        check.conclusion.as_deref(),
+                        check.state.as_deref(),
+                    ) {
+                        (Some(status), _, _) if status != "COMPLETED" => "PENDING",
+                        (_, Some(state), _) | (_, None, Some(state)) => {
+                            normalize_checks_state(state)
+                        }
+                        (_, None, None) => "PENDING",
+                    }
+                })
+                .collect();
+            ["FAILURE", "PENDING", "SUCCESS"]
+                .into_iter()
+                .find(|state| states.contains(state))
+                .map(|state| state.to_owned())
+        }
+    }
+
+    /// A pull request as returned by the GraphQL API, which reports the
+    /// combined CI status on the head commit.
+    #[derive(Debug, Deserialize)]
+    struct GraphqlPullRequestInfo {
+        #[serde(flatten)]
+        info: PullRequestInfo,
+        #[serde(default)]
+        commits: Option<GraphqlConnection<GraphqlPullRequestCommit>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct GraphqlConnection<T> {
+        nodes: Vec<T>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct GraphqlPullRequestCommit {
+        commit: GraphqlCommit,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct GraphqlCommit {
+        #[serde(rename = "statusCheckRollup")]
+        status_check_rollup: Option<GraphqlStatusCheckRollup>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct GraphqlStatusCheckRollup {
+        state: String,
+    }
+
+    impl From<GraphqlPullRequestInfo> for PullRequestInfo {
+        fn from(value: GraphqlPullRequestInfo) -> Self {
+            let GraphqlPullRequestInfo { mut info, commits } = value;
+            info.checks_state = commits
+                .and_then(|commits| commits.nodes.into_iter().last())
+                .and_then(|node| node.commit.status_check_rollup)
+                .map(|rollup| normalize_checks_state(&rollup.state).to_owned());
+            info
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct GraphqlResponse<T> {
+        data: Option<T>,
+        #[serde(default)]
+        errors: Vec<GraphqlError>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct GraphqlError {
+        message: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct SearchPullRequestsData {
+        search: SearchPullRequestsConnection,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct SearchPullRequestsConnection {
+        #[serde(rename = "pageInfo")]
+        page_info: PageInfo,
+        nodes: Vec<GraphqlPullRequestInfo>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct PageInfo {
+        #[serde(rename = "hasNextPage")]
+        has_next_page: bool,
+        #[serde(rename = "endCursor")]
+        end_cursor: Option<String>,
+    }
+
+    /// A client which calls the GitHub REST and GraphQL APIs directly, rather
+    /// than invoking the `gh` command-line utility.
+    pub struct RestGithubClient {
+        /// The base URL for REST API calls, such as `https://api.github.com`,
+        /// or `https://<host>/api/v3` for GitHub Enterprise.
+        pub api_base_url: String,
+
+        /// The owner of the GitHub repository.
+        pub owner: String,
+
+        /// The name of the GitHub repository.
+        pub repo_name: String,
+
+        /// The token used to authenticate API calls.
+        pub token: String,
+    }
+
+    impl Debug for RestGithubClient {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "<RestGithubClient api_base_url={:?} owner={:?} repo_name={:?} token=not shown>",
+                self.api_base_url, self.owner, self.repo_name
+            )
+        }
+    }
+
+    impl RestGithubClient {
+        /// The GraphQL endpoint lives next to the REST endpoint: at
+        /// `https://api.github.com/graphql` for the public API, and at
+        /// `https://<host>/api/graphql` for GitHub Enterprise.
+        fn graphql_url(&self) -> String {
+            let base_url = self
+                .api_base_url
+                .strip_suffix("/v3")
+                .unwrap_or(&self.api_base_url);
+            format!("{base_url}/graphql")
+        }
+
+        fn repo_url(&self) -> String {
+            format!(
+                "{}/repos/{}/{}",
+                self.api_base_url, self.owner, self.repo_name
+            )
+        }
+
+        #[instrument]
+        fn call_api<T: DeserializeOwned>(
+            &self,
+            effects: &Effects,
+            method: &str,
+            url: &str,
+            body: Option<serde_json::Value>,
+        ) -> EyreExitOr<T> {
+            let request_description = format!("{method} {url}");
+            debug!(?request_description, "Calling GitHub API");
+            let (effects, progress) = effects.start_operation(OperationType::CallForgeApi(
+                Arc::new(request_description.clone()),
+            ));
+            let _progress = progress;
+
+            let request = ureq::request(method, url)
+                .set("Accept", "application/vnd.github+json")
+                .set("Authorization", &format!("Bearer {}", self.token))
+                .set("User-Agent", "git-branchless")
+                .set("X-GitHub-Api-Version", GITHUB_API_VERSION);
+            let result = match body {
+                Some(body) => request
+                    .set("Content-Type", "application/json")
+                    .send_string(&body.to_string()),
+                None => request.call(),
+            };
+            let response = match result {
+                Ok(response) => response,
+                Err(ureq::Error::Status(status, response)) => {
+                    writeln!(
+                        effects.get_output_stream(),
+                        "Call to `{request_description}` failed with status {status}:",
+                    )?;
+                    writeln!(
+                        effects.get_output_stream(),
+                        "{}",
+                        response.into_string().unwrap_or_default()
+                    )?;
+                    return Ok(Err(ExitCode(1)));
+                }
+                Err(err @ ureq::Error::Transport(_)) => {
+                    return Err(err)
+                        .wrap_err_with(|| format!("Calling GitHub API: {request_description}"));
+                }
+            };
+            let response = response
+                .into_string()
+                .wrap_err("Reading GitHub API response")?;
+            let response = serde_json::from_str(&response)
+                .wrap_err_with(|| format!("Deserializing response from {request_description}"))?;
+            Ok(Ok(response))
+        }
+    }
+
+    impl GithubClient for RestGithubClient {
+        #[instrument]
+        fn query_github_username(&self, effects: &Effects) -> EyreExitOr<String> {
+            #[derive(Deserialize)]
+            struct User {
+                login: String,
+            }
+            let url = format!("{}/user", self.api_base_url);
+            let User { login } = try_exit_code!(self.call_api(effects, "GET", &url, None)?);
+            Ok(Ok(login))
+        }
+
+        #[instrument]
+        fn query_repo_pull_request_infos(
+            &self,
+            effects: &Effects,
+        ) -> EyreExitOr<HashMap<String, PullRequestInfo>> {
+            let url = self.graphql_url();
+            let search_query = format!(
+                "repo:{}/{} is:pr is:open author:@me",
+                self.owner, self.repo_name
+            );
+            let mut pull_request_infos = HashMap::new();
+            let mut cursor: Option<String> = None;
+            loop {
+                let request = serde_json::json!({
+                    "query": SEARCH_PULL_REQUESTS_QUERY,
+                    "variables": {
+                        "query": search_query,
+                        "cursor": cursor,
+                    },
+                });
+                let response: GraphqlResponse<SearchPullRequestsData> =
+                    try_exit_code!(self.call_api(effects, "POST", &url, Some(request))?);
+                let GraphqlResponse { data, errors } = response;
+                let data = match data {
+                    Some(data) if errors.is_empty() => data,
+                    _ => {
+                        writeln!(
+                            effects.get_output_stream(),
+                            "Querying pull requests from the GitHub API failed: {}",
+                            errors.into_iter().map(|error| error.message).join("; ")
+                        )?;
+                        return Ok(Err(ExitCode(1)));
+                    }
+                };
+                let SearchPullRequestsConnection { page_info, nodes } = data.search;
+                pull_request_infos.extend(nodes.into_iter().map(|item| {
+                    let item = PullRequestInfo::from(item);
+                    (item.head_ref_name.clone(), item)
+                }));
+                match page_info {
+                    PageInfo {
+                        has_next_page: true,
+                        end_cursor: Some(end_cursor),
+                    } => cursor = Some(end_cursor),
+                    PageInfo { .. } => break,
+                }
+            }
+            Ok(Ok(pull_request_infos))
+        }
+
+        #[instrument]
+        fn create_pull_request(
+            &self,
+            effects: &Effects,
+            args: CreatePullRequestArgs,
+            submit_options: &SubmitOptions,
+        ) -> EyreExitOr<String> {
+            #[derive(Deserialize)]
+            struct CreatedPullRequest {
+                html_url: String,
+            }
+            let CreatePullRequestArgs {
+                head_ref_oid: _,
+                head_ref_name,
+                base_ref_name,
+                title,
+                body,
+            } = args;
+            let SubmitOptions {
+                create: _,
+                draft,
+                execution_strategy: _,
+                num_jobs: _,
+                message: _,
+            } = submit_options;
+            let url = format!("{}/pulls", self.repo_url());
+            let request = serde_json::json!({
+                "head": head_ref_name,
+                "base": base_ref_name,
+                "title": title,
+                "body": body,
+                "draft": draft,
+            });
+            let CreatedPullRequest { html_url } =
+                try_exit_code!(self.call_api(effects, "POST", &url, Some(request))?);
+            Ok(Ok(html_url))
+        }
+
+        #[instrument]
+        fn update_pull_request(
+            &self,
+            effects: &Effects,
+            number: usize,
+            args: UpdatePullRequestArgs,
+            _submit_options: &SubmitOptions,
+        ) -> EyreExitOr<()> {
+            let UpdatePullRequestArgs {
+                head_ref_oid: _, // branch should have been pushed by caller
+                base_ref_name,
+                title,
+                body,
+            } = args;
+            let url = format!("{}/pulls/{number}", self.repo_url());
+            let request = serde_json::json!({
+                "base": base_ref_name,
+                "title": title,
+                "body": body,
+            });
+            let _: serde_json::Value =
+                try_exit_code!(self.call_api(effects, "PATCH", &url, Some(request))?);
+            Ok(Ok(()))
+        }
+    }
+
     /// The mock state on disk, representing the remote Github repository and
     /// server.
     #[derive(Debug, Default, Deserialize, Serialize)]
@@ -1118,6 +1744,7 @@ mod client {
                 let CreatePullRequestArgs {
                     head_ref_oid,
                     head_ref_name,
+                    base_ref_name,
                     title,
                     body,
                 } = args;
@@ -1140,11 +1767,13 @@ mod client {
                     url: url.clone(),
                     head_ref_name: head_ref_name.clone(),
                     head_ref_oid: SerializedNonZeroOid(head_ref_oid),
-                    base_ref_name: Self::main_branch().to_owned(),
+                    base_ref_name,
                     closed: false,
                     is_draft: *draft,
                     title,
                     body,
+                    review_decision: None,
+                    checks_state: None,
                 };
                 state.pull_requests.insert(head_ref_name, pull_request_info);
                 Ok(url)
@@ -1195,10 +1824,6 @@ mod client {
             "mock-github-repo"
         }
 
-        fn main_branch() -> &'static str {
-            "master"
-        }
-
         /// Get the path on disk where the mock state is stored.
         pub fn state_path(&self) -> PathBuf {
             self.remote_repo_path.join("mock-github-client-state.json")
@@ -1225,7 +1850,10 @@ mod client {
 
 /// Testing utilities.
 pub mod testing {
-    pub use super::client::MockGithubClient;
+    pub use super::client::{
+        CreatePullRequestArgs, GithubClient, MockGithubClient, PullRequestInfo, RestGithubClient,
+        UpdatePullRequestArgs,
+    };
 }
 
 #[cfg(test)]
@@ -1242,4 +1870,42 @@ mod tests {
         assert_eq!(commit_summary_slug("foo_~_bar"), "foo-bar");
         assert_eq!(commit_summary_slug("!!!"), "to-review")
     }
+
+    #[test]
+    fn test_parse_github_repository() {
+        let expected = Some(("owner".to_owned(), "repo".to_owned()));
+        assert_eq!(
+            parse_github_repository("git@github.com:owner/repo.git"),
+            expected
+        );
+        assert_eq!(
+            parse_github_repository("https://github.com/owner/repo"),
+            expected
+        );
+        assert_eq!(
+            parse_github_repository("ssh://git@github.example.com/owner/repo.git/"),
+            expected
+        );
+        assert_eq!(parse_github_repository("repo"), None);
+    }
+
+    #[test]
+    fn test_replace_stack_block() {
+        let stack_block = format!("{STACK_BLOCK_START}\nnew\n{STACK_BLOCK_END}");
+        assert_eq!(
+            replace_stack_block("message\n", &stack_block),
+            format!("{stack_block}\n\nmessage\n")
+        );
+        assert_eq!(
+            replace_stack_block(
+                &format!("before\r\n{STACK_BLOCK_START}\nold\n{STACK_BLOCK_END}\r\nafter"),
+                &stack_block
+            ),
+            format!("before\r\n{stack_block}\r\nafter")
+        );
+        assert_eq!(
+            replace_stack_block("**Stack:**\n\n* old\n\n\n---\n\nmessage\n", &stack_block),
+            format!("{stack_block}\n\nmessage\n")
+        );
+    }
 }
@@ -0,0 +1,1114 @@
+//! GitLab backend for submitting patch stacks.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{Debug, Write};
+
+use itertools::Itertools;
+use lib::core::config::get_main_branch_name;
+use lib::core::dag::CommitSet;
+use lib::core::dag::Dag;
+use lib::core::effects::Effects;
+use lib::core::effects::OperationType;
+use lib::core::eventlog::EventLogDb;
+use lib::core::repo_ext::RepoExt;
+use lib::core::repo_ext::RepoReferencesSnapshot;
+use lib::git::CategorizedReferenceName;
+use lib::git::GitErrorCode;
+use lib::git::GitRunInfo;
+use lib::git::RepoError;
+use lib::git::{BranchType, NonZeroOid, Repo, ReviewInfo};
+use lib::try_exit_code;
+use lib::util::ExitCode;
+use lib::util::EyreExitOr;
+
+use tracing::debug;
+use tracing::instrument;
+use tracing::warn;
+
+use crate::branch_forge::BranchForge;
+use crate::github::{commit_summary_slug, singleton};
+use crate::SubmitStatus;
+use crate::{CommitStatus, CreateStatus, Forge, LandSupport, SubmitOptions};
+
+/// Testing environment variable. When this is set, the executable will use the
+/// mock GitLab implementation. This should be set to the path of an existing
+/// repository that represents the remote/GitLab.
+pub const MOCK_REMOTE_REPO_PATH_ENV_KEY: &str = "BRANCHLESS_SUBMIT_GITLAB_MOCK_REMOTE_REPO_PATH";
+
+/// GitLab marks a merge request as a draft by prefixing its title.
+const DRAFT_TITLE_PREFIX: &str = "Draft: ";
+
+/// The [GitLab](https://en.wikipedia.org/wiki/GitLab) code hosting platform.
+/// This forge integrates specifically with the `glab` command-line utility.
+///
+/// One merge request is created per commit. Each merge request targets the
+/// branch of the nearest ancestor commit which also has a merge request (or
+/// the main branch, if there is no such commit), so that the stack can be
+/// reviewed in order.
+#[allow(missing_docs)]
+#[derive(Debug)]
+pub struct GitlabForge<'a> {
+    pub effects: &'a Effects,
+    pub git_run_info: &'a GitRunInfo,
+    pub repo: &'a Repo,
+    pub event_log_db: &'a EventLogDb<'a>,
+    pub dag: &'a Dag,
+    pub client: Box<dyn client::GitlabClient>,
+}
+
+impl Forge for GitlabForge<'_> {
+    #[instrument]
+    fn query_status(
+        &mut self,
+        commit_set: CommitSet,
+    ) -> EyreExitOr<HashMap<NonZeroOid, CommitStatus>> {
+        let effects = self.effects;
+        let merge_request_infos =
+            try_exit_code!(self.client.query_repo_merge_request_infos(effects)?);
+        let references_snapshot = self.repo.get_references_snapshot()?;
+
+        let mut result = HashMap::new();
+        for branch in self.repo.get_all_local_branches()? {
+            let local_branch_oid = match branch.get_oid()? {
+                Some(branch_oid) => branch_oid,
+                None => continue,
+            };
+            if !self.dag.set_contains(&commit_set, local_branch_oid)? {
+                continue;
+            }
+
+            let local_branch_name = branch.get_name()?;
+            let remote_name = branch.get_push_remote_name()?;
+            let remote_branch_name = branch.get_upstream_branch_name_without_push_remote_name()?;
+
+            let submit_status = match remote_branch_name
+                .as_ref()
+                .and_then(|remote_branch_name| merge_request_infos.get(remote_branch_name))
+            {
+                None => SubmitStatus::Unsubmitted,
+                Some(merge_request_info) => {
+                    let updated_merge_request_info = self.make_updated_merge_request_info(
+                        &references_snapshot,
+                        &merge_request_infos,
+                        local_branch_oid,
+                    )?;
+                    debug!(
+                        ?merge_request_info,
+                        ?updated_merge_request_info,
+                        "Comparing merge request info"
+                    );
+                    if updated_merge_request_info
+                        .fields_to_update(merge_request_info)
+                        .is_empty()
+                    {
+                        SubmitStatus::UpToDate
+                    } else {
+                        SubmitStatus::NeedsUpdate
+                    }
+                }
+            };
+            result.insert(
+                local_branch_oid,
+                CommitStatus {
+                    submit_status,
+                    remote_name,
+                    local_commit_name: Some(local_branch_name.to_owned()),
+                    remote_commit_name: remote_branch_name,
+                },
+            );
+        }
+
+        for commit_oid in self.dag.commit_set_to_vec(&commit_set)? {
+            result.entry(commit_oid).or_insert(CommitStatus {
+                submit_status: SubmitStatus::Unsubmitted,
+                remote_name: None,
+                local_commit_name: None,
+                remote_commit_name: None,
+            });
+        }
+
+        Ok(Ok(result))
+    }
+
+    #[instrument]
+    fn create(
+        &mut self,
+        commits: HashMap<NonZeroOid, CommitStatus>,
+        options: &SubmitOptions,
+    ) -> EyreExitOr<HashMap<NonZeroOid, CreateStatus>> {
+        let effects = self.effects;
+        let commit_oids = self.dag.sort(&commits.keys().copied().collect())?;
+
+        let references_snapshot = self.repo.get_references_snapshot()?;
+        let mut branch_forge = BranchForge {
+            effects,
+            git_run_info: self.git_run_info,
+            dag: self.dag,
+            repo: self.repo,
+            event_log_db: self.event_log_db,
+            references_snapshot: &references_snapshot,
+        };
+        let gitlab_username = try_exit_code!(self.client.query_gitlab_username(effects)?);
+
+        // Generate branches for all the commits to create.
+        let commits_to_create = commit_oids
+            .into_iter()
+            .map(|commit_oid| (commit_oid, commits.get(&commit_oid).unwrap()))
+            .filter_map(
+                |(commit_oid, commit_status)| match commit_status.submit_status {
+                    SubmitStatus::Local
+                    | SubmitStatus::Unknown
+                    | SubmitStatus::NeedsUpdate
+                    | SubmitStatus::UpToDate => None,
+                    SubmitStatus::Unsubmitted => Some((commit_oid, commit_status)),
+                },
+            )
+            .collect_vec();
+        let mut created_branches = HashMap::new();
+        for (commit_oid, commit_status) in commits_to_create.iter().copied() {
+            let commit = self.repo.find_commit_or_fail(commit_oid)?;
+
+            let local_branch_name = match &commit_status.local_commit_name {
+                Some(local_branch_name) => local_branch_name.clone(),
+                None => {
+                    let summary = commit.get_summary()?;
+                    let summary = String::from_utf8_lossy(&summary);
+                    let summary_slug = commit_summary_slug(&summary);
+                    let new_branch_name_base = format!("{gitlab_username}/{summary_slug}");
+                    let mut new_branch_name = new_branch_name_base.clone();
+                    for i in 2.. {
+                        if i > 6 {
+                            writeln!(
+                                effects.get_output_stream(),
+                                "Could not generate fresh branch name for commit: {}",
+                                effects
+                                    .get_glyphs()
+                                    .render(commit.friendly_describe(effects.get_glyphs())?)?,
+                            )?;
+                            return Ok(Err(ExitCode(1)));
+                        }
+                        match self.repo.find_branch(&new_branch_name, BranchType::Local)? {
+                            Some(_) => {
+                                new_branch_name = format!("{new_branch_name_base}-{i}");
+                            }
+                            None => break,
+                        }
+                    }
+                    match self.repo.create_branch(&new_branch_name, &commit, false) {
+                        Ok(_branch) => {}
+                        Err(RepoError::CreateBranch { source, name: _ })
+                            if source.code() == GitErrorCode::Exists => {}
+                        Err(err) => return Err(err.into()),
+                    };
+                    new_branch_name
+                }
+            };
+
+            let created_branch = try_exit_code!(branch_forge.create(
+                singleton(&commits, commit_oid, |commit_status| CommitStatus {
+                    local_commit_name: Some(local_branch_name.clone()),
+                    ..commit_status.clone()
+                }),
+                options
+            )?);
+            created_branches.extend(created_branch.into_iter());
+        }
+
+        // Create the merge requests in topological order, so that each merge
+        // request can target the branch of the merge request before it. The
+        // merge requests are created with up-to-date metadata, so there's no
+        // need to update them afterwards.
+        for (commit_oid, _) in commits_to_create {
+            let local_branch_name = match created_branches.get(&commit_oid) {
+                Some(CreateStatus {
+                    final_commit_oid: _,
+                    local_commit_name,
+                }) => local_commit_name,
+                None => {
+                    writeln!(
+                        effects.get_output_stream(),
+                        "Could not find local branch name for commit: {}",
+                        effects.get_glyphs().render(
+                            self.repo
+                                .find_commit_or_fail(commit_oid)?
+                                .friendly_describe(effects.get_glyphs())?
+                        )?
+                    )?;
+                    return Ok(Err(ExitCode(1)));
+                }
+            };
+
+            let merge_request_infos =
+                try_exit_code!(self.client.query_repo_merge_request_infos(effects)?);
+            let references_snapshot = self.repo.get_references_snapshot()?;
+            let client::UpdateMergeRequestArgs {
+                head_ref_oid: _,
+                target_branch,
+                title,
+                description,
+            } = self.make_updated_merge_request_info(
+                &references_snapshot,
+                &merge_request_infos,
+                commit_oid,
+            )?;
+            try_exit_code!(self.client.create_merge_request(
+                effects,
+                client::CreateMergeRequestArgs {
+                    head_ref_oid: commit_oid,
+                    source_branch: local_branch_name.clone(),
+                    target_branch,
+                    title,
+                    description,
+                },
+                options
+            )?);
+        }
+
+        Ok(Ok(created_branches))
+    }
+
+    #[instrument]
+    fn update(
+        &mut self,
+        commit_statuses: HashMap<NonZeroOid, CommitStatus>,
+        options: &SubmitOptions,
+    ) -> EyreExitOr<()> {
+        let effects = self.effects;
+        let SubmitOptions {
+            create: _,
+            draft: _,
+            execution_strategy: _,
+            num_jobs: _,
+            message: _,
+        } = options;
+
+        let merge_request_infos =
+            try_exit_code!(self.client.query_repo_merge_request_infos(effects)?);
+        let references_snapshot = self.repo.get_references_snapshot()?;
+        let mut branch_forge = BranchForge {
+            effects,
+            git_run_info: self.git_run_info,
+            dag: self.dag,
+            repo: self.repo,
+            event_log_db: self.event_log_db,
+            references_snapshot: &references_snapshot,
+        };
+
+        let commit_set: CommitSet = commit_statuses.keys().copied().collect();
+        let commit_oids = self.dag.sort(&commit_set)?;
+        {
+            let (effects, progress) = effects.start_operation(OperationType::UpdateCommits);
+            progress.notify_progress(0, commit_oids.len());
+            for commit_oid in commit_oids {
+                let commit_status = match commit_statuses.get(&commit_oid) {
+                    Some(commit_status) => commit_status,
+                    None => {
+                        warn!(
+                            ?commit_oid,
+                            ?commit_statuses,
+                            "Commit not found in commit statuses"
+                        );
+                        continue;
+                    }
+                };
+                let remote_branch_name = match &commit_status.remote_commit_name {
+                    Some(remote_branch_name) => remote_branch_name,
+                    None => {
+                        warn!(
+                            ?commit_oid,
+                            ?commit_statuses,
+                            "Commit does not have remote branch name"
+                        );
+                        continue;
+                    }
+                };
+                let merge_request_info = match merge_request_infos.get(remote_branch_name) {
+                    Some(merge_request_info) => merge_request_info,
+                    None => {
+                        warn!(
+                            ?commit_oid,
+                            ?commit_statuses,
+                            "Commit does not have merge request"
+                        );
+                        continue;
+                    }
+                };
+
+                let updated_merge_request_info = self.make_updated_merge_request_info(
+                    &references_snapshot,
+                    &merge_request_infos,
+                    commit_oid,
+                )?;
+                let updated_fields = {
+                    let fields = updated_merge_request_info.fields_to_update(merge_request_info);
+                    if fields.is_empty() {
+                        "none (this should not happen)".to_owned()
+                    } else {
+                        fields.join(", ")
+                    }
+                };
+                let client::UpdateMergeRequestArgs {
+                    head_ref_oid: _, // Updated by `branch_forge.update`.
+                    target_branch,
+                    title,
+                    description,
+                } = updated_merge_request_info;
+                writeln!(
+                    effects.get_output_stream(),
+                    "Updating merge request ({updated_fields}) for commit {}",
+                    effects.get_glyphs().render(
+                        self.repo
+                            .find_commit_or_fail(commit_oid)?
+                            .friendly_describe(effects.get_glyphs())?
+                    )?
+                )?;
+
+                // Push branch:
+                try_exit_code!(
+                    branch_forge.update(singleton(&commit_statuses, commit_oid, |x| x), options)?
+                );
+
+                // Update metadata, preserving the draft state of the merge
+                // request:
+                let title = if merge_request_info.draft {
+                    format!("{DRAFT_TITLE_PREFIX}{title}")
+                } else {
+                    title
+                };
+                try_exit_code!(self.client.update_merge_request(
+                    &effects,
+                    merge_request_info.iid,
+                    client::UpdateMergeRequestArgs {
+                        head_ref_oid: commit_oid,
+                        target_branch,
+                        title,
+                        description,
+                    },
+                    options
+                )?);
+                progress.notify_progress_inc(1);
+            }
+        }
+
+        Ok(Ok(()))
+    }
+
+    #[instrument]
+    fn query_review_infos(
+        &mut self,
+        commit_set: CommitSet,
+    ) -> EyreExitOr<Option<HashMap<NonZeroOid, ReviewInfo>>> {
+        let merge_request_infos =
+            try_exit_code!(self.client.query_repo_merge_request_infos(self.effects)?);
+        let mut result = HashMap::new();
+        for branch in self.repo.get_all_local_branches()? {
+            let local_branch_oid = match branch.get_oid()? {
+                Some(branch_oid) => branch_oid,
+                None => continue,
+            };
+            if !self.dag.set_contains(&commit_set, local_branch_oid)? {
+                continue;
+            }
+            if let Some(merge_request_info) = branch
+                .get_upstream_branch_name_without_push_remote_name()?
+                .and_then(|remote_branch_name| merge_request_infos.get(&remote_branch_name))
+            {
+                result.insert(local_branch_oid, merge_request_info.to_review_info());
+            }
+        }
+        Ok(Ok(Some(result)))
+    }
+
+    fn land_support(&self) -> LandSupport {
+        LandSupport::Supported
+    }
+}
+
+impl GitlabForge<'_> {
+    /// Construct a real or mock GitLab client according to the environment.
+    pub fn client(git_run_info: GitRunInfo) -> Box<dyn client::GitlabClient> {
+        match env::var(MOCK_REMOTE_REPO_PATH_ENV_KEY) {
+            Ok(path) => Box::new(client::MockGitlabClient {
+                remote_repo_path: path.into(),
+            }),
+            Err(_) => {
+                let GitRunInfo {
+                    path_to_git: _,
+                    working_directory,
+                    env,
+                } = git_run_info;
+                let glab_run_info = GitRunInfo {
+                    path_to_git: "glab".into(),
+                    working_directory,
+                    env,
+                };
+                Box::new(client::RealGitlabClient { glab_run_info })
+            }
+        }
+    }
+
+    /// Find the merge request associated with one of the local branches
+    /// pointing to the given commit, if any.
+    fn get_merge_request_info<'b>(
+        &self,
+        references_snapshot: &RepoReferencesSnapshot,
+        merge_request_infos: &'b HashMap<String, client::MergeRequestInfo>,
+        commit_oid: NonZeroOid,
+    ) -> eyre::Result<Option<&'b client::MergeRequestInfo>> {
+        let branch_names = match references_snapshot.branch_oid_to_names.get(&commit_oid) {
+            Some(branch_names) => branch_names,
+            None => return Ok(None),
+        };
+        for branch_name in branch_names.iter().sorted() {
+            let local_branch = match self.repo.find_branch(
+                &CategorizedReferenceName::new(branch_name).render_suffix(),
+                BranchType::Local,
+            )? {
+                Some(local_branch) => local_branch,
+                None => continue,
+            };
+            let remote_branch_name =
+                match local_branch.get_upstream_branch_name_without_push_remote_name()? {
+                    Some(remote_branch_name) => remote_branch_name,
+                    None => continue,
+                };
+            if let Some(merge_request_info) = merge_request_infos.get(&remote_branch_name) {
+                return Ok(Some(merge_request_info));
+            }
+        }
+        Ok(None)
+    }
+
+    #[instrument]
+    fn make_updated_merge_request_info(
+        &self,
+        references_snapshot: &RepoReferencesSnapshot,
+        merge_request_infos: &HashMap<String, client::MergeRequestInfo>,
+        commit_oid: NonZeroOid,
+    ) -> eyre::Result<client::UpdateMergeRequestArgs> {
+        let commit = self.repo.find_commit_or_fail(commit_oid)?;
+        let title = String::from_utf8_lossy(&commit.get_summary()?).into_owned();
+        let description = String::from_utf8_lossy(&commit.get_message_pretty()).into_owned();
+
+        let stack_ancestor_oids = {
+            let main_branch_oid = CommitSet::from(references_snapshot.main_branch_oid);
+            let stack_ancestor_oids = self
+                .dag
+                .query_only(CommitSet::from(commit_oid), main_branch_oid)?
+                .difference(&CommitSet::from(commit_oid));
+            self.dag.commit_set_to_vec(&stack_ancestor_oids)?
+        };
+        let nearest_ancestor_with_merge_request_info = {
+            let mut result = None;
+            for stack_ancestor_oid in stack_ancestor_oids.into_iter().rev() {
+                if let Some(info) = self.get_merge_request_info(
+                    references_snapshot,
+                    merge_request_infos,
+                    stack_ancestor_oid,
+                )? {
+                    result = Some(info);
+                    break;
+                }
+            }
+            result
+        };
+        let target_branch = match nearest_ancestor_with_merge_request_info {
+            Some(info) => info.source_branch.clone(),
+            None => get_main_branch_name(self.repo)?,
+        };
+
+        Ok(client::UpdateMergeRequestArgs {
+            head_ref_oid: commit_oid,
+            target_branch,
+            title,
+            description,
+        })
+    }
+}
+
+mod client {
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt::{Debug, Write};
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use std::sync::Arc;
+
+    use eyre::Context;
+    use itertools::Itertools;
+    use lib::core::dag::Dag;
+    use lib::core::effects::{Effects, OperationType};
+    use lib::core::eventlog::{EventLogDb, EventReplayer};
+    use lib::core::formatting::Glyphs;
+    use lib::core::repo_ext::RepoExt;
+    use lib::git::{
+        ChecksState, GitRunInfo, NonZeroOid, Repo, ReviewInfo, ReviewState, SerializedNonZeroOid,
+    };
+    use lib::try_exit_code;
+    use lib::util::{ExitCode, EyreExitOr};
+    use serde::{Deserialize, Serialize};
+    use tracing::{debug, instrument};
+
+    use super::DRAFT_TITLE_PREFIX;
+    use crate::SubmitOptions;
+
+    /// The subset of the GitLab merge request API object that we use.
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct MergeRequestInfo {
+        pub iid: usize,
+        pub web_url: String,
+        pub source_branch: String,
+        pub target_branch: String,
+        pub sha: SerializedNonZeroOid,
+        pub state: String,
+        pub draft: bool,
+        pub title: String,
+        #[serde(default)]
+        pub description: Option<String>,
+        /// GitLab's summary of what, if anything, is blocking the merge
+        /// request from being merged, such as `not_approved` or
+        /// `ci_still_running`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub detailed_merge_status: Option<String>,
+    }
+
+    impl MergeRequestInfo {
+        /// Convert into the forge-independent representation stored in the
+        /// review info cache.
+        pub fn to_review_info(&self) -> ReviewInfo {
+            let (review_state, checks_state) = if self.draft {
+                (Some(ReviewState::Draft), None)
+            } else {
+                match self.detailed_merge_status.as_deref() {
+                    Some("mergeable") => (Some(ReviewState::Approved), Some(ChecksState::Success)),
+                    Some("not_approved") => (Some(ReviewState::ReviewRequired), None),
+                    Some("requested_changes") => (Some(ReviewState::ChangesRequested), None),
+                    Some("ci_still_running") => (None, Some(ChecksState::Pending)),
+                    Some("ci_must_pass") => (None, Some(ChecksState::Failure)),
+                    // GitLab hasn't finished checking whether the merge
+                    // request can be merged.
+                    None | Some("checking" | "unchecked" | "preparing") => (None, None),
+                    // Anything else, such as `conflict` or `need_rebase`,
+                    // prevents the merge request from being merged.
+                    Some(_) => (Some(ReviewState::Blocked), None),
+                }
+            };
+            ReviewInfo {
+                name: format!("!{}", self.iid),
+                url: Some(self.web_url.clone()),
+                review_state,
+                checks_state,
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct CreateMergeRequestArgs {
+        pub head_ref_oid: NonZeroOid,
+        pub source_branch: String,
+        pub target_branch: String,
+        pub title: String,
+        pub description: String,
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    pub struct UpdateMergeRequestArgs {
+        pub head_ref_oid: NonZeroOid,
+        pub target_branch: String,
+        pub title: String,
+        pub description: String,
+    }
+
+    impl UpdateMergeRequestArgs {
+        pub fn fields_to_update(&self, merge_request_info: &MergeRequestInfo) -> Vec<&'static str> {
+            let MergeRequestInfo {
+                iid: _,
+                web_url: _,
+                source_branch: _,
+                sha: SerializedNonZeroOid(old_head_ref_oid),
+                target_branch: old_target_branch,
+                state: _,
+                draft,
+                title: old_title,
+                description: old_description,
+                detailed_merge_status: _,
+            } = merge_request_info;
+            let Self {
+                head_ref_oid: new_head_ref_oid,
+                target_branch: new_target_branch,
+                title: new_title,
+                description: new_description,
+            } = self;
+
+            let old_title = if *draft {
+                old_title
+                    .strip_prefix(DRAFT_TITLE_PREFIX)
+                    .unwrap_or(old_title)
+            } else {
+                old_title
+            };
+            let old_description = old_description.as_deref().unwrap_or_default();
+
+            let mut updated_fields = Vec::new();
+            if old_head_ref_oid != new_head_ref_oid {
+                updated_fields.push("commit");
+            }
+            if old_target_branch != new_target_branch {
+                updated_fields.push("target branch");
+            }
+            if old_title != new_title {
+                updated_fields.push("title");
+            }
+            if old_description != new_description {
+                updated_fields.push("description");
+            }
+            updated_fields
+        }
+    }
+
+    pub trait GitlabClient: Debug {
+        /// Get the username of the currently-logged-in user.
+        fn query_gitlab_username(&self, effects: &Effects) -> EyreExitOr<String>;
+
+        /// Get the details of all open merge requests for the
+        /// currently-logged-in user in the current repository. The resulting
+        /// map is keyed by source branch name.
+        fn query_repo_merge_request_infos(
+            &self,
+            effects: &Effects,
+        ) -> EyreExitOr<HashMap<String, MergeRequestInfo>>;
+
+        /// Create a merge request and return its URL.
+        fn create_merge_request(
+            &self,
+            effects: &Effects,
+            args: CreateMergeRequestArgs,
+            submit_options: &SubmitOptions,
+        ) -> EyreExitOr<String>;
+
+        /// Update the metadata of an existing merge request.
+        fn update_merge_request(
+            &self,
+            effects: &Effects,
+            iid: usize,
+            args: UpdateMergeRequestArgs,
+            submit_options: &SubmitOptions,
+        ) -> EyreExitOr<()>;
+    }
+
+    #[derive(Debug)]
+    pub struct RealGitlabClient {
+        pub glab_run_info: GitRunInfo,
+    }
+
+    impl RealGitlabClient {
+        #[instrument]
+        fn run_glab(&self, effects: &Effects, args: &[&str]) -> EyreExitOr<Vec<u8>> {
+            let GitRunInfo {
+                path_to_git: exe,
+                working_directory,
+                env,
+            } = &self.glab_run_info;
+            let exe_invocation = format!("{} {}", exe.to_string_lossy(), args.join(" "));
+            debug!(?exe_invocation, "Invoking glab");
+            let (effects, progress) =
+                effects.start_operation(OperationType::RunTests(Arc::new(exe_invocation.clone())));
+            let _progress = progress;
+
+            let child = Command::new(exe)
+                .args(args)
+                .current_dir(working_directory)
+                .envs(env.iter())
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .context("Invoking `glab` command-line executable")?;
+            let output = child
+                .wait_with_output()
+                .context("Waiting for `glab` invocation")?;
+            if !output.status.success() {
+                writeln!(
+                    effects.get_output_stream(),
+                    "Call to `{exe_invocation}` failed",
+                )?;
+                writeln!(effects.get_output_stream(), "Stdout:")?;
+                writeln!(
+                    effects.get_output_stream(),
+                    "{}",
+                    String::from_utf8_lossy(&output.stdout)
+                )?;
+                writeln!(effects.get_output_stream(), "Stderr:")?;
+                writeln!(
+                    effects.get_output_stream(),
+                    "{}",
+                    String::from_utf8_lossy(&output.stderr)
+                )?;
+                return Ok(Err(ExitCode::try_from(output.status)?));
+            }
+            Ok(Ok(output.stdout))
+        }
+    }
+
+    impl GitlabClient for RealGitlabClient {
+        #[instrument]
+        fn query_gitlab_username(&self, effects: &Effects) -> EyreExitOr<String> {
+            #[derive(Deserialize)]
+            struct User {
+                username: String,
+            }
+            let output = try_exit_code!(self.run_glab(effects, &["api", "user"])?);
+            let User { username } = serde_json::from_slice(&output)
+                .wrap_err("Deserializing output from glab api user")?;
+            Ok(Ok(username))
+        }
+
+        #[instrument]
+        fn query_repo_merge_request_infos(
+            &self,
+            effects: &Effects,
+        ) -> EyreExitOr<HashMap<String, MergeRequestInfo>> {
+            let output = try_exit_code!(self.run_glab(
+                effects,
+                &[
+                    "api",
+                    "--paginate",
+                    "projects/:id/merge_requests?state=opened&scope=created_by_me&per_page=100",
+                ]
+            )?);
+            // With `--paginate`, each page is emitted as a separate JSON array.
+            let mut merge_request_infos = HashMap::new();
+            for page in serde_json::Deserializer::from_slice(&output).into_iter() {
+                let page: Vec<MergeRequestInfo> =
+                    page.wrap_err("Deserializing output from glab api merge_requests")?;
+                merge_request_infos.extend(
+                    page.into_iter()
+                        .map(|item| (item.source_branch.clone(), item)),
+                );
+            }
+            Ok(Ok(merge_request_infos))
+        }
+
+        #[instrument]
+        fn create_merge_request(
+            &self,
+            effects: &Effects,
+            args: CreateMergeRequestArgs,
+            submit_options: &SubmitOptions,
+        ) -> EyreExitOr<String> {
+            let CreateMergeRequestArgs {
+                head_ref_oid: _,
+                source_branch,
+                target_branch,
+                title,
+                description,
+            } = args;
+            let SubmitOptions {
+                create: _,
+                draft,
+                execution_strategy: _,
+                num_jobs: _,
+                message: _,
+            } = submit_options;
+            let title = if *draft {
+                format!("{DRAFT_TITLE_PREFIX}{title}")
+            } else {
+                title
+            };
+
+            let source_branch_field = format!("source_branch={source_branch}");
+            let target_branch_field = format!("target_branch={target_branch}");
+            let title_field = format!("title={title}");
+            let description_field = format!("description={description}");
+            let output = try_exit_code!(self.run_glab(
+                effects,
+                &[
+                    "api",
+                    "--method",
+                    "POST",
+                    "projects/:id/merge_requests",
+                    "--raw-field",
+                    &source_branch_field,
+                    "--raw-field",
+                    &target_branch_field,
+                    "--raw-field",
+                    &title_field,
+                    "--raw-field",
+                    &description_field,
+                ]
+            )?);
+            let merge_request_info: MergeRequestInfo = serde_json::from_slice(&output)
+                .wrap_err("Deserializing output from glab api merge_requests")?;
+            Ok(Ok(merge_request_info.web_url))
+        }
+
+        #[instrument]
+        fn update_merge_request(
+            &self,
+            effects: &Effects,
+            iid: usize,
+            args: UpdateMergeRequestArgs,
+            _submit_options: &SubmitOptions,
+        ) -> EyreExitOr<()> {
+            let UpdateMergeRequestArgs {
+                head_ref_oid: _, // branch should have been pushed by caller
+                target_branch,
+                title,
+                description,
+            } = args;
+            let endpoint = format!("projects/:id/merge_requests/{iid}");
+            let target_branch_field = format!("target_branch={target_branch}");
+            let title_field = format!("title={title}");
+            let description_field = format!("description={description}");
+            try_exit_code!(self.run_glab(
+                effects,
+                &[
+                    "api",
+                    "--method",
+                    "PUT",
+                    &endpoint,
+                    "--raw-field",
+                    &target_branch_field,
+                    "--raw-field",
+                    &title_field,
+                    "--raw-field",
+                    &description_field,
+                ]
+            )?);
+            Ok(Ok(()))
+        }
+    }
+
+    /// The mock state on disk, representing the remote GitLab repository and
+    /// server.
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    pub struct MockState {
+        /// The next index to assign a newly-created merge request.
+        pub merge_request_index: usize,
+
+        /// Information about all merge requests for the repository, keyed by
+        /// source branch. Sorted for determinism when dumping state for
+        /// testing.
+        pub merge_requests: BTreeMap<String, MergeRequestInfo>,
+    }
+
+    impl MockState {
+        fn load(path: &Path) -> eyre::Result<Self> {
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(Default::default());
+                }
+                Err(err) => return Err(err).wrap_err("Opening mock GitLab client state file"),
+            };
+            let state = serde_json::from_reader(file)?;
+            Ok(state)
+        }
+
+        /// Mark merge requests as merged once their commit has been
+        /// incorporated into their target branch.
+        fn restore_invariants(&mut self, remote_repo: &Repo) -> eyre::Result<()> {
+            let effects = Effects::new_suppress_for_test(Glyphs::text());
+            let conn = remote_repo.get_db_conn()?;
+            let event_log_db = EventLogDb::new(&conn)?;
+            let event_replayer =
+                EventReplayer::from_event_log_db(&effects, remote_repo, &event_log_db)?;
+            let event_cursor = event_replayer.make_default_cursor();
+            let references_snapshot = remote_repo.get_references_snapshot()?;
+            let dag = Dag::open_and_sync(
+                &effects,
+                remote_repo,
+                &event_replayer,
+                event_cursor,
+                &references_snapshot,
+            )?;
+
+            let branches: HashMap<String, NonZeroOid> = remote_repo
+                .get_all_local_branches()?
+                .into_iter()
+                .map(|branch| -> eyre::Result<_> {
+                    let branch_name = branch.get_name()?.to_owned();
+                    let branch_oid = branch.get_oid()?.unwrap();
+                    Ok((branch_name, branch_oid))
+                })
+                .try_collect()?;
+            for (_, merge_request_info) in self.merge_requests.iter_mut() {
+                let target_branch = &merge_request_info.target_branch;
+                let target_branch_oid = match branches.get(target_branch) {
+                    Some(oid) => *oid,
+                    None => {
+                        eyre::bail!("Could not find target branch {target_branch:?} for merge request: {merge_request_info:?}");
+                    }
+                };
+                let SerializedNonZeroOid(head_ref_oid) = merge_request_info.sha;
+                if dag.query_is_ancestor(head_ref_oid, target_branch_oid)? {
+                    merge_request_info.state = "merged".to_owned();
+                }
+            }
+            Ok(())
+        }
+
+        fn save(&self, path: &Path) -> eyre::Result<()> {
+            let state = serde_json::to_string_pretty(self)?;
+            fs::write(path, state)?;
+            Ok(())
+        }
+    }
+
+    /// A mock client representing the remote GitLab repository and server.
+    #[derive(Debug)]
+    pub struct MockGitlabClient {
+        /// The path to the remote repository on disk.
+        pub remote_repo_path: PathBuf,
+    }
+
+    impl GitlabClient for MockGitlabClient {
+        fn query_gitlab_username(&self, _effects: &Effects) -> EyreExitOr<String> {
+            Ok(Ok(Self::username().to_owned()))
+        }
+
+        fn query_repo_merge_request_infos(
+            &self,
+            _effects: &Effects,
+        ) -> EyreExitOr<HashMap<String, MergeRequestInfo>> {
+            let merge_request_infos = self.with_state_mut(|state| {
+                let merge_request_infos = state
+                    .merge_requests
+                    .values()
+                    .filter(|merge_request_info| merge_request_info.state == "opened")
+                    .cloned()
+                    .map(|merge_request_info| {
+                        (merge_request_info.source_branch.clone(), merge_request_info)
+                    })
+                    .collect();
+                Ok(merge_request_infos)
+            })?;
+            Ok(Ok(merge_request_infos))
+        }
+
+        fn create_merge_request(
+            &self,
+            _effects: &Effects,
+            args: CreateMergeRequestArgs,
+            submit_options: &SubmitOptions,
+        ) -> EyreExitOr<String> {
+            let url = self.with_state_mut(|state| {
+                state.merge_request_index += 1;
+                let CreateMergeRequestArgs {
+                    head_ref_oid,
+                    source_branch,
+                    target_branch,
+                    title,
+                    description,
+                } = args;
+                let SubmitOptions {
+                    create,
+                    draft,
+                    execution_strategy: _,
+                    num_jobs: _,
+                    message: _,
+                } = submit_options;
+                assert!(create);
+                let url = format!(
+                    "https://gitlab.example.com/{}/{}/-/merge_requests/{}",
+                    Self::username(),
+                    Self::repo_name(),
+                    state.merge_request_index
+                );
+                let title = if *draft {
+                    format!("{DRAFT_TITLE_PREFIX}{title}")
+                } else {
+                    title
+                };
+                let merge_request_info = MergeRequestInfo {
+                    iid: state.merge_request_index,
+                    web_url: url.clone(),
+                    source_branch: source_branch.clone(),
+                    target_branch,
+                    sha: SerializedNonZeroOid(head_ref_oid),
+                    state: "opened".to_owned(),
+                    draft: *draft,
+                    title,
+                    description: Some(description),
+                    detailed_merge_status: None,
+                };
+                state
+                    .merge_requests
+                    .insert(source_branch, merge_request_info);
+                Ok(url)
+            })?;
+            Ok(Ok(url))
+        }
+
+        fn update_merge_request(
+            &self,
+            _effects: &Effects,
+            iid: usize,
+            args: UpdateMergeRequestArgs,
+            _submit_options: &SubmitOptions,
+        ) -> EyreExitOr<()> {
+            self.with_state_mut(|state| -> eyre::Result<()> {
+                let UpdateMergeRequestArgs {
+                    head_ref_oid,
+                    target_branch,
+                    title,
+                    description,
+                } = args;
+                let merge_request_info = match state
+                    .merge_requests
+                    .values_mut()
+                    .find(|merge_request_info| merge_request_info.iid == iid)
+                {
+                    Some(merge_request_info) => merge_request_info,
+                    None => {
+                        eyre::bail!("Could not find merge request with IID {iid}");
+                    }
+                };
+                merge_request_info.sha = SerializedNonZeroOid(head_ref_oid);
+                merge_request_info.target_branch = target_branch;
+                merge_request_info.title = title;
+                merge_request_info.description = Some(description);
+                Ok(())
+            })?;
+            Ok(Ok(()))
+        }
+    }
+
+    impl MockGitlabClient {
+        fn username() -> &'static str {
+            "mock-gitlab-username"
+        }
+
+        fn repo_name() -> &'static str {
+            "mock-gitlab-repo"
+        }
+
+        /// Get the path on disk where the mock state is stored.
+        pub fn state_path(&self) -> PathBuf {
+            self.remote_repo_path.join("mock-gitlab-client-state.json")
+        }
+
+        /// Load the mock state from disk, run the given function, and then save
+        /// the state back to disk. GitLab-specific merge request invariants are
+        /// restored before and after running the function.
+        pub fn with_state_mut<T>(
+            &self,
+            f: impl FnOnce(&mut MockState) -> eyre::Result<T>,
+        ) -> eyre::Result<T> {
+            let repo = Repo::from_dir(&self.remote_repo_path)?;
+            let state_path = self.state_path();
+            let mut state = MockState::load(&state_path)?;
+            state.restore_invariants(&repo)?;
+            let result = f(&mut state)?;
+            state.restore_invariants(&repo)?;
+            state.save(&state_path)?;
+            Ok(result)
+        }
+    }
+}
+
+/// Testing utilities.
+pub mod testing {
+    pub use super::client::MockGitlabClient;
+}
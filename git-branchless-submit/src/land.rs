@@ -0,0 +1,241 @@
+//! Land reviewed commits onto the main branch.
+
+use std::fmt::Write;
+use std::time::SystemTime;
+
+use git_branchless_opts::{ForgeKind, Revset};
+use lib::core::dag::{CommitSet, Dag};
+use lib::core::effects::Effects;
+use lib::core::eventlog::EventLogDb;
+use lib::core::formatting::Pluralize;
+use lib::core::repo_ext::{RepoExt, RepoReferencesSnapshot};
+use lib::git::{update_review_info_cache, ChecksState, GitRunInfo, Repo, ReviewInfo, ReviewState};
+use lib::try_exit_code;
+use lib::util::{ExitCode, EyreExitOr};
+use tracing::{debug, instrument};
+
+use crate::{select_forge, CommitStatus, LandSupport, SubmitStatus};
+
+/// Land the provided commits, along with any of their ancestors which aren't
+/// yet on the main branch, by fast-forwarding the main branch to them and
+/// pushing it. The commits must already be on top of the main branch, so that
+/// the commits which land are the ones which were reviewed.
+#[instrument]
+pub(crate) fn land(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    dag: &mut Dag,
+    event_log_db: &EventLogDb,
+    references_snapshot: &RepoReferencesSnapshot,
+    revset: &Revset,
+    forge_kind: Option<ForgeKind>,
+    commit_set: CommitSet,
+    dry_run: bool,
+) -> EyreExitOr<()> {
+    let commits_to_land = dag.query_only(commit_set, dag.main_branch_commit.clone())?;
+    let commit_oids = dag.sort(&commits_to_land)?;
+    let root_oids = dag.commit_set_to_vec(&dag.query_roots(commits_to_land.clone())?)?;
+    let head_oids = dag.commit_set_to_vec(&dag.query_heads(commits_to_land.clone())?)?;
+
+    let mut forge = select_forge(
+        effects,
+        git_run_info,
+        repo,
+        dag,
+        event_log_db,
+        references_snapshot,
+        revset,
+        forge_kind,
+    )?;
+    if let LandSupport::Unsupported { forge_name } = forge.land_support() {
+        writeln!(
+            effects.get_output_stream(),
+            "Landing is not supported for the {forge_name} forge; submit through {forge_name} instead."
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    let (root_oid, head_oid) = match (root_oids.as_slice(), head_oids.as_slice()) {
+        ([], []) => {
+            writeln!(effects.get_output_stream(), "There are no commits to land.")?;
+            return Ok(Ok(()));
+        }
+        ([root_oid], [head_oid]) => (*root_oid, *head_oid),
+        (root_oids, head_oids) => {
+            writeln!(
+                effects.get_output_stream(),
+                "Can only land a single stack of commits at a time, but the provided commits have {} and {}.",
+                Pluralize {
+                    determiner: None,
+                    amount: root_oids.len(),
+                    unit: ("root", "roots"),
+                },
+                Pluralize {
+                    determiner: None,
+                    amount: head_oids.len(),
+                    unit: ("head", "heads"),
+                },
+            )?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+
+    let main_branch = repo.get_main_branch()?;
+    let main_branch_name = main_branch.get_name()?.to_owned();
+    // Rebasing the commits here would land commits which were never reviewed
+    // or tested, and which their code reviews don't point to.
+    let root_commit = repo.find_commit_or_fail(root_oid)?;
+    if root_commit.get_parent_oids() != vec![references_snapshot.main_branch_oid] {
+        writeln!(
+            effects.get_output_stream(),
+            "Cannot land onto {main_branch_name}: the commits to land aren't on top of it. Move them onto it with `git sync`, submit them again, and then retry."
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    let statuses = try_exit_code!(forge.query_status(commits_to_land.clone())?);
+    let review_infos = try_exit_code!(forge.query_review_infos(commits_to_land.clone())?);
+    debug!(?statuses, ?review_infos, "Statuses of commits to land");
+
+    let mut can_land = true;
+    for commit_oid in commit_oids.iter() {
+        let reason = match get_reason_cannot_land(
+            statuses.get(commit_oid),
+            review_infos
+                .as_ref()
+                .map(|review_infos| review_infos.get(commit_oid)),
+        ) {
+            Some(reason) => reason,
+            None => continue,
+        };
+        can_land = false;
+        let commit = repo.find_commit_or_fail(*commit_oid)?;
+        writeln!(
+            effects.get_output_stream(),
+            "Cannot land commit {}: {reason}",
+            effects
+                .get_glyphs()
+                .render(commit.friendly_describe(effects.get_glyphs())?)?,
+        )?;
+    }
+    if !can_land {
+        return Ok(Err(ExitCode(1)));
+    }
+
+    let (remote_name, upstream_branch_name) = match (
+        main_branch.get_push_remote_name()?,
+        main_branch.get_upstream_branch_name_without_push_remote_name()?,
+    ) {
+        (Some(remote_name), Some(upstream_branch_name)) => (remote_name, upstream_branch_name),
+        _ => {
+            writeln!(
+                effects.get_output_stream(),
+                "Cannot land onto {main_branch_name}: it doesn't have an upstream branch to push to. Set one with: git branch --set-upstream-to <remote>/<branch> {main_branch_name}"
+            )?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+    if dry_run {
+        writeln!(
+            effects.get_output_stream(),
+            "Would land {} onto {main_branch_name}.",
+            Pluralize {
+                determiner: None,
+                amount: commit_oids.len(),
+                unit: ("commit", "commits"),
+            },
+        )?;
+        return Ok(Ok(()));
+    }
+
+    let now = SystemTime::now();
+    let event_tx_id = event_log_db.make_transaction_id(now, "land")?;
+    try_exit_code!(git_run_info.run(
+        effects,
+        Some(event_tx_id),
+        &[
+            "push",
+            &remote_name,
+            &format!("{head_oid}:refs/heads/{upstream_branch_name}"),
+        ],
+    )?);
+
+    let main_branch_reference_name = main_branch.get_reference_name()?;
+    if repo.get_head_info()?.reference_name.as_ref() == Some(&main_branch_reference_name) {
+        // If the main branch is checked out, fast-forward the working copy
+        // along with it, as `git sync` does.
+        try_exit_code!(git_run_info.run(
+            effects,
+            Some(event_tx_id),
+            &["rebase", &head_oid.to_string()],
+        )?);
+    } else {
+        repo.create_reference(&main_branch_reference_name, head_oid, true, "land")?;
+    }
+
+    // Refresh the cached code review information for the landed commits. This
+    // drops the code reviews which the forge closed once their commits reached
+    // the main branch, and keeps any which are still open.
+    if let Some(review_infos) = try_exit_code!(forge.query_review_infos(commits_to_land)?) {
+        update_review_info_cache(
+            repo,
+            commit_oids
+                .iter()
+                .map(|commit_oid| (*commit_oid, review_infos.get(commit_oid).cloned()))
+                .collect(),
+        )?;
+    }
+
+    writeln!(
+        effects.get_output_stream(),
+        "Landed {} onto {main_branch_name}.",
+        Pluralize {
+            determiner: None,
+            amount: commit_oids.len(),
+            unit: ("commit", "commits"),
+        },
+    )?;
+    Ok(Ok(()))
+}
+
+/// Determine why the given commit can't be landed, if anything. `review_info`
+/// is `None` if the forge doesn't support code review.
+fn get_reason_cannot_land(
+    commit_status: Option<&CommitStatus>,
+    review_info: Option<Option<&ReviewInfo>>,
+) -> Option<&'static str> {
+    if let Some(CommitStatus {
+        submit_status: SubmitStatus::NeedsUpdate,
+        ..
+    }) = commit_status
+    {
+        return Some("it has local changes which haven't been submitted");
+    }
+    let (review_state, checks_state) = match review_info {
+        // The forge doesn't support code review.
+        None => return None,
+        Some(None) => return Some("it doesn't have an open code review"),
+        Some(Some(ReviewInfo {
+            name: _,
+            url: _,
+            review_state,
+            checks_state,
+        })) => (review_state, checks_state),
+    };
+    match checks_state {
+        None | Some(ChecksState::Success) => {}
+        Some(ChecksState::Pending) => return Some("its CI checks are still running"),
+        Some(ChecksState::Failure) => return Some("its CI checks failed"),
+    }
+    match review_state {
+        None => Some("the state of its code review couldn't be determined"),
+        Some(ReviewState::Approved | ReviewState::NotRequired) => None,
+        Some(ReviewState::Draft) => Some("its code review is a draft"),
+        Some(ReviewState::ReviewRequired) => Some("its code review hasn't been approved"),
+        Some(ReviewState::ChangesRequested) => Some("changes were requested in its code review"),
+        Some(ReviewState::Blocked) => {
+            Some("its code review can't be merged, such as due to conflicts")
+        }
+    }
+}
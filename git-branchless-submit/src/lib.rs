@@ -10,7 +10,10 @@
 #![allow(clippy::too_many_arguments, clippy::blocks_in_if_conditions)]
 
 mod branch_forge;
+pub mod gerrit;
 pub mod github;
+pub mod gitlab;
+mod land;
 pub mod phabricator;
 
 use std::collections::{BTreeSet, HashMap};
@@ -19,9 +22,11 @@ use std::time::SystemTime;
 
 use branch_forge::BranchForge;
 use cursive_core::theme::{BaseColor, Effect, Style};
+use gerrit::GerritForge;
 use git_branchless_invoke::CommandContext;
 use git_branchless_test::{RawTestOptions, ResolvedTestOptions, Verbosity};
 use github::GithubForge;
+use gitlab::GitlabForge;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use lib::core::dag::{union_all, CommitSet, Dag};
@@ -29,7 +34,7 @@ use lib::core::effects::Effects;
 use lib::core::eventlog::{EventLogDb, EventReplayer};
 use lib::core::formatting::{Pluralize, StyledStringBuilder};
 use lib::core::repo_ext::{RepoExt, RepoReferencesSnapshot};
-use lib::git::{GitRunInfo, NonZeroOid, Repo};
+use lib::git::{update_review_info_cache, GitRunInfo, NonZeroOid, Repo, ReviewInfo};
 use lib::try_exit_code;
 use lib::util::{ExitCode, EyreExitOr};
 
@@ -38,10 +43,13 @@ use git_branchless_opts::{
 };
 use git_branchless_revset::resolve_commits;
 use phabricator::PhabricatorForge;
+use serde::Serialize;
 use tracing::{debug, info, instrument, warn};
 
 use crate::github::github_push_remote;
 
+pub use branch_forge::{read_stack_records, should_record_stacks, StackRecord};
+
 lazy_static! {
     /// The style for branches which were successfully submitted.
     pub static ref STYLE_PUSHED: Style =
@@ -155,6 +163,20 @@ pub struct CreateStatus {
     pub local_commit_name: String,
 }
 
+/// Whether commits submitted to a forge can be landed with `git submit --land`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LandSupport {
+    /// The commits can be landed by pushing them to the main branch, once
+    /// their code reviews (if any) have been approved.
+    Supported,
+
+    /// The commits have to be landed through the forge itself.
+    Unsupported {
+        /// The name of the forge, for display purposes.
+        forge_name: &'static str,
+    },
+}
+
 /// "Forge" refers to a Git hosting provider, such as GitHub, GitLab, etc.
 /// Commits can be pushed for review to a forge.
 pub trait Forge: Debug {
@@ -177,6 +199,20 @@ pub trait Forge: Debug {
         commits: HashMap<NonZeroOid, CommitStatus>,
         options: &SubmitOptions,
     ) -> EyreExitOr<()>;
+
+    /// Get information about the code review for each of the provided commits,
+    /// such as whether it has been approved. Commits without an open code
+    /// review are omitted from the result. Returns `None` if the forge doesn't
+    /// report code review information, in which case commits can be landed
+    /// without approval.
+    fn query_review_infos(
+        &mut self,
+        commit_set: CommitSet,
+    ) -> EyreExitOr<Option<HashMap<NonZeroOid, ReviewInfo>>>;
+
+    /// Whether commits submitted to this forge can be landed with `git submit
+    /// --land`.
+    fn land_support(&self) -> LandSupport;
 }
 
 /// `submit` command.
@@ -195,6 +231,9 @@ pub fn command_main(ctx: CommandContext, args: SubmitArgs) -> EyreExitOr<()> {
         num_jobs,
         execution_strategy,
         dry_run,
+        status,
+        json,
+        land,
     } = args;
     submit(
         &effects,
@@ -208,6 +247,9 @@ pub fn command_main(ctx: CommandContext, args: SubmitArgs) -> EyreExitOr<()> {
         num_jobs,
         execution_strategy,
         dry_run,
+        status,
+        json,
+        land,
     )
 }
 
@@ -223,6 +265,9 @@ fn submit(
     num_jobs: Option<usize>,
     execution_strategy: Option<TestExecutionStrategy>,
     dry_run: bool,
+    status: bool,
+    json: bool,
+    land: bool,
 ) -> EyreExitOr<()> {
     let repo = Repo::from_current_dir()?;
     let conn = repo.get_db_conn()?;
@@ -257,6 +302,7 @@ fn submit(
         no_cache: true,
         interactive: false,
         jobs: num_jobs,
+        repeat: 1,
         verbosity: Verbosity::None,
         apply_fixes: false,
     };
@@ -268,8 +314,10 @@ fn submit(
         use_cache: _,
         is_interactive: _,
         num_jobs,
+        num_runs: _,
         verbosity: _,
         fix_options: _,
+        shared_cache: _,
     } = {
         let now = SystemTime::now();
         let event_tx_id =
@@ -294,8 +342,33 @@ fn submit(
     };
 
     let unioned_revset = Revset(revsets.iter().map(|Revset(inner)| inner).join(" + "));
+    if land {
+        return land::land(
+            effects,
+            git_run_info,
+            &repo,
+            &mut dag,
+            &event_log_db,
+            &references_snapshot,
+            &unioned_revset,
+            forge_kind,
+            commit_set,
+            dry_run,
+        );
+    }
+
+    // The forge holds onto the DAG, so sort the commits for the status report
+    // ahead of time.
+    let sorted_commit_oids = dag.sort(&commit_set)?;
+    // Keep the forge's progress output (such as the commands it runs) out of
+    // the JSON report so that it can be parsed.
+    let forge_effects = if status && json {
+        effects.suppress()
+    } else {
+        effects.clone()
+    };
     let mut forge = select_forge(
-        effects,
+        &forge_effects,
         git_run_info,
         &repo,
         &mut dag,
@@ -304,8 +377,19 @@ fn submit(
         &unioned_revset,
         forge_kind,
     )?;
-    let statuses = try_exit_code!(forge.query_status(commit_set)?);
+    let statuses = try_exit_code!(forge.query_status(commit_set.clone())?);
     debug!(?statuses, "Commit statuses");
+    if status {
+        let review_infos = try_exit_code!(forge.query_review_infos(commit_set)?);
+        return print_status_report(
+            effects,
+            &repo,
+            &sorted_commit_oids,
+            &statuses,
+            review_infos.as_ref(),
+            json,
+        );
+    }
 
     #[allow(clippy::type_complexity)]
     let (_local_commits, unsubmitted_commits, commits_to_update, commits_to_skip): (
@@ -413,6 +497,18 @@ fn submit(
         (updated_commit_names, skipped_commit_names)
     };
 
+    if !dry_run {
+        // Refresh the cached code review information which is shown in the
+        // smartlog, now that the code reviews have been created or updated.
+        if let Some(review_infos) = try_exit_code!(forge.query_review_infos(commit_set.clone())?) {
+            let review_infos = sorted_commit_oids
+                .iter()
+                .map(|commit_oid| (*commit_oid, review_infos.get(commit_oid).cloned()))
+                .collect();
+            update_review_info_cache(&repo, review_infos)?;
+        }
+    }
+
     if !submitted_commit_names.is_empty() {
         writeln!(
             effects.get_output_stream(),
@@ -517,6 +613,122 @@ repository. To submit them, retry this operation with the --create option.",
     Ok(Ok(()))
 }
 
+/// An entry in the JSON output of `git submit --status`.
+#[derive(Debug, Serialize)]
+struct StatusReportEntry {
+    commit_oid: String,
+    summary: String,
+    status: &'static str,
+    local_commit_name: Option<String>,
+    remote_name: Option<String>,
+    remote_commit_name: Option<String>,
+    review_url: Option<String>,
+}
+
+fn print_status_report(
+    effects: &Effects,
+    repo: &Repo,
+    commit_oids: &[NonZeroOid],
+    statuses: &HashMap<NonZeroOid, CommitStatus>,
+    review_infos: Option<&HashMap<NonZeroOid, ReviewInfo>>,
+    json: bool,
+) -> EyreExitOr<()> {
+    let mut entries = Vec::new();
+    let mut short_oids = Vec::new();
+    for commit_oid in commit_oids {
+        let commit = repo.find_commit_or_fail(*commit_oid)?;
+        short_oids.push(commit.get_short_oid()?);
+        // Forges don't necessarily report commits which they would never
+        // push, such as commits without branches for the branch forge.
+        let CommitStatus {
+            submit_status,
+            remote_name,
+            local_commit_name,
+            remote_commit_name,
+        } = statuses.get(commit_oid).cloned().unwrap_or(CommitStatus {
+            submit_status: SubmitStatus::Local,
+            remote_name: None,
+            local_commit_name: None,
+            remote_commit_name: None,
+        });
+        let status = match submit_status {
+            SubmitStatus::Local => "local",
+            SubmitStatus::Unsubmitted => "unsubmitted",
+            SubmitStatus::Unknown => "unknown",
+            SubmitStatus::UpToDate => "up-to-date",
+            SubmitStatus::NeedsUpdate => "needs-update",
+        };
+        entries.push(StatusReportEntry {
+            commit_oid: commit_oid.to_string(),
+            summary: String::from_utf8_lossy(&commit.get_summary()?).into_owned(),
+            status,
+            local_commit_name,
+            remote_name,
+            remote_commit_name,
+            review_url: review_infos
+                .and_then(|review_infos| review_infos.get(commit_oid))
+                .and_then(|review_info| review_info.url.clone()),
+        });
+    }
+
+    if json {
+        writeln!(
+            effects.get_output_stream(),
+            "{}",
+            serde_json::to_string_pretty(&entries)?
+        )?;
+        return Ok(Ok(()));
+    }
+
+    let rows: Vec<[String; 6]> = entries
+        .into_iter()
+        .zip(short_oids)
+        .map(|(entry, short_oid)| {
+            let StatusReportEntry {
+                commit_oid: _,
+                summary,
+                status,
+                local_commit_name,
+                remote_name: _,
+                remote_commit_name,
+                review_url,
+            } = entry;
+            [
+                short_oid,
+                status.to_owned(),
+                local_commit_name.unwrap_or_else(|| "-".to_owned()),
+                remote_commit_name.unwrap_or_else(|| "-".to_owned()),
+                review_url.unwrap_or_else(|| "-".to_owned()),
+                summary,
+            ]
+        })
+        .collect();
+    let header = [
+        "Commit",
+        "Status",
+        "Local name",
+        "Remote name",
+        "Review",
+        "Summary",
+    ]
+    .map(String::from);
+    let mut column_widths = [0; 6];
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        for (column_width, cell) in column_widths.iter_mut().zip(row.iter()) {
+            *column_width = (*column_width).max(cell.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line = row
+            .iter()
+            .zip(column_widths.iter())
+            .map(|(cell, column_width)| format!("{cell:column_width$}"))
+            .join("  ");
+        writeln!(effects.get_output_stream(), "{}", line.trim_end())?;
+    }
+    Ok(Ok(()))
+}
+
 #[instrument]
 fn select_forge<'a>(
     effects: &'a Effects,
@@ -582,13 +794,31 @@ fn select_forge<'a>(
             references_snapshot,
         }),
 
+        ForgeKind::Gerrit => Box::new(GerritForge {
+            effects,
+            git_run_info,
+            repo,
+            dag,
+            event_log_db,
+            client: GerritForge::client(git_run_info.clone(), repo)?,
+        }),
+
         ForgeKind::Github => Box::new(GithubForge {
             effects,
             git_run_info,
             repo,
             dag,
             event_log_db,
-            client: GithubForge::client(git_run_info.clone()),
+            client: GithubForge::client(git_run_info.clone(), repo)?,
+        }),
+
+        ForgeKind::Gitlab => Box::new(GitlabForge {
+            effects,
+            git_run_info,
+            repo,
+            dag,
+            event_log_db,
+            client: GitlabForge::client(git_run_info.clone()),
         }),
 
         ForgeKind::Phabricator => Box::new(PhabricatorForge {
@@ -26,7 +26,9 @@ use lib::core::rewrite::{
     execute_rebase_plan, BuildRebasePlanError, BuildRebasePlanOptions, ExecuteRebasePlanOptions,
     ExecuteRebasePlanResult, RebasePlanBuilder, RebasePlanPermissions, RepoResource,
 };
-use lib::git::{Commit, GitRunInfo, MaybeZeroOid, NonZeroOid, Repo, RepoError, TestCommand};
+use lib::git::{
+    Commit, GitRunInfo, MaybeZeroOid, NonZeroOid, Repo, RepoError, ReviewInfo, TestCommand,
+};
 use lib::try_exit_code;
 use lib::util::{ExitCode, EyreExitOr};
 use rayon::ThreadPoolBuilder;
@@ -35,7 +37,9 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{instrument, warn};
 
-use crate::{CommitStatus, CreateStatus, Forge, SubmitOptions, SubmitStatus, STYLE_PUSHED};
+use crate::{
+    CommitStatus, CreateStatus, Forge, LandSupport, SubmitOptions, SubmitStatus, STYLE_PUSHED,
+};
 
 /// Wrapper around the Phabricator "ID" type. (This is *not* a PHID, just a
 /// regular ID).
@@ -399,8 +403,10 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
                 use_cache: false,
                 is_interactive: false,
                 num_jobs: *num_jobs,
+                num_runs: 1,
                 verbosity: Verbosity::None,
                 fix_options: Some((execute_options.clone(), permissions.clone())),
+                shared_cache: None,
             },
         ) {
             Ok(Ok(test_results)) => test_results,
@@ -434,6 +440,7 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
             for (commit_oid, test_output) in test_outputs {
                 let head_commit_oid = match test_output.test_status {
                     TestStatus::CheckoutFailed
+                    | TestStatus::WorktreeSetupFailed(_)
                     | TestStatus::SpawnTestFailed(_)
                     | TestStatus::TerminatedBySignal
                     | TestStatus::AlreadyInProgress
@@ -636,8 +643,10 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
             use_cache: false,
             is_interactive: false,
             num_jobs: *num_jobs,
+            num_runs: 1,
             verbosity: Verbosity::None,
             fix_options: Some((execute_options, permissions)),
+            shared_cache: None,
         };
         let TestResults {
             search_bounds: _,
@@ -675,6 +684,7 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
             .partition(|(_commit_oid, test_output)| match test_output.test_status {
                 TestStatus::Passed { .. } => true,
                 TestStatus::CheckoutFailed
+                | TestStatus::WorktreeSetupFailed(_)
                 | TestStatus::SpawnTestFailed(_)
                 | TestStatus::TerminatedBySignal
                 | TestStatus::AlreadyInProgress
@@ -709,6 +719,21 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
         )?);
         Ok(Ok(()))
     }
+
+    #[instrument]
+    fn query_review_infos(
+        &mut self,
+        _commit_set: CommitSet,
+    ) -> EyreExitOr<Option<HashMap<NonZeroOid, ReviewInfo>>> {
+        Ok(Ok(None))
+    }
+
+    fn land_support(&self) -> LandSupport {
+        // Revisions are landed with `arc land`.
+        LandSupport::Unsupported {
+            forge_name: "Phabricator",
+        }
+    }
 }
 
 impl PhabricatorForge<'_> {
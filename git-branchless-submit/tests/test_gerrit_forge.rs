@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::fs;
+
+use git_branchless_submit::gerrit::testing::MockGerritClient;
+use lib::git::GitVersion;
+use lib::testing::{
+    make_git_with_remote_repo, remove_rebase_lines, Git, GitRunOptions, GitWrapperWithRemoteRepo,
+};
+
+/// Minimum version due to changes in the output of `git push`.
+const MIN_VERSION: GitVersion = GitVersion(2, 36, 0);
+
+fn mock_env(git: &Git) -> HashMap<String, String> {
+    git.get_base_env(0)
+        .into_iter()
+        .map(|(k, v)| {
+            (
+                k.to_str().unwrap().to_string(),
+                v.to_str().unwrap().to_string(),
+            )
+        })
+        .chain([(
+            git_branchless_submit::gerrit::MOCK_REMOTE_REPO_PATH_ENV_KEY.to_string(),
+            git.repo_path.clone().to_str().unwrap().to_owned(),
+        )])
+        .collect()
+}
+
+fn dump_state(local_repo: &Git, remote_repo: &Git) -> eyre::Result<String> {
+    let local_repo_smartlog: String = local_repo.smartlog()?;
+    let client = MockGerritClient {
+        remote_repo_path: remote_repo.repo_path.clone(),
+    };
+    // Process any pushes which the mock server hasn't seen yet.
+    client.with_state_mut(|_state| Ok(()))?;
+    let change_info_path = client.state_path();
+    let change_info =
+        fs::read_to_string(change_info_path).unwrap_or_else(|err| format!("Error: {err}"));
+    let state = format!(
+        "\
+Local state:
+{local_repo_smartlog}
+
+Change info:
+{change_info}
+"
+    );
+    Ok(state)
+}
+
+#[test]
+fn test_gerrit_forge_adds_change_ids() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _temp_dir,
+        original_repo: remote_repo,
+        cloned_repo: local_repo,
+    } = make_git_with_remote_repo()?;
+    if remote_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    remote_repo.init_repo()?;
+    remote_repo.clone_repo_into(&local_repo, &[])?;
+
+    local_repo.detach_head()?;
+    local_repo.commit_file("test1", 1)?;
+    local_repo.commit_file("test2", 2)?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "gerrit"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Skipped 2 commits (not yet on remote): create test1.txt, create test2.txt
+        These commits were skipped because they were not already associated with a remote
+        repository. To submit them, retry this operation with the --create option.
+        "###);
+    }
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--create", "--forge", "gerrit"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        let stdout = remove_rebase_lines(stdout);
+        insta::assert_snapshot!(stdout, @r###"
+        Attempting rebase in-memory...
+        [1/2] Committed as: b7ef1e7 create test1.txt
+        [2/2] Committed as: 08f2724 create test2.txt
+        branchless: running command: <git-executable> checkout 08f272443103d9938e4108e9bd6d2fcb357fbf17
+        In-memory rebase succeeded.
+        branchless: running command: <git-executable> push origin 08f272443103d9938e4108e9bd6d2fcb357fbf17:refs/for/master
+        Submitted 2 commits: I62fc20d2a290daea0d52bdc2ed2ad4be6491010e, I96d1c37a3d4363611c49f7e52186e189a04c531f
+        "###);
+    }
+    {
+        let stdout = local_repo.run(&["log", "--format=%B", "master.."])?.0;
+        insta::assert_snapshot!(stdout, @r###"
+        create test2.txt
+
+        Change-Id: I96d1c37a3d4363611c49f7e52186e189a04c531f
+
+        create test1.txt
+
+        Change-Id: I62fc20d2a290daea0d52bdc2ed2ad4be6491010e
+        "###);
+    }
+    {
+        let state = dump_state(&local_repo, &remote_repo)?;
+        insta::assert_snapshot!(state, @r###"
+        Local state:
+        O f777ecc (master) create initial.txt
+        |
+        o b7ef1e7 create test1.txt
+        |
+        @ 08f2724 create test2.txt
+
+
+        Change info:
+        {
+          "change_index": 2,
+          "changes": {
+            "I62fc20d2a290daea0d52bdc2ed2ad4be6491010e": {
+              "id": "I62fc20d2a290daea0d52bdc2ed2ad4be6491010e",
+              "number": 1,
+              "url": "https://gerrit.example.com/c/mock-gerrit-project/+/1",
+              "branch": "master",
+              "status": "NEW",
+              "wip": false,
+              "currentPatchSet": {
+                "number": 1,
+                "revision": "b7ef1e7568f080f978b60917605467ed4fe51741"
+              }
+            },
+            "I96d1c37a3d4363611c49f7e52186e189a04c531f": {
+              "id": "I96d1c37a3d4363611c49f7e52186e189a04c531f",
+              "number": 2,
+              "url": "https://gerrit.example.com/c/mock-gerrit-project/+/2",
+              "branch": "master",
+              "status": "NEW",
+              "wip": false,
+              "currentPatchSet": {
+                "number": 1,
+                "revision": "08f272443103d9938e4108e9bd6d2fcb357fbf17"
+              }
+            }
+          }
+        }
+        "###);
+    }
+
+    // Submitting again should be a no-op.
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "gerrit"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"Skipped 2 commits (already up-to-date): I62fc20d2a290daea0d52bdc2ed2ad4be6491010e, I96d1c37a3d4363611c49f7e52186e189a04c531f");
+    }
+
+    // Rewording the first commit should upload a new patch set for both
+    // changes, preserving their Change-Ids.
+    local_repo.branchless(
+        "reword",
+        &[
+            "HEAD^",
+            "-m",
+            "updated test1.txt\n\nChange-Id: I62fc20d2a290daea0d52bdc2ed2ad4be6491010e",
+        ],
+    )?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "gerrit"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        let stdout = remove_rebase_lines(stdout);
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push origin ef3935747c4c847d90b247a71420bb561dc297fa:refs/for/master
+        Updated 2 commits: I62fc20d2a290daea0d52bdc2ed2ad4be6491010e, I96d1c37a3d4363611c49f7e52186e189a04c531f
+        "###);
+    }
+    {
+        let state = dump_state(&local_repo, &remote_repo)?;
+        insta::assert_snapshot!(state, @r###"
+        Local state:
+        O f777ecc (master) create initial.txt
+        |
+        o 9fcfbf3 updated test1.txt
+        |
+        @ ef39357 create test2.txt
+
+
+        Change info:
+        {
+          "change_index": 2,
+          "changes": {
+            "I62fc20d2a290daea0d52bdc2ed2ad4be6491010e": {
+              "id": "I62fc20d2a290daea0d52bdc2ed2ad4be6491010e",
+              "number": 1,
+              "url": "https://gerrit.example.com/c/mock-gerrit-project/+/1",
+              "branch": "master",
+              "status": "NEW",
+              "wip": false,
+              "currentPatchSet": {
+                "number": 2,
+                "revision": "9fcfbf31cf6db2cdeb5c5ba0a76adf1f2390ce3b"
+              }
+            },
+            "I96d1c37a3d4363611c49f7e52186e189a04c531f": {
+              "id": "I96d1c37a3d4363611c49f7e52186e189a04c531f",
+              "number": 2,
+              "url": "https://gerrit.example.com/c/mock-gerrit-project/+/2",
+              "branch": "master",
+              "status": "NEW",
+              "wip": false,
+              "currentPatchSet": {
+                "number": 2,
+                "revision": "ef3935747c4c847d90b247a71420bb561dc297fa"
+              }
+            }
+          }
+        }
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gerrit_forge_preserves_change_id() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _temp_dir,
+        original_repo: remote_repo,
+        cloned_repo: local_repo,
+    } = make_git_with_remote_repo()?;
+    if remote_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    remote_repo.init_repo()?;
+    remote_repo.clone_repo_into(&local_repo, &[])?;
+
+    local_repo.detach_head()?;
+    local_repo.write_file_txt("test1", "contents\n")?;
+    local_repo.run(&["add", "."])?;
+    local_repo.run(&[
+        "commit",
+        "-m",
+        "create test1.txt\n\nSigned-off-by: Foo <foo@example.com>\nChange-Id: I0123456789abcdef0123456789abcdef01234567",
+    ])?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--create", "--draft", "--forge", "gerrit"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push origin 226868122c94c177e3b88d65cb6d4d9fc47e11be:refs/for/master%wip
+        Submitted 1 commit: I0123456789abcdef0123456789abcdef01234567
+        "###);
+    }
+    {
+        let state = dump_state(&local_repo, &remote_repo)?;
+        insta::assert_snapshot!(state, @r###"
+        Local state:
+        O f777ecc (master) create initial.txt
+        |
+        @ 2268681 create test1.txt
+
+
+        Change info:
+        {
+          "change_index": 1,
+          "changes": {
+            "I0123456789abcdef0123456789abcdef01234567": {
+              "id": "I0123456789abcdef0123456789abcdef01234567",
+              "number": 1,
+              "url": "https://gerrit.example.com/c/mock-gerrit-project/+/1",
+              "branch": "master",
+              "status": "NEW",
+              "wip": true,
+              "currentPatchSet": {
+                "number": 1,
+                "revision": "226868122c94c177e3b88d65cb6d4d9fc47e11be"
+              }
+            }
+          }
+        }
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gerrit_forge_land_unsupported() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _temp_dir,
+        original_repo: remote_repo,
+        cloned_repo: local_repo,
+    } = make_git_with_remote_repo()?;
+    if remote_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    remote_repo.init_repo()?;
+    remote_repo.clone_repo_into(&local_repo, &[])?;
+
+    local_repo.detach_head()?;
+    local_repo.commit_file("test1", 1)?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "gerrit", "--land"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"Landing is not supported for the Gerrit forge; submit through Gerrit instead.");
+    }
+
+    Ok(())
+}
@@ -96,14 +96,18 @@ fn test_github_forge_reorder_commits() -> eyre::Result<()> {
             },
         )?;
         insta::assert_snapshot!(stdout, @r###"
-        branchless: running command: <git-executable> push --set-upstream origin mock-github-username/create-test1-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-github-username/create-test1-txt
         branch 'mock-github-username/create-test1-txt' set up to track 'origin/mock-github-username/create-test1-txt'.
-        branchless: running command: <git-executable> push --set-upstream origin mock-github-username/create-test2-txt
+        Pushed branch mock-github-username/create-test1-txt (62fc20d create test1.txt)
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-github-username/create-test2-txt
         branch 'mock-github-username/create-test2-txt' set up to track 'origin/mock-github-username/create-test2-txt'.
+        Pushed branch mock-github-username/create-test2-txt (96d1c37 create test2.txt)
         Updating pull request (title, body) for commit 62fc20d create test1.txt
-        branchless: running command: <git-executable> push --force-with-lease origin mock-github-username/create-test1-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test1-txt
+        Pushed branch mock-github-username/create-test1-txt (62fc20d create test1.txt)
         Updating pull request (base branch, title, body) for commit 96d1c37 create test2.txt
-        branchless: running command: <git-executable> push --force-with-lease origin mock-github-username/create-test2-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test2-txt
+        Pushed branch mock-github-username/create-test2-txt (96d1c37 create test2.txt)
         Submitted 2 commits: mock-github-username/create-test1-txt, mock-github-username/create-test2-txt
         "###);
     }
@@ -113,9 +117,9 @@ fn test_github_forge_reorder_commits() -> eyre::Result<()> {
         Local state:
         O f777ecc (master) create initial.txt
         |
-        o 62fc20d (mock-github-username/create-test1-txt) create test1.txt
+        o 62fc20d (mock-github-username/create-test1-txt) #1 create test1.txt
         |
-        @ 96d1c37 (mock-github-username/create-test2-txt) create test2.txt
+        @ 96d1c37 (mock-github-username/create-test2-txt) #2 create test2.txt
 
 
         Remote state:
@@ -139,7 +143,7 @@ fn test_github_forge_reorder_commits() -> eyre::Result<()> {
               "closed": false,
               "isDraft": false,
               "title": "[1/2] create test1.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n\n---\n\ncreate test1.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/1\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test1.txt\n"
             },
             "mock-github-username/create-test2-txt": {
               "number": 2,
@@ -150,7 +154,7 @@ fn test_github_forge_reorder_commits() -> eyre::Result<()> {
               "closed": false,
               "isDraft": false,
               "title": "[2/2] create test2.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n\n---\n\ncreate test2.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test2.txt\n"
             }
           }
         }
@@ -172,9 +176,11 @@ fn test_github_forge_reorder_commits() -> eyre::Result<()> {
         )?;
         insta::assert_snapshot!(stdout, @r###"
         Updating pull request (commit, base branch, title, body) for commit fe65c1f create test2.txt
-        branchless: running command: <git-executable> push --force-with-lease origin mock-github-username/create-test2-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test2-txt
+        Pushed branch mock-github-username/create-test2-txt (fe65c1f create test2.txt)
         Updating pull request (commit, base branch, title, body) for commit 0770943 create test1.txt
-        branchless: running command: <git-executable> push --force-with-lease origin mock-github-username/create-test1-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test1-txt
+        Pushed branch mock-github-username/create-test1-txt (0770943 create test1.txt)
         Updated 2 commits: mock-github-username/create-test1-txt, mock-github-username/create-test2-txt
         "###);
     }
@@ -184,9 +190,9 @@ fn test_github_forge_reorder_commits() -> eyre::Result<()> {
         Local state:
         O f777ecc (master) create initial.txt
         |
-        @ fe65c1f (> mock-github-username/create-test2-txt) create test2.txt
+        @ fe65c1f (> mock-github-username/create-test2-txt) #2 create test2.txt
         |
-        o 0770943 (mock-github-username/create-test1-txt) create test1.txt
+        o 0770943 (mock-github-username/create-test1-txt) #1 create test1.txt
 
 
         Remote state:
@@ -210,7 +216,7 @@ fn test_github_forge_reorder_commits() -> eyre::Result<()> {
               "closed": false,
               "isDraft": false,
               "title": "[2/2] create test1.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n\n\n---\n\ncreate test1.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/1\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test1.txt\n"
             },
             "mock-github-username/create-test2-txt": {
               "number": 2,
@@ -221,7 +227,7 @@ fn test_github_forge_reorder_commits() -> eyre::Result<()> {
               "closed": false,
               "isDraft": false,
               "title": "[1/2] create test2.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n\n\n---\n\ncreate test2.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/2\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test2.txt\n"
             }
           }
         }
@@ -259,14 +265,18 @@ fn test_github_forge_mock_client_closes_pull_requests() -> eyre::Result<()> {
             },
         )?;
         insta::assert_snapshot!(stdout, @r###"
-        branchless: running command: <git-executable> push --set-upstream origin mock-github-username/create-test1-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-github-username/create-test1-txt
         branch 'mock-github-username/create-test1-txt' set up to track 'origin/mock-github-username/create-test1-txt'.
-        branchless: running command: <git-executable> push --set-upstream origin mock-github-username/create-test2-txt
+        Pushed branch mock-github-username/create-test1-txt (62fc20d create test1.txt)
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-github-username/create-test2-txt
         branch 'mock-github-username/create-test2-txt' set up to track 'origin/mock-github-username/create-test2-txt'.
+        Pushed branch mock-github-username/create-test2-txt (96d1c37 create test2.txt)
         Updating pull request (title, body) for commit 62fc20d create test1.txt
-        branchless: running command: <git-executable> push --force-with-lease origin mock-github-username/create-test1-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test1-txt
+        Pushed branch mock-github-username/create-test1-txt (62fc20d create test1.txt)
         Updating pull request (base branch, title, body) for commit 96d1c37 create test2.txt
-        branchless: running command: <git-executable> push --force-with-lease origin mock-github-username/create-test2-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test2-txt
+        Pushed branch mock-github-username/create-test2-txt (96d1c37 create test2.txt)
         Submitted 2 commits: mock-github-username/create-test1-txt, mock-github-username/create-test2-txt
         "###);
     }
@@ -276,9 +286,9 @@ fn test_github_forge_mock_client_closes_pull_requests() -> eyre::Result<()> {
         Local state:
         O f777ecc (master) create initial.txt
         |
-        o 62fc20d (mock-github-username/create-test1-txt) create test1.txt
+        o 62fc20d (mock-github-username/create-test1-txt) #1 create test1.txt
         |
-        @ 96d1c37 (mock-github-username/create-test2-txt) create test2.txt
+        @ 96d1c37 (mock-github-username/create-test2-txt) #2 create test2.txt
 
 
         Remote state:
@@ -302,7 +312,7 @@ fn test_github_forge_mock_client_closes_pull_requests() -> eyre::Result<()> {
               "closed": false,
               "isDraft": false,
               "title": "[1/2] create test1.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n\n---\n\ncreate test1.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/1\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test1.txt\n"
             },
             "mock-github-username/create-test2-txt": {
               "number": 2,
@@ -313,7 +323,7 @@ fn test_github_forge_mock_client_closes_pull_requests() -> eyre::Result<()> {
               "closed": false,
               "isDraft": false,
               "title": "[2/2] create test2.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n\n---\n\ncreate test2.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test2.txt\n"
             }
           }
         }
@@ -358,7 +368,8 @@ fn test_github_forge_mock_client_closes_pull_requests() -> eyre::Result<()> {
         )?;
         insta::assert_snapshot!(stdout, @r###"
         Updating pull request (commit, base branch, title, body) for commit fa46633 create test2.txt
-        branchless: running command: <git-executable> push --force-with-lease origin mock-github-username/create-test2-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test2-txt
+        Pushed branch mock-github-username/create-test2-txt (fa46633 create test2.txt)
         Updated 1 commit: mock-github-username/create-test2-txt
         "###);
     }
@@ -369,7 +380,7 @@ fn test_github_forge_mock_client_closes_pull_requests() -> eyre::Result<()> {
         :
         O 047b7ad (master) create test1.txt
         |
-        @ fa46633 (> mock-github-username/create-test2-txt) create test2.txt
+        @ fa46633 (> mock-github-username/create-test2-txt) #2 create test2.txt
 
 
         Remote state:
@@ -392,7 +403,7 @@ fn test_github_forge_mock_client_closes_pull_requests() -> eyre::Result<()> {
               "closed": true,
               "isDraft": false,
               "title": "[1/2] create test1.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n\n---\n\ncreate test1.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/1\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test1.txt\n"
             },
             "mock-github-username/create-test2-txt": {
               "number": 2,
@@ -403,7 +414,7 @@ fn test_github_forge_mock_client_closes_pull_requests() -> eyre::Result<()> {
               "closed": false,
               "isDraft": false,
               "title": "[1/1] create test2.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n\n---\n\ncreate test2.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test2.txt\n"
             }
           }
         }
@@ -454,10 +465,12 @@ fn test_github_forge_no_include_unsubmitted_commits_in_stack() -> eyre::Result<(
             },
         )?;
         insta::assert_snapshot!(stdout, @r###"
-        branchless: running command: <git-executable> push --set-upstream origin mock-github-username/create-test1-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-github-username/create-test1-txt
         branch 'mock-github-username/create-test1-txt' set up to track 'origin/mock-github-username/create-test1-txt'.
+        Pushed branch mock-github-username/create-test1-txt (62fc20d create test1.txt)
         Updating pull request (title, body) for commit 62fc20d create test1.txt
-        branchless: running command: <git-executable> push --force-with-lease origin mock-github-username/create-test1-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test1-txt
+        Pushed branch mock-github-username/create-test1-txt (62fc20d create test1.txt)
         Submitted 1 commit: mock-github-username/create-test1-txt
         "###);
     }
@@ -467,7 +480,7 @@ fn test_github_forge_no_include_unsubmitted_commits_in_stack() -> eyre::Result<(
         Local state:
         O f777ecc (master) create initial.txt
         |
-        o 62fc20d (mock-github-username/create-test1-txt) create test1.txt
+        o 62fc20d (mock-github-username/create-test1-txt) #1 create test1.txt
         |
         o 96d1c37 create test2.txt
         |
@@ -493,7 +506,7 @@ fn test_github_forge_no_include_unsubmitted_commits_in_stack() -> eyre::Result<(
               "closed": false,
               "isDraft": false,
               "title": "[1/1] create test1.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n\n\n---\n\ncreate test1.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/1\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test1.txt\n"
             }
           }
         }
@@ -544,10 +557,12 @@ fn test_github_forge_multiple_commits_in_pull_request() -> eyre::Result<()> {
             },
         )?;
         insta::assert_snapshot!(stdout, @r###"
-        branchless: running command: <git-executable> push --set-upstream origin mock-github-username/create-test3-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-github-username/create-test3-txt
         branch 'mock-github-username/create-test3-txt' set up to track 'origin/mock-github-username/create-test3-txt'.
+        Pushed branch mock-github-username/create-test3-txt (70deb1e create test3.txt)
         Updating pull request (title, body) for commit 70deb1e create test3.txt
-        branchless: running command: <git-executable> push --force-with-lease origin mock-github-username/create-test3-txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test3-txt
+        Pushed branch mock-github-username/create-test3-txt (70deb1e create test3.txt)
         Submitted 1 commit: mock-github-username/create-test3-txt
         "###);
     }
@@ -561,7 +576,7 @@ fn test_github_forge_multiple_commits_in_pull_request() -> eyre::Result<()> {
         |
         o 96d1c37 create test2.txt
         |
-        @ 70deb1e (mock-github-username/create-test3-txt) create test3.txt
+        @ 70deb1e (mock-github-username/create-test3-txt) #1 create test3.txt
 
 
         Remote state:
@@ -587,7 +602,400 @@ fn test_github_forge_multiple_commits_in_pull_request() -> eyre::Result<()> {
               "closed": false,
               "isDraft": false,
               "title": "[1/1] create test3.txt",
-              "body": "**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n\n\n---\n\ncreate test3.txt\n\n"
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/1\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test3.txt\n"
+            }
+          }
+        }
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_github_forge_preserves_body_outside_stack_block() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _temp_dir,
+        original_repo: remote_repo,
+        cloned_repo: local_repo,
+    } = make_git_with_remote_repo()?;
+    if remote_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    remote_repo.init_repo()?;
+    remote_repo.clone_repo_into(&local_repo, &[])?;
+
+    local_repo.detach_head()?;
+    local_repo.commit_file("test1", 1)?;
+    {
+        let (_stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "github", "--create"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+    }
+
+    // Simulate the author editing the pull request description.
+    let client = MockGithubClient {
+        remote_repo_path: remote_repo.repo_path.clone(),
+    };
+    client.with_state_mut(|state| {
+        let pull_request_info = state
+            .pull_requests
+            .get_mut("mock-github-username/create-test1-txt")
+            .unwrap();
+        pull_request_info.body = format!(
+            "Some text before the stack.\n\n{}\nSome text after the stack.\n",
+            pull_request_info.body
+        );
+        Ok(())
+    })?;
+
+    local_repo.commit_file("test2", 2)?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "github", "--create"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-github-username/create-test2-txt
+        branch 'mock-github-username/create-test2-txt' set up to track 'origin/mock-github-username/create-test2-txt'.
+        Pushed branch mock-github-username/create-test2-txt (96d1c37 create test2.txt)
+        Updating pull request (title, body) for commit 62fc20d create test1.txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test1-txt
+        Pushed branch mock-github-username/create-test1-txt (62fc20d create test1.txt)
+        Updating pull request (base branch, title, body) for commit 96d1c37 create test2.txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-github-username/create-test2-txt
+        Pushed branch mock-github-username/create-test2-txt (96d1c37 create test2.txt)
+        Submitted 1 commit: mock-github-username/create-test2-txt
+        Skipped 1 commit (already up-to-date): mock-github-username/create-test1-txt
+        "###);
+    }
+
+    let bodies = {
+        let mut bodies = String::new();
+        client.with_state_mut(|state| {
+            for pull_request_info in state.pull_requests.values() {
+                bodies.push_str(&format!(
+                    "# {}\n{}\n",
+                    pull_request_info.title, pull_request_info.body
+                ));
+            }
+            Ok(())
+        })?;
+        bodies
+    };
+    insta::assert_snapshot!(bodies, @r###"
+    # [1/2] create test1.txt
+    Some text before the stack.
+
+    <!-- git-branchless: stack start -->
+    **Stack:**
+
+    * ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/1
+    * https://example.com/mock-github-username/mock-github-repo/pulls/2
+
+    ---
+    <!-- git-branchless: stack end -->
+
+    create test1.txt
+
+    Some text after the stack.
+
+    # [2/2] create test2.txt
+    <!-- git-branchless: stack start -->
+    **Stack:**
+
+    * https://example.com/mock-github-username/mock-github-repo/pulls/1
+    * ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/2
+
+    ---
+    <!-- git-branchless: stack end -->
+
+    create test2.txt
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn test_github_forge_review_status_in_smartlog() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _temp_dir,
+        original_repo: remote_repo,
+        cloned_repo: local_repo,
+    } = make_git_with_remote_repo()?;
+    if remote_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    remote_repo.init_repo()?;
+    remote_repo.clone_repo_into(&local_repo, &[])?;
+
+    local_repo.detach_head()?;
+    local_repo.commit_file("test1", 1)?;
+    local_repo.commit_file("test2", 2)?;
+    local_repo.branchless_with_options(
+        "submit",
+        &["--forge", "github", "--create"],
+        &GitRunOptions {
+            env: mock_env(&remote_repo),
+            ..Default::default()
+        },
+    )?;
+
+    let client = MockGithubClient {
+        remote_repo_path: remote_repo.repo_path.clone(),
+    };
+    client.with_state_mut(|state| {
+        let pull_request_info = state
+            .pull_requests
+            .get_mut("mock-github-username/create-test1-txt")
+            .unwrap();
+        pull_request_info.review_decision = Some("APPROVED".to_owned());
+        pull_request_info.checks_state = Some("SUCCESS".to_owned());
+        let pull_request_info = state
+            .pull_requests
+            .get_mut("mock-github-username/create-test2-txt")
+            .unwrap();
+        pull_request_info.review_decision = Some("CHANGES_REQUESTED".to_owned());
+        pull_request_info.checks_state = Some("PENDING".to_owned());
+        Ok(())
+    })?;
+
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "github", "--status"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Commit   Status      Local name                             Remote name                            Review                                                             Summary
+        62fc20d  up-to-date  mock-github-username/create-test1-txt  mock-github-username/create-test1-txt  https://example.com/mock-github-username/mock-github-repo/pulls/1  create test1.txt
+        96d1c37  up-to-date  mock-github-username/create-test2-txt  mock-github-username/create-test2-txt  https://example.com/mock-github-username/mock-github-repo/pulls/2  create test2.txt
+        "###);
+    }
+
+    // The smartlog only reads the cache, and `git submit --status` doesn't
+    // update it, so it isn't updated until the next time commits are
+    // submitted.
+    {
+        let stdout = local_repo.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d (mock-github-username/create-test1-txt) #1 create test1.txt
+        |
+        @ 96d1c37 (mock-github-username/create-test2-txt) #2 create test2.txt
+        "###);
+    }
+    local_repo.branchless_with_options(
+        "submit",
+        &["--forge", "github"],
+        &GitRunOptions {
+            env: mock_env(&remote_repo),
+            ..Default::default()
+        },
+    )?;
+    {
+        let stdout = local_repo.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d (mock-github-username/create-test1-txt) #1 (approved, checks passed) create test1.txt
+        |
+        @ 96d1c37 (mock-github-username/create-test2-txt) #2 (changes requested, checks pending) create test2.txt
+        "###);
+    }
+
+    local_repo.run(&[
+        "config",
+        "branchless.commitDescriptors.reviewStatus",
+        "false",
+    ])?;
+    {
+        let stdout = local_repo.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d (mock-github-username/create-test1-txt) create test1.txt
+        |
+        @ 96d1c37 (mock-github-username/create-test2-txt) create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_github_forge_land() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _temp_dir,
+        original_repo: remote_repo,
+        cloned_repo: local_repo,
+    } = make_git_with_remote_repo()?;
+    if remote_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    remote_repo.init_repo()?;
+    remote_repo.clone_repo_into(&local_repo, &[])?;
+
+    local_repo.detach_head()?;
+    local_repo.commit_file("test1", 1)?;
+    local_repo.commit_file("test2", 2)?;
+    local_repo.branchless_with_options(
+        "submit",
+        &["--forge", "github", "--create"],
+        &GitRunOptions {
+            env: mock_env(&remote_repo),
+            ..Default::default()
+        },
+    )?;
+
+    let client = MockGithubClient {
+        remote_repo_path: remote_repo.repo_path.clone(),
+    };
+    let set_review_decision = |branch_name: &str, review_decision: &str| {
+        client.with_state_mut(|state| {
+            state
+                .pull_requests
+                .get_mut(branch_name)
+                .unwrap()
+                .review_decision = Some(review_decision.to_owned());
+            Ok(())
+        })
+    };
+    set_review_decision("mock-github-username/create-test1-txt", "APPROVED")?;
+    set_review_decision("mock-github-username/create-test2-txt", "REVIEW_REQUIRED")?;
+
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "github", "--land"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"Cannot land commit 96d1c37 create test2.txt: its code review hasn't been approved");
+    }
+
+    set_review_decision("mock-github-username/create-test2-txt", "APPROVED")?;
+    let set_checks_state = |branch_name: &str, checks_state: &str| {
+        client.with_state_mut(|state| {
+            state
+                .pull_requests
+                .get_mut(branch_name)
+                .unwrap()
+                .checks_state = Some(checks_state.to_owned());
+            Ok(())
+        })
+    };
+    set_checks_state("mock-github-username/create-test2-txt", "FAILURE")?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "github", "--land"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"Cannot land commit 96d1c37 create test2.txt: its CI checks failed");
+    }
+
+    set_checks_state("mock-github-username/create-test2-txt", "SUCCESS")?;
+    // The remote repository's main branch can't be pushed to while checked out.
+    remote_repo.detach_head()?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "github", "--land", "--dry-run"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"Would land 2 commits onto master.");
+    }
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "github", "--land"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push origin 96d1c37a3d4363611c49f7e52186e189a04c531f:refs/heads/master
+        Landed 2 commits onto master.
+        "###);
+    }
+    // Process the push so that the landed pull requests are closed. The second
+    // pull request is based on the first one's branch, so it stays open, and
+    // its cached review information is kept.
+    client.with_state_mut(|_state| Ok(()))?;
+    {
+        let state = dump_state(&local_repo, &remote_repo)?;
+        insta::assert_snapshot!(state, @r###"
+        Local state:
+        :
+        O 62fc20d (mock-github-username/create-test1-txt) create test1.txt
+        |
+        @ 96d1c37 (master, mock-github-username/create-test2-txt) #2 (approved, checks passed) create test2.txt
+
+
+        Remote state:
+        @ f777ecc create initial.txt
+        |
+        O 62fc20d (mock-github-username/create-test1-txt) create test1.txt
+        |
+        O 96d1c37 (master, mock-github-username/create-test2-txt) create test2.txt
+
+
+        Pull request info:
+        {
+          "pull_request_index": 2,
+          "pull_requests": {
+            "mock-github-username/create-test1-txt": {
+              "number": 1,
+              "url": "https://example.com/mock-github-username/mock-github-repo/pulls/1",
+              "headRefName": "mock-github-username/create-test1-txt",
+              "headRefOid": "62fc20d2a290daea0d52bdc2ed2ad4be6491010e",
+              "baseRefName": "master",
+              "closed": true,
+              "isDraft": false,
+              "title": "[1/2] create test1.txt",
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/1\n* https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test1.txt\n",
+              "reviewDecision": "APPROVED"
+            },
+            "mock-github-username/create-test2-txt": {
+              "number": 2,
+              "url": "https://example.com/mock-github-username/mock-github-repo/pulls/2",
+              "headRefName": "mock-github-username/create-test2-txt",
+              "headRefOid": "96d1c37a3d4363611c49f7e52186e189a04c531f",
+              "baseRefName": "mock-github-username/create-test1-txt",
+              "closed": false,
+              "isDraft": false,
+              "title": "[2/2] create test2.txt",
+              "body": "<!-- git-branchless: stack start -->\n**Stack:**\n\n* https://example.com/mock-github-username/mock-github-repo/pulls/1\n* ➡️ https://example.com/mock-github-username/mock-github-repo/pulls/2\n\n---\n<!-- git-branchless: stack end -->\n\ncreate test2.txt\n",
+              "reviewDecision": "APPROVED",
+              "checksState": "SUCCESS"
             }
           }
         }
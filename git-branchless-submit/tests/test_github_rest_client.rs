@@ -0,0 +1,272 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+use git_branchless_opts::TestExecutionStrategy;
+use git_branchless_submit::github::testing::{
+    CreatePullRequestArgs, GithubClient, RestGithubClient, UpdatePullRequestArgs,
+};
+use git_branchless_submit::SubmitOptions;
+use lib::core::effects::Effects;
+use lib::core::formatting::Glyphs;
+use lib::git::NonZeroOid;
+use lib::util::ExitCode;
+
+/// A request received by the mock server, with nondeterministic headers
+/// removed.
+#[derive(Debug)]
+struct MockRequest {
+    request_line: String,
+    authorization: Option<String>,
+    body: String,
+}
+
+/// Start a mock GitHub API server which serves the given responses in order,
+/// one per connection, and returns the requests that it received.
+fn serve(responses: Vec<(u16, String)>) -> eyre::Result<(String, JoinHandle<Vec<MockRequest>>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let handle = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for (status, response_body) in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut authorization = None;
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(':').unwrap();
+                match name.to_ascii_lowercase().as_str() {
+                    "authorization" => authorization = Some(value.trim().to_owned()),
+                    "content-length" => content_length = value.trim().parse().unwrap(),
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response_body}",
+                response_body.len()
+            )
+            .unwrap();
+            requests.push(MockRequest {
+                request_line: request_line.trim_end().to_owned(),
+                authorization,
+                body: String::from_utf8(body).unwrap(),
+            });
+        }
+        requests
+    });
+    Ok((url, handle))
+}
+
+fn make_client(api_base_url: String) -> RestGithubClient {
+    RestGithubClient {
+        api_base_url,
+        owner: "mock-owner".to_owned(),
+        repo_name: "mock-repo".to_owned(),
+        token: "mock-token".to_owned(),
+    }
+}
+
+fn submit_options() -> SubmitOptions {
+    SubmitOptions {
+        create: true,
+        draft: false,
+        execution_strategy: TestExecutionStrategy::WorkingCopy,
+        num_jobs: 1,
+        message: None,
+    }
+}
+
+fn pull_request_json(number: usize, head_ref_name: &str) -> serde_json::Value {
+    serde_json::json!({
+        "number": number,
+        "url": format!("https://github.com/mock-owner/mock-repo/pull/{number}"),
+        "headRefName": head_ref_name,
+        "headRefOid": "62fc20d2a290daea0d52bdc2ed2ad4be6491010e",
+        "baseRefName": "master",
+        "closed": false,
+        "isDraft": false,
+        "title": format!("PR {number}"),
+        "body": "",
+    })
+}
+
+#[test]
+fn test_github_rest_client_query_pull_requests_paginates() -> eyre::Result<()> {
+    let first_page = serde_json::json!({
+        "data": {
+            "search": {
+                "pageInfo": { "hasNextPage": true, "endCursor": "cursor1" },
+                "nodes": [pull_request_json(1, "foo")],
+            }
+        }
+    });
+    let mut reviewed_pull_request = pull_request_json(2, "bar");
+    reviewed_pull_request["reviewDecision"] = "CHANGES_REQUESTED".into();
+    reviewed_pull_request["commits"] = serde_json::json!({
+        "nodes": [{ "commit": { "statusCheckRollup": { "state": "ERROR" } } }],
+    });
+    let second_page = serde_json::json!({
+        "data": {
+            "search": {
+                "pageInfo": { "hasNextPage": false, "endCursor": null },
+                "nodes": [reviewed_pull_request],
+            }
+        }
+    });
+    let (url, handle) = serve(vec![
+        (200, first_page.to_string()),
+        (200, second_page.to_string()),
+    ])?;
+
+    // The GraphQL endpoint for GitHub Enterprise is `/api/graphql`, next to
+    // the `/api/v3` REST endpoint.
+    let client = make_client(format!("{url}/api/v3"));
+    let effects = Effects::new_suppress_for_test(Glyphs::text());
+    let pull_request_infos = client.query_repo_pull_request_infos(&effects)?.unwrap();
+    let mut numbers = pull_request_infos
+        .iter()
+        .map(|(head_ref_name, info)| (head_ref_name.as_str(), info.number))
+        .collect::<Vec<_>>();
+    numbers.sort();
+    assert_eq!(numbers, vec![("bar", 2), ("foo", 1)]);
+    insta::assert_debug_snapshot!(pull_request_infos["bar"].to_review_info(), @r###"
+    ReviewInfo {
+        name: "#2",
+        url: Some(
+            "https://github.com/mock-owner/mock-repo/pull/2",
+        ),
+        review_state: Some(
+            ChangesRequested,
+        ),
+        checks_state: Some(
+            Failure,
+        ),
+    }
+    "###);
+    insta::assert_debug_snapshot!(pull_request_infos["foo"].to_review_info(), @r###"
+    ReviewInfo {
+        name: "#1",
+        url: Some(
+            "https://github.com/mock-owner/mock-repo/pull/1",
+        ),
+        review_state: Some(
+            NotRequired,
+        ),
+        checks_state: None,
+    }
+    "###);
+
+    let requests = handle.join().unwrap();
+    assert_eq!(requests.len(), 2);
+    for request in &requests {
+        assert_eq!(request.request_line, "POST /api/graphql HTTP/1.1");
+        assert_eq!(request.authorization.as_deref(), Some("Bearer mock-token"));
+    }
+    let first_request: serde_json::Value = serde_json::from_str(&requests[0].body)?;
+    insta::assert_snapshot!(first_request["variables"], @r###"{"cursor":null,"query":"repo:mock-owner/mock-repo is:pr is:open author:@me"}"###);
+    let second_request: serde_json::Value = serde_json::from_str(&requests[1].body)?;
+    insta::assert_snapshot!(second_request["variables"], @r###"{"cursor":"cursor1","query":"repo:mock-owner/mock-repo is:pr is:open author:@me"}"###);
+
+    Ok(())
+}
+
+#[test]
+fn test_github_rest_client_create_and_update_pull_request() -> eyre::Result<()> {
+    let (url, handle) = serve(vec![
+        (
+            201,
+            serde_json::json!({
+                "html_url": "https://github.com/mock-owner/mock-repo/pull/3"
+            })
+            .to_string(),
+        ),
+        (200, pull_request_json(3, "foo").to_string()),
+    ])?;
+    let client = make_client(url);
+    let effects = Effects::new_suppress_for_test(Glyphs::text());
+    let head_ref_oid: NonZeroOid = "62fc20d2a290daea0d52bdc2ed2ad4be6491010e".parse()?;
+
+    let pull_request_url = client
+        .create_pull_request(
+            &effects,
+            CreatePullRequestArgs {
+                head_ref_oid,
+                head_ref_name: "foo".to_owned(),
+                base_ref_name: "master".to_owned(),
+                title: "my title".to_owned(),
+                body: "my body".to_owned(),
+            },
+            &submit_options(),
+        )?
+        .unwrap();
+    assert_eq!(
+        pull_request_url,
+        "https://github.com/mock-owner/mock-repo/pull/3"
+    );
+
+    client
+        .update_pull_request(
+            &effects,
+            3,
+            UpdatePullRequestArgs {
+                head_ref_oid,
+                base_ref_name: "bar".to_owned(),
+                title: "new title".to_owned(),
+                body: "new body".to_owned(),
+            },
+            &submit_options(),
+        )?
+        .unwrap();
+
+    let requests = handle.join().unwrap();
+    insta::assert_debug_snapshot!(requests, @r###"
+    [
+        MockRequest {
+            request_line: "POST /repos/mock-owner/mock-repo/pulls HTTP/1.1",
+            authorization: Some(
+                "Bearer mock-token",
+            ),
+            body: "{\"base\":\"master\",\"body\":\"my body\",\"draft\":false,\"head\":\"foo\",\"title\":\"my title\"}",
+        },
+        MockRequest {
+            request_line: "PATCH /repos/mock-owner/mock-repo/pulls/3 HTTP/1.1",
+            authorization: Some(
+                "Bearer mock-token",
+            ),
+            body: "{\"base\":\"bar\",\"body\":\"new body\",\"title\":\"new title\"}",
+        },
+    ]
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn test_github_rest_client_http_error() -> eyre::Result<()> {
+    let (url, handle) = serve(vec![(
+        401,
+        serde_json::json!({ "message": "Bad credentials" }).to_string(),
+    )])?;
+    let client = make_client(url);
+    let effects = Effects::new_suppress_for_test(Glyphs::text());
+    let result = client.query_github_username(&effects)?;
+    assert!(matches!(result, Err(ExitCode(1))));
+
+    let requests = handle.join().unwrap();
+    assert_eq!(requests[0].request_line, "GET /user HTTP/1.1");
+
+    Ok(())
+}
@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::fs;
+
+use git_branchless_submit::gitlab::testing::MockGitlabClient;
+use lib::git::GitVersion;
+use lib::testing::{
+    make_git_with_remote_repo, remove_rebase_lines, Git, GitRunOptions, GitWrapperWithRemoteRepo,
+};
+
+/// Minimum version due to changes in the output of `git push`.
+const MIN_VERSION: GitVersion = GitVersion(2, 36, 0);
+
+fn mock_env(git: &Git) -> HashMap<String, String> {
+    git.get_base_env(0)
+        .into_iter()
+        .map(|(k, v)| {
+            (
+                k.to_str().unwrap().to_string(),
+                v.to_str().unwrap().to_string(),
+            )
+        })
+        .chain([(
+            git_branchless_submit::gitlab::MOCK_REMOTE_REPO_PATH_ENV_KEY.to_string(),
+            git.repo_path.clone().to_str().unwrap().to_owned(),
+        )])
+        .collect()
+}
+
+fn dump_state(local_repo: &Git, remote_repo: &Git) -> eyre::Result<String> {
+    let local_repo_smartlog: String = local_repo.smartlog()?;
+    let remote_repo_smartlog = remote_repo.smartlog()?;
+    let client = MockGitlabClient {
+        remote_repo_path: remote_repo.repo_path.clone(),
+    };
+    let merge_request_info_path = client.state_path();
+    let merge_request_info =
+        fs::read_to_string(merge_request_info_path).unwrap_or_else(|err| format!("Error: {err}"));
+    let state = format!(
+        "\
+Local state:
+{local_repo_smartlog}
+
+Remote state:
+{remote_repo_smartlog}
+
+Merge request info:
+{merge_request_info}
+"
+    );
+    Ok(state)
+}
+
+#[test]
+fn test_gitlab_forge_stack_targets_previous_branch() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _temp_dir,
+        original_repo: remote_repo,
+        cloned_repo: local_repo,
+    } = make_git_with_remote_repo()?;
+    if remote_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    remote_repo.init_repo()?;
+    remote_repo.clone_repo_into(&local_repo, &[])?;
+
+    local_repo.detach_head()?;
+    local_repo.commit_file("test1", 1)?;
+    local_repo.commit_file("test2", 2)?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--create", "--forge", "gitlab"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-gitlab-username/create-test1-txt
+        branch 'mock-gitlab-username/create-test1-txt' set up to track 'origin/mock-gitlab-username/create-test1-txt'.
+        Pushed branch mock-gitlab-username/create-test1-txt (62fc20d create test1.txt)
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-gitlab-username/create-test2-txt
+        branch 'mock-gitlab-username/create-test2-txt' set up to track 'origin/mock-gitlab-username/create-test2-txt'.
+        Pushed branch mock-gitlab-username/create-test2-txt (96d1c37 create test2.txt)
+        Submitted 2 commits: mock-gitlab-username/create-test1-txt, mock-gitlab-username/create-test2-txt
+        "###);
+    }
+    {
+        let state = dump_state(&local_repo, &remote_repo)?;
+        insta::assert_snapshot!(state, @r###"
+        Local state:
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d (mock-gitlab-username/create-test1-txt) !1 create test1.txt
+        |
+        @ 96d1c37 (mock-gitlab-username/create-test2-txt) !2 create test2.txt
+
+
+        Remote state:
+        @ f777ecc (> master) create initial.txt
+        |
+        o 62fc20d (mock-gitlab-username/create-test1-txt) create test1.txt
+        |
+        o 96d1c37 (mock-gitlab-username/create-test2-txt) create test2.txt
+
+
+        Merge request info:
+        {
+          "merge_request_index": 2,
+          "merge_requests": {
+            "mock-gitlab-username/create-test1-txt": {
+              "iid": 1,
+              "web_url": "https://gitlab.example.com/mock-gitlab-username/mock-gitlab-repo/-/merge_requests/1",
+              "source_branch": "mock-gitlab-username/create-test1-txt",
+              "target_branch": "master",
+              "sha": "62fc20d2a290daea0d52bdc2ed2ad4be6491010e",
+              "state": "opened",
+              "draft": false,
+              "title": "create test1.txt",
+              "description": "create test1.txt\n"
+            },
+            "mock-gitlab-username/create-test2-txt": {
+              "iid": 2,
+              "web_url": "https://gitlab.example.com/mock-gitlab-username/mock-gitlab-repo/-/merge_requests/2",
+              "source_branch": "mock-gitlab-username/create-test2-txt",
+              "target_branch": "mock-gitlab-username/create-test1-txt",
+              "sha": "96d1c37a3d4363611c49f7e52186e189a04c531f",
+              "state": "opened",
+              "draft": false,
+              "title": "create test2.txt",
+              "description": "create test2.txt\n"
+            }
+          }
+        }
+        "###);
+    }
+
+    // Submitting again should be a no-op.
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "gitlab"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"Skipped 2 commits (already up-to-date): mock-gitlab-username/create-test1-txt, mock-gitlab-username/create-test2-txt");
+    }
+
+    // Rewording the first commit should update both merge requests' commits,
+    // but only the first merge request's title and description.
+    local_repo.branchless("reword", &["HEAD^", "-m", "updated test1.txt"])?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "gitlab"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        let stdout = remove_rebase_lines(stdout);
+        insta::assert_snapshot!(stdout, @r###"
+        Updating merge request (commit, title, description) for commit a4d40b2 updated test1.txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-gitlab-username/create-test1-txt
+        Pushed branch mock-gitlab-username/create-test1-txt (a4d40b2 updated test1.txt)
+        Updating merge request (commit) for commit 0250cd5 create test2.txt
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin mock-gitlab-username/create-test2-txt
+        Pushed branch mock-gitlab-username/create-test2-txt (0250cd5 create test2.txt)
+        Updated 2 commits: mock-gitlab-username/create-test1-txt, mock-gitlab-username/create-test2-txt
+        "###);
+    }
+    {
+        let state = dump_state(&local_repo, &remote_repo)?;
+        insta::assert_snapshot!(state, @r###"
+        Local state:
+        O f777ecc (master) create initial.txt
+        |
+        o a4d40b2 (mock-gitlab-username/create-test1-txt) !1 updated test1.txt
+        |
+        @ 0250cd5 (> mock-gitlab-username/create-test2-txt) !2 create test2.txt
+
+
+        Remote state:
+        @ f777ecc (> master) create initial.txt
+        |
+        o a4d40b2 (mock-gitlab-username/create-test1-txt) updated test1.txt
+        |
+        o 0250cd5 (mock-gitlab-username/create-test2-txt) create test2.txt
+
+
+        Merge request info:
+        {
+          "merge_request_index": 2,
+          "merge_requests": {
+            "mock-gitlab-username/create-test1-txt": {
+              "iid": 1,
+              "web_url": "https://gitlab.example.com/mock-gitlab-username/mock-gitlab-repo/-/merge_requests/1",
+              "source_branch": "mock-gitlab-username/create-test1-txt",
+              "target_branch": "master",
+              "sha": "a4d40b2ed43f7eaf473ec656b5c3cceaac0bf500",
+              "state": "opened",
+              "draft": false,
+              "title": "updated test1.txt",
+              "description": "updated test1.txt\n"
+            },
+            "mock-gitlab-username/create-test2-txt": {
+              "iid": 2,
+              "web_url": "https://gitlab.example.com/mock-gitlab-username/mock-gitlab-repo/-/merge_requests/2",
+              "source_branch": "mock-gitlab-username/create-test2-txt",
+              "target_branch": "mock-gitlab-username/create-test1-txt",
+              "sha": "0250cd569f15b2c17d5c1b5776492ad3abf314cc",
+              "state": "opened",
+              "draft": false,
+              "title": "create test2.txt",
+              "description": "create test2.txt\n"
+            }
+          }
+        }
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gitlab_forge_draft() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _temp_dir,
+        original_repo: remote_repo,
+        cloned_repo: local_repo,
+    } = make_git_with_remote_repo()?;
+    if remote_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    remote_repo.init_repo()?;
+    remote_repo.clone_repo_into(&local_repo, &[])?;
+
+    local_repo.detach_head()?;
+    local_repo.commit_file("test1", 1)?;
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--create", "--draft", "--forge", "gitlab"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin mock-gitlab-username/create-test1-txt
+        branch 'mock-gitlab-username/create-test1-txt' set up to track 'origin/mock-gitlab-username/create-test1-txt'.
+        Pushed branch mock-gitlab-username/create-test1-txt (62fc20d create test1.txt)
+        Submitted 1 commit: mock-gitlab-username/create-test1-txt
+        "###);
+    }
+
+    // The "Draft:" title prefix should not be considered a pending update.
+    {
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "gitlab"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"Skipped 1 commit (already up-to-date): mock-gitlab-username/create-test1-txt");
+    }
+    {
+        let state = dump_state(&local_repo, &remote_repo)?;
+        insta::assert_snapshot!(state, @r###"
+        Local state:
+        O f777ecc (master) create initial.txt
+        |
+        @ 62fc20d (mock-gitlab-username/create-test1-txt) !1 (draft) create test1.txt
+
+
+        Remote state:
+        @ f777ecc (> master) create initial.txt
+        |
+        o 62fc20d (mock-gitlab-username/create-test1-txt) create test1.txt
+
+
+        Merge request info:
+        {
+          "merge_request_index": 1,
+          "merge_requests": {
+            "mock-gitlab-username/create-test1-txt": {
+              "iid": 1,
+              "web_url": "https://gitlab.example.com/mock-gitlab-username/mock-gitlab-repo/-/merge_requests/1",
+              "source_branch": "mock-gitlab-username/create-test1-txt",
+              "target_branch": "master",
+              "sha": "62fc20d2a290daea0d52bdc2ed2ad4be6491010e",
+              "state": "opened",
+              "draft": true,
+              "title": "Draft: create test1.txt",
+              "description": "create test1.txt\n"
+            }
+          }
+        }
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gitlab_forge_land_blocked() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _temp_dir,
+        original_repo: remote_repo,
+        cloned_repo: local_repo,
+    } = make_git_with_remote_repo()?;
+    if remote_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    remote_repo.init_repo()?;
+    remote_repo.clone_repo_into(&local_repo, &[])?;
+
+    local_repo.detach_head()?;
+    local_repo.commit_file("test1", 1)?;
+    local_repo.branchless_with_options(
+        "submit",
+        &["--create", "--forge", "gitlab"],
+        &GitRunOptions {
+            env: mock_env(&remote_repo),
+            ..Default::default()
+        },
+    )?;
+
+    let client = MockGitlabClient {
+        remote_repo_path: remote_repo.repo_path.clone(),
+    };
+    let try_land = |detailed_merge_status: &str| -> eyre::Result<String> {
+        client.with_state_mut(|state| {
+            state
+                .merge_requests
+                .get_mut("mock-gitlab-username/create-test1-txt")
+                .unwrap()
+                .detailed_merge_status = Some(detailed_merge_status.to_owned());
+            Ok(())
+        })?;
+        let (stdout, _stderr) = local_repo.branchless_with_options(
+            "submit",
+            &["--forge", "gitlab", "--land", "--dry-run"],
+            &GitRunOptions {
+                env: mock_env(&remote_repo),
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        Ok(stdout)
+    };
+
+    insta::assert_snapshot!(try_land("ci_still_running")?, @"Cannot land commit 62fc20d create test1.txt: its CI checks are still running");
+    insta::assert_snapshot!(try_land("ci_must_pass")?, @"Cannot land commit 62fc20d create test1.txt: its CI checks failed");
+    insta::assert_snapshot!(try_land("conflict")?, @"Cannot land commit 62fc20d create test1.txt: its code review can't be merged, such as due to conflicts");
+    insta::assert_snapshot!(try_land("checking")?, @"Cannot land commit 62fc20d create test1.txt: the state of its code review couldn't be determined");
+
+    Ok(())
+}
@@ -108,18 +108,16 @@ fn test_submit() -> eyre::Result<()> {
         let (stdout, stderr) = cloned_repo.run(&["submit", "--create"])?;
         let stderr = redact_remotes(stderr);
         insta::assert_snapshot!(stderr, @r###"
-        branchless: processing 1 update: branch bar
-        branchless: processing 1 update: branch qux
-        To: file://<remote>
-         * [new branch]      bar -> bar
-         * [new branch]      qux -> qux
+        branchless: processing 2 updates: branch bar, branch qux
         branchless: processing 1 update: remote branch origin/bar
         branchless: processing 1 update: remote branch origin/qux
         "###);
         insta::assert_snapshot!(stdout, @r###"
-        branchless: running command: <git-executable> push --set-upstream origin bar qux
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin bar qux
         branch 'bar' set up to track 'origin/bar'.
         branch 'qux' set up to track 'origin/qux'.
+        Pushed branch bar (f57e36f create test4.txt)
+        Pushed branch qux (20230db create test5.txt)
         Submitted 2 commits: bar, qux
         "###);
     }
@@ -143,13 +141,12 @@ fn test_submit() -> eyre::Result<()> {
          * branch            bar        -> FETCH_HEAD
          * branch            qux        -> FETCH_HEAD
         branchless: processing 1 update: branch qux
-        To: file://<remote>
-         + 20230db...bae8307 qux -> qux (forced update)
         branchless: processing 1 update: remote branch origin/qux
         "###);
         insta::assert_snapshot!(stdout, @r###"
         branchless: running command: <git-executable> fetch origin refs/heads/bar refs/heads/qux
-        branchless: running command: <git-executable> push --force-with-lease origin qux
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin qux
+        Pushed branch qux (bae8307 updated message)
         Updated 1 commit: qux
         Skipped 1 commit (already up-to-date): bar
         "###);
@@ -261,13 +258,11 @@ fn test_submit_existing_branch() -> eyre::Result<()> {
         )?;
         let stderr = redact_remotes(stderr);
         let stderr = remove_nondeterministic_lines(stderr);
-        insta::assert_snapshot!(stderr, @r###"
-        To: file://<remote>
-         ! [rejected]        feature -> feature (fetch first)
-        error: failed to push some refs to 'file://<remote>'
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin feature
+        Failed to push branch feature (f57e36f create test4.txt): [rejected] (fetch first)
         "###);
-        insta::assert_snapshot!(stdout, @"branchless: running command: <git-executable> push --set-upstream origin feature
-");
     }
 
     {
@@ -316,13 +311,12 @@ fn test_submit_up_to_date_branch() -> eyre::Result<()> {
         let stderr = redact_remotes(stderr);
         insta::assert_snapshot!(stderr, @r###"
         branchless: processing 1 update: branch feature
-        To: file://<remote>
-         * [new branch]      feature -> feature
         branchless: processing 1 update: remote branch origin/feature
         "###);
         insta::assert_snapshot!(stdout, @r###"
-        branchless: running command: <git-executable> push --set-upstream origin feature
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin feature
         branch 'feature' set up to track 'origin/feature'.
+        Pushed branch feature (70deb1e create test3.txt)
         Submitted 1 commit: feature
         "###);
     }
@@ -343,3 +337,552 @@ fn test_submit_up_to_date_branch() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_submit_status() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _guard,
+        original_repo,
+        cloned_repo,
+    } = make_git_with_remote_repo()?;
+
+    if original_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    {
+        original_repo.init_repo()?;
+        original_repo.commit_file("test1", 1)?;
+        original_repo.clone_repo_into(&cloned_repo, &[])?;
+        cloned_repo.init_repo_with_options(&GitInitOptions {
+            make_initial_commit: false,
+            ..Default::default()
+        })?;
+    }
+
+    cloned_repo.run(&["checkout", "-b", "foo"])?;
+    cloned_repo.commit_file("test2", 2)?;
+    cloned_repo.run(&["submit", "--create", "foo"])?;
+    cloned_repo.commit_file("test3", 3)?;
+    cloned_repo.run(&["checkout", "-b", "bar"])?;
+    cloned_repo.commit_file("test4", 4)?;
+    cloned_repo.run(&["branch", "-f", "foo", "HEAD^"])?;
+
+    {
+        let (stdout, _stderr) = cloned_repo.run(&["submit", "--status"])?;
+        let stdout = remove_nondeterministic_lines(stdout);
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> fetch origin refs/heads/foo
+        Commit   Status        Local name  Remote name  Review  Summary
+        96d1c37  local         -           -            -       create test2.txt
+        70deb1e  needs-update  foo         origin/foo   -       create test3.txt
+        355e173  unsubmitted   bar         -            -       create test4.txt
+        "###);
+    }
+    {
+        let (stdout, _stderr) = cloned_repo.run(&["submit", "--status", "--json"])?;
+        let stdout = remove_nondeterministic_lines(stdout);
+        insta::assert_snapshot!(stdout, @r###"
+        [
+          {
+            "commit_oid": "96d1c37a3d4363611c49f7e52186e189a04c531f",
+            "summary": "create test2.txt",
+            "status": "local",
+            "local_commit_name": null,
+            "remote_name": null,
+            "remote_commit_name": null,
+            "review_url": null
+          },
+          {
+            "commit_oid": "70deb1e28791d8e7dd5a1f0c871a51b91282562f",
+            "summary": "create test3.txt",
+            "status": "needs-update",
+            "local_commit_name": "foo",
+            "remote_name": "origin",
+            "remote_commit_name": "origin/foo",
+            "review_url": null
+          },
+          {
+            "commit_oid": "355e173bf9c5d2efac2e451da0cdad3fb82b869a",
+            "summary": "create test4.txt",
+            "status": "unsubmitted",
+            "local_commit_name": "bar",
+            "remote_name": null,
+            "remote_commit_name": null,
+            "review_url": null
+          }
+        ]
+        "###);
+    }
+
+    // Nothing should have been pushed.
+    {
+        let (stdout, _stderr) = original_repo.run(&["branch", "--list"])?;
+        insta::assert_snapshot!(stdout, @r###"
+          foo
+        * master
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_submit_land() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _guard,
+        original_repo,
+        cloned_repo,
+    } = make_git_with_remote_repo()?;
+
+    if original_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    {
+        original_repo.init_repo()?;
+        original_repo.commit_file("test1", 1)?;
+        original_repo.clone_repo_into(&cloned_repo, &[])?;
+        cloned_repo.init_repo_with_options(&GitInitOptions {
+            make_initial_commit: false,
+            ..Default::default()
+        })?;
+    }
+
+    cloned_repo.detach_head()?;
+    cloned_repo.commit_file("test2", 2)?;
+    cloned_repo.commit_file("test3", 3)?;
+
+    // The main branch moves after the commits were made, so they can't be
+    // landed until they've been moved onto it.
+    original_repo.commit_file("test4", 4)?;
+    original_repo.detach_head()?;
+    cloned_repo.run(&["fetch"])?;
+    cloned_repo.run(&["branch", "-f", "master", "origin/master"])?;
+    {
+        let (stdout, _stderr) = cloned_repo.run_with_options(
+            &["submit", "--land", "HEAD^"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"Cannot land onto master: the commits to land aren't on top of it. Move them onto it with `git sync`, submit them again, and then retry.");
+    }
+
+    cloned_repo.branchless("sync", &[])?;
+    {
+        let (stdout, _stderr) = cloned_repo.run(&["submit", "--land", "HEAD^"])?;
+        let stdout = redact_remotes(stdout);
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push origin 44352d000bc6b106acc6124f8b23294b572d84a6:refs/heads/master
+        Landed 1 commit onto master.
+        "###);
+    }
+    {
+        let stdout = cloned_repo.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        :
+        O 44352d0 (master) create test2.txt
+        |
+        @ cf5eb24 create test3.txt
+        "###);
+    }
+    {
+        let (stdout, _stderr) = original_repo.run(&["log", "--oneline", "master"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        44352d0 create test2.txt
+        bf0d52a create test4.txt
+        62fc20d create test1.txt
+        f777ecc create initial.txt
+        "###);
+    }
+
+    // Nothing left to land.
+    {
+        let (stdout, _stderr) = cloned_repo.run(&["submit", "--land", "master"])?;
+        insta::assert_snapshot!(stdout, @"There are no commits to land.");
+    }
+
+    // The main branch can be landed onto while it's checked out.
+    cloned_repo.run(&["checkout", "master"])?;
+    {
+        let (stdout, _stderr) = cloned_repo.run(&["submit", "--land", "cf5eb24"])?;
+        let stdout = redact_remotes(stdout);
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push origin cf5eb244a42cb9c3c7854f82d7b0f3e92e0abfaa:refs/heads/master
+        branchless: running command: <git-executable> rebase cf5eb244a42cb9c3c7854f82d7b0f3e92e0abfaa
+        Landed 1 commit onto master.
+        "###);
+    }
+    {
+        let stdout = cloned_repo.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        :
+        @ cf5eb24 (> master) create test3.txt
+        "###);
+    }
+
+    // The main branch must have an upstream branch to push the landed commits
+    // to.
+    cloned_repo.detach_head()?;
+    cloned_repo.commit_file("test5", 5)?;
+    cloned_repo.run(&["branch", "--unset-upstream", "master"])?;
+    {
+        let (stdout, _stderr) = cloned_repo.run_with_options(
+            &["submit", "--land", "HEAD"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"Cannot land onto master: it doesn't have an upstream branch to push to. Set one with: git branch --set-upstream-to <remote>/<branch> master");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_submit_record_stacks() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _guard,
+        original_repo,
+        cloned_repo,
+    } = make_git_with_remote_repo()?;
+
+    if original_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    {
+        original_repo.init_repo()?;
+        original_repo.commit_file("test1", 1)?;
+        original_repo.commit_file("test2", 2)?;
+
+        original_repo.clone_repo_into(&cloned_repo, &[])?;
+    }
+
+    cloned_repo.init_repo_with_options(&GitInitOptions {
+        make_initial_commit: false,
+        ..Default::default()
+    })?;
+    cloned_repo.run(&["config", "branchless.submit.branch.recordStacks", "true"])?;
+    cloned_repo.run(&["checkout", "-b", "foo"])?;
+    cloned_repo.commit_file("test3", 3)?;
+    cloned_repo.run(&["checkout", "-b", "bar"])?;
+    cloned_repo.commit_file("test4", 4)?;
+
+    {
+        let (stdout, stderr) = cloned_repo.run(&["submit", "--create", "foo+bar"])?;
+        let stderr = redact_remotes(stderr);
+        insta::assert_snapshot!(stderr, @r###"
+        branchless: processing 2 updates: branch bar, branch foo
+        branchless: processing 1 update: remote branch origin/bar
+        branchless: processing 1 update: remote branch origin/foo
+        To: file://<remote>
+         * [new reference]   refs/branchless/stacks/bar -> refs/branchless/stacks/bar
+         * [new reference]   refs/branchless/stacks/foo -> refs/branchless/stacks/foo
+        "###);
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin bar foo
+        branch 'bar' set up to track 'origin/bar'.
+        branch 'foo' set up to track 'origin/foo'.
+        Pushed branch bar (355e173 create test4.txt)
+        Pushed branch foo (70deb1e create test3.txt)
+        branchless: running command: <git-executable> push --force origin refs/branchless/stacks/bar refs/branchless/stacks/foo
+        Submitted 2 commits: bar, foo
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = original_repo.run(&[
+            "for-each-ref",
+            "--format=%(refname) %(objecttype)",
+            "refs/branchless/stacks/",
+        ])?;
+        insta::assert_snapshot!(stdout, @r###"
+        refs/branchless/stacks/bar blob
+        refs/branchless/stacks/foo blob
+        "###);
+        let (stdout, _stderr) =
+            original_repo.run(&["cat-file", "-p", "refs/branchless/stacks/foo"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        parent master
+        head 70deb1e28791d8e7dd5a1f0c871a51b91282562f
+        "###);
+        let (stdout, _stderr) =
+            original_repo.run(&["cat-file", "-p", "refs/branchless/stacks/bar"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        parent foo
+        head 355e173bf9c5d2efac2e451da0cdad3fb82b869a
+        "###);
+    }
+
+    // Restack `bar` directly onto the main branch and make sure that its
+    // recorded parent is updated.
+    cloned_repo.branchless("move", &["-x", "bar", "-d", "master"])?;
+    {
+        let (stdout, stderr) = cloned_repo.run(&["submit", "bar"])?;
+        let stderr = redact_remotes(stderr);
+        insta::assert_snapshot!(stderr, @r###"
+        From: file://<remote>
+         * branch            bar        -> FETCH_HEAD
+        branchless: processing 1 update: branch bar
+        branchless: processing 1 update: remote branch origin/bar
+        To: file://<remote>
+         + c1a2589...654c97f refs/branchless/stacks/bar -> refs/branchless/stacks/bar (forced update)
+        "###);
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> fetch origin refs/heads/bar
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin bar
+        Pushed branch bar (f57e36f create test4.txt)
+        branchless: running command: <git-executable> push --force origin refs/branchless/stacks/bar
+        Updated 1 commit: bar
+        "###);
+        let (stdout, _stderr) =
+            original_repo.run(&["cat-file", "-p", "refs/branchless/stacks/bar"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        parent master
+        head f57e36f51563788bd8761c41226ff147e9cfa300
+        "###);
+    }
+
+    // Once `foo` is deleted locally and on the remote, its record is stale and
+    // is deleted the next time that stacks are recorded.
+    cloned_repo.run(&["push", "origin", "--delete", "foo"])?;
+    cloned_repo.run(&["branch", "-D", "foo"])?;
+    cloned_repo.run(&["commit", "--amend", "-m", "amended test4.txt"])?;
+    {
+        let (stdout, _stderr) = cloned_repo.run(&["submit", "bar"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> fetch origin refs/heads/bar
+        branchless: running command: <git-executable> push --porcelain --atomic --force-with-lease origin bar
+        Pushed branch bar (d151a93 amended test4.txt)
+        branchless: running command: <git-executable> push --force origin refs/branchless/stacks/bar :refs/branchless/stacks/foo
+        Updated 1 commit: bar
+        "###);
+    }
+    {
+        let (stdout, _stderr) = cloned_repo.run(&[
+            "for-each-ref",
+            "--format=%(refname)",
+            "refs/branchless/stacks/",
+        ])?;
+        insta::assert_snapshot!(stdout, @"refs/branchless/stacks/bar");
+        let (stdout, _stderr) = original_repo.run(&[
+            "for-each-ref",
+            "--format=%(refname)",
+            "refs/branchless/stacks/",
+        ])?;
+        insta::assert_snapshot!(stdout, @"refs/branchless/stacks/bar");
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_submit_push_batches_and_retries() -> eyre::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _guard,
+        original_repo,
+        cloned_repo,
+    } = make_git_with_remote_repo()?;
+
+    if original_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    {
+        original_repo.init_repo()?;
+        original_repo.commit_file("test1", 1)?;
+        original_repo.commit_file("test2", 2)?;
+
+        original_repo.clone_repo_into(&cloned_repo, &[])?;
+    }
+
+    // Make the remote fall back to non-atomic pushes, and drop the connection
+    // during the first push which includes `bar`.
+    original_repo.run(&["config", "receive.advertiseAtomic", "false"])?;
+    let hook_path = original_repo
+        .repo_path
+        .join(".git")
+        .join("hooks")
+        .join("pre-receive");
+    std::fs::write(
+        &hook_path,
+        r#"#!/bin/sh
+if grep -q refs/heads/bar && [ ! -f "$GIT_DIR/bar-failed" ]; then
+    touch "$GIT_DIR/bar-failed"
+    kill -9 $PPID
+fi
+"#,
+    )?;
+    std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))?;
+
+    cloned_repo.init_repo_with_options(&GitInitOptions {
+        make_initial_commit: false,
+        ..Default::default()
+    })?;
+    cloned_repo.run(&["checkout", "-b", "bar"])?;
+    cloned_repo.commit_file("test3", 3)?;
+    cloned_repo.run(&["checkout", "-b", "baz"])?;
+    cloned_repo.commit_file("test4", 4)?;
+    cloned_repo.run(&["checkout", "-b", "foo"])?;
+    cloned_repo.commit_file("test5", 5)?;
+
+    {
+        let (stdout, stderr) = cloned_repo.run(&["submit", "--create", "-j", "2"])?;
+        // The messages about the dropped connection vary between shells and
+        // Git versions.
+        let stderr: String = stderr
+            .lines()
+            .filter(|line| line.starts_with("branchless:"))
+            .flat_map(|line| [line, "\n"])
+            .collect();
+        insta::assert_snapshot!(stderr, @r###"
+        branchless: processing 1 update: branch foo
+        branchless: processing 1 update: remote branch origin/foo
+        branchless: processing 1 update: branch bar
+        branchless: processing 1 update: remote branch origin/bar
+        branchless: processing 1 update: branch baz
+        branchless: processing 1 update: remote branch origin/baz
+        "###);
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push --porcelain --set-upstream origin bar baz
+        branchless: running command: <git-executable> push --porcelain --set-upstream origin foo
+        branch 'foo' set up to track 'origin/foo'.
+        Retrying push of 2 branches to origin (attempt 2 of 3)...
+        branchless: running command: <git-executable> push --porcelain --set-upstream origin bar
+        branch 'bar' set up to track 'origin/bar'.
+        branchless: running command: <git-executable> push --porcelain --set-upstream origin baz
+        branch 'baz' set up to track 'origin/baz'.
+        Pushed branch bar (70deb1e create test3.txt)
+        Pushed branch baz (355e173 create test4.txt)
+        Pushed branch foo (f81d55c create test5.txt)
+        Submitted 3 commits: bar, baz, foo
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = original_repo.run(&["branch"])?;
+        insta::assert_snapshot!(stdout, @r###"
+          bar
+          baz
+          foo
+        * master
+        "###);
+        let (stdout, _stderr) = cloned_repo.run(&["config", "branch.bar.remote"])?;
+        insta::assert_snapshot!(stdout, @"origin");
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_submit_rejected_push() -> eyre::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _guard,
+        original_repo,
+        cloned_repo,
+    } = make_git_with_remote_repo()?;
+
+    if original_repo.get_version()? < MIN_VERSION {
+        return Ok(());
+    }
+
+    {
+        original_repo.init_repo()?;
+        original_repo.commit_file("test1", 1)?;
+        original_repo.commit_file("test2", 2)?;
+
+        original_repo.clone_repo_into(&cloned_repo, &[])?;
+    }
+
+    // Reject every push of `bar`, which also fails the other branches in the
+    // same atomic push.
+    let hook_path = original_repo
+        .repo_path
+        .join(".git")
+        .join("hooks")
+        .join("update");
+    std::fs::write(
+        &hook_path,
+        r#"#!/bin/sh
+if [ "$1" = refs/heads/bar ]; then
+    echo "bar is protected"
+    exit 1
+fi
+"#,
+    )?;
+    std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))?;
+
+    cloned_repo.init_repo_with_options(&GitInitOptions {
+        make_initial_commit: false,
+        ..Default::default()
+    })?;
+    cloned_repo.run(&["checkout", "-b", "bar"])?;
+    cloned_repo.commit_file("test3", 3)?;
+    cloned_repo.run(&["checkout", "-b", "baz"])?;
+    cloned_repo.commit_file("test4", 4)?;
+    cloned_repo.run(&["checkout", "-b", "foo"])?;
+    cloned_repo.commit_file("test5", 5)?;
+
+    {
+        let (stdout, stderr) = cloned_repo.run_with_options(
+            &["submit", "--create"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        // Git pads the messages from the remote with trailing whitespace.
+        let stderr: String = stderr
+            .lines()
+            .flat_map(|line| [line.trim_end(), "\n"])
+            .collect();
+        insta::assert_snapshot!(stderr, @r###"
+        remote: bar is protected
+        remote: error: hook declined to update refs/heads/bar
+        branchless: processing 2 updates: branch baz, branch foo
+        branchless: processing 1 update: remote branch origin/baz
+        branchless: processing 1 update: remote branch origin/foo
+        "###);
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin bar baz foo
+        Retrying push of 2 branches to origin (attempt 2 of 3)...
+        branchless: running command: <git-executable> push --porcelain --atomic --set-upstream origin baz foo
+        branch 'baz' set up to track 'origin/baz'.
+        branch 'foo' set up to track 'origin/foo'.
+        Failed to push branch bar (70deb1e create test3.txt): [remote rejected] (hook declined)
+        Pushed branch baz (355e173 create test4.txt)
+        Pushed branch foo (f81d55c create test5.txt)
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = original_repo.run(&["branch"])?;
+        insta::assert_snapshot!(stdout, @r###"
+          baz
+          foo
+        * master
+        "###);
+        let (stdout, _stderr) = cloned_repo.run(&["config", "branch.baz.remote"])?;
+        insta::assert_snapshot!(stdout, @"origin");
+        let (stdout, _stderr) = cloned_repo.run_with_options(
+            &["config", "branch.bar.remote"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"");
+    }
+
+    Ok(())
+}
@@ -11,6 +11,7 @@
 )]
 #![allow(clippy::too_many_arguments, clippy::blocks_in_if_conditions)]
 
+mod report;
 mod worker;
 
 use std::collections::{HashMap, HashSet};
@@ -18,8 +19,8 @@ use std::fmt::Write as _;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::sync::Arc;
-use std::time::SystemTime;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
 use bstr::ByteSlice;
 use clap::ValueEnum;
@@ -51,9 +52,10 @@ use lib::core::rewrite::{
 };
 use lib::git::{
     get_latest_test_command_path, get_test_locks_dir, get_test_tree_dir, get_test_worktrees_dir,
-    make_test_command_slug, Commit, ConfigRead, GitRunInfo, GitRunResult, MaybeZeroOid, NonZeroOid,
-    Repo, SerializedNonZeroOid, SerializedTestResult, TestCommand, WorkingCopyChangesType,
-    TEST_ABORT_EXIT_CODE, TEST_INDETERMINATE_EXIT_CODE, TEST_SUCCESS_EXIT_CODE,
+    make_test_command_slug, Commit, ConfigRead, DirectoryTestCache, GitRunInfo, GitRunResult,
+    MaybeZeroOid, NonZeroOid, Repo, SerializedNonZeroOid, SerializedTestResult, TestCacheBackend,
+    TestCommand, WorkingCopyChangesType, TEST_ABORT_EXIT_CODE, TEST_INDETERMINATE_EXIT_CODE,
+    TEST_SUCCESS_EXIT_CODE,
 };
 use lib::try_exit_code;
 use lib::util::{get_sh, ExitCode, EyreExitOr};
@@ -65,11 +67,12 @@ use thiserror::Error;
 use tracing::{debug, info, instrument, warn};
 
 use git_branchless_opts::{
-    MoveOptions, ResolveRevsetOptions, Revset, TestArgs, TestExecutionStrategy, TestSearchStrategy,
-    TestSubcommand,
+    MoveOptions, ResolveRevsetOptions, Revset, TestArgs, TestExecutionStrategy, TestReport,
+    TestSearchStrategy, TestSubcommand,
 };
 use git_branchless_revset::resolve_commits;
 
+use crate::report::write_reports;
 use crate::worker::{worker, JobResult, WorkQueue, WorkerId};
 
 lazy_static! {
@@ -137,6 +140,9 @@ pub struct RawTestOptions {
     /// The number of jobs to run in parallel.
     pub jobs: Option<usize>,
 
+    /// The number of times to run the test command on each commit.
+    pub repeat: usize,
+
     /// The requested verbosity of the test output.
     pub verbosity: Verbosity,
 
@@ -212,8 +218,13 @@ pub struct ResolvedTestOptions {
     pub use_cache: bool,
     pub is_interactive: bool,
     pub num_jobs: usize,
+    pub num_runs: usize,
     pub verbosity: Verbosity,
     pub fix_options: Option<(ExecuteRebasePlanOptions, RebasePlanPermissions)>,
+
+    /// A cache of test results which is shared with other clones of the
+    /// repository, in addition to the repository's own cache.
+    pub shared_cache: Option<Box<dyn TestCacheBackend>>,
 }
 
 impl ResolvedTestOptions {
@@ -239,6 +250,7 @@ impl ResolvedTestOptions {
             no_cache,
             interactive,
             jobs,
+            repeat,
             verbosity,
             apply_fixes,
         } = options;
@@ -365,6 +377,24 @@ BUG: Expected resolved_interactive ({resolved_interactive:?}) to match interacti
             return Ok(Err(ExitCode(1)));
         }
 
+        match (*repeat, resolved_interactive) {
+            (0, _) => {
+                writeln!(
+                    effects.get_output_stream(),
+                    "The --repeat option must be at least 1."
+                )?;
+                return Ok(Err(ExitCode(1)));
+            }
+            (2.., true) => {
+                writeln!(
+                    effects.get_output_stream(),
+                    "The --repeat option cannot be used with the --interactive option."
+                )?;
+                return Ok(Err(ExitCode(1)));
+            }
+            (_, _) => {}
+        }
+
         let resolved_num_jobs = if resolved_num_jobs == 0 {
             num_cpus::get_physical()
         } else {
@@ -436,6 +466,16 @@ BUG: Expected resolved_interactive ({resolved_interactive:?}) to match interacti
             *search
         };
 
+        let shared_cache: Option<Box<dyn TestCacheBackend>> = if *no_cache {
+            None
+        } else {
+            let shared_cache_dir: Option<PathBuf> = config.get("branchless.test.sharedCacheDir")?;
+            match shared_cache_dir {
+                Some(shared_cache_dir) => Some(Box::new(DirectoryTestCache::new(shared_cache_dir))),
+                None => None,
+            }
+        };
+
         let resolved_test_options = ResolvedTestOptions {
             command: TestCommand::String(resolved_command),
             execution_strategy: resolved_execution_strategy,
@@ -444,8 +484,10 @@ BUG: Expected resolved_interactive ({resolved_interactive:?}) to match interacti
             is_dry_run: *dry_run,
             is_interactive: resolved_interactive,
             num_jobs: resolved_num_jobs,
+            num_runs: *repeat,
             verbosity: *verbosity,
             fix_options,
+            shared_cache,
         };
         debug!(?resolved_test_options, "Resolved test options");
         Ok(Ok(resolved_test_options))
@@ -468,8 +510,15 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
         TestSubcommand::Clean {
             revset,
             resolve_revset_options,
+            worktrees: false,
         } => subcommand_clean(&effects, revset, &resolve_revset_options),
 
+        TestSubcommand::Clean {
+            revset: _,
+            resolve_revset_options: _,
+            worktrees: true,
+        } => subcommand_clean_worktrees(&effects, &git_run_info),
+
         TestSubcommand::Run {
             exec: command,
             command: command_alias,
@@ -482,6 +531,8 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
             no_cache,
             interactive,
             jobs,
+            repeat,
+            reports,
         } => subcommand_run(
             &effects,
             &git_run_info,
@@ -495,12 +546,14 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
                 no_cache,
                 interactive,
                 jobs,
+                repeat,
                 verbosity: Verbosity::from(verbosity),
                 apply_fixes: false,
             },
             revset,
             &resolve_revset_options,
             None,
+            &reports,
         ),
 
         TestSubcommand::Show {
@@ -509,6 +562,7 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
             revset,
             resolve_revset_options,
             verbosity,
+            diff,
         } => subcommand_show(
             &effects,
             &RawTestOptions {
@@ -521,11 +575,13 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
                 no_cache: false,
                 interactive: false,
                 jobs: None,
+                repeat: 1,
                 verbosity: Verbosity::from(verbosity),
                 apply_fixes: false,
             },
             revset,
             &resolve_revset_options,
+            diff,
         ),
 
         TestSubcommand::Fix {
@@ -552,17 +608,28 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
                 no_cache,
                 interactive: false,
                 jobs,
+                repeat: 1,
                 verbosity: Verbosity::from(verbosity),
                 apply_fixes: true,
             },
             revset,
             &resolve_revset_options,
             Some(&move_options),
+            &[],
         ),
+
+        TestSubcommand::Export {
+            path,
+            revset,
+            resolve_revset_options,
+        } => subcommand_export(&effects, &path, revset, &resolve_revset_options),
+
+        TestSubcommand::Import { path } => subcommand_import(&effects, &path),
     }
 }
 
-/// Run the command provided in `options` on each of the commits in `revset`.
+/// Run the command provided in `options` on each of the commits in `revset`,
+/// and write the results to each of the provided `reports`.
 #[instrument]
 fn subcommand_run(
     effects: &Effects,
@@ -571,6 +638,7 @@ fn subcommand_run(
     revset: Revset,
     resolve_revset_options: &ResolveRevsetOptions,
     move_options: Option<&MoveOptions>,
+    reports: &[TestReport],
 ) -> EyreExitOr<()> {
     let now = SystemTime::now();
     let repo = Repo::from_current_dir()?;
@@ -625,6 +693,7 @@ fn subcommand_run(
         &commits,
         &options,
     )?);
+    write_reports(&repo, &options.command, &test_results, reports)?;
 
     try_exit_code!(print_summary(
         effects,
@@ -784,6 +853,10 @@ pub struct TestOutput {
 
     /// The resulting status of the test.
     pub test_status: TestStatus,
+
+    /// How long the test command took to run. This is zero if the command
+    /// wasn't run, such as if the result was cached.
+    pub duration: Duration,
 }
 
 /// The possible results of attempting to run a test.
@@ -792,6 +865,10 @@ pub enum TestStatus {
     /// Attempting to set up the working directory for the repository failed.
     CheckoutFailed,
 
+    /// The command configured to set up a new testing worktree failed, with
+    /// the provided output.
+    WorktreeSetupFailed(String),
+
     /// Invoking the test command failed.
     SpawnTestFailed(String),
 
@@ -808,12 +885,20 @@ pub enum TestStatus {
 
     /// The test command indicated that the commit should be skipped for testing.
     Indeterminate {
+        /// Whether or not the result was cached (indicating that we didn't
+        /// actually re-run the test).
+        cached: bool,
+
         /// The exit code of the command.
         exit_code: i32,
     },
 
     /// The test command indicated that the process should be aborted entirely.
     Abort {
+        /// Whether or not the result was cached (indicating that we didn't
+        /// actually re-run the test).
+        cached: bool,
+
         /// The exit code of the command.
         exit_code: i32,
     },
@@ -830,6 +915,10 @@ pub enum TestStatus {
         /// Whether the test was run interactively (the user executed the
         /// command via `--interactive`).
         interactive: bool,
+
+        /// Whether the test command was run multiple times and the runs
+        /// didn't all have the same result.
+        flaky: bool,
     },
 
     /// The test passed and returned a successful exit code.
@@ -866,6 +955,7 @@ impl TestStatus {
     fn get_icon(&self) -> &'static str {
         match self {
             TestStatus::CheckoutFailed
+            | TestStatus::WorktreeSetupFailed(_)
             | TestStatus::SpawnTestFailed(_)
             | TestStatus::AlreadyInProgress
             | TestStatus::ReadCacheFailed(_)
@@ -880,6 +970,7 @@ impl TestStatus {
     fn get_style(&self) -> Style {
         match self {
             TestStatus::CheckoutFailed
+            | TestStatus::WorktreeSetupFailed(_)
             | TestStatus::SpawnTestFailed(_)
             | TestStatus::AlreadyInProgress
             | TestStatus::ReadCacheFailed(_)
@@ -904,6 +995,14 @@ impl TestStatus {
                 .append(commit.friendly_describe(glyphs)?)
                 .build(),
 
+            TestStatus::WorktreeSetupFailed(output) => StyledStringBuilder::new()
+                .append_styled(
+                    format!("Failed to set up worktree: {output}: "),
+                    self.get_style(),
+                )
+                .append(commit.friendly_describe(glyphs)?)
+                .build(),
+
             TestStatus::SpawnTestFailed(err) => StyledStringBuilder::new()
                 .append_styled(
                     format!("Failed to spawn command: {err}: "),
@@ -927,7 +1026,10 @@ impl TestStatus {
                 .append(commit.friendly_describe(glyphs)?)
                 .build(),
 
-            TestStatus::Indeterminate { exit_code } => StyledStringBuilder::new()
+            TestStatus::Indeterminate {
+                cached: _,
+                exit_code,
+            } => StyledStringBuilder::new()
                 .append_styled(
                     format!("Exit code indicated to skip this commit (exit code {exit_code}): "),
                     self.get_style(),
@@ -935,7 +1037,10 @@ impl TestStatus {
                 .append(commit.friendly_describe(glyphs)?)
                 .build(),
 
-            TestStatus::Abort { exit_code } => StyledStringBuilder::new()
+            TestStatus::Abort {
+                cached: _,
+                exit_code,
+            } => StyledStringBuilder::new()
                 .append_styled(
                     format!("Exit code indicated to abort command (exit code {exit_code}): "),
                     self.get_style(),
@@ -947,11 +1052,15 @@ impl TestStatus {
                 cached,
                 interactive,
                 exit_code,
+                flaky,
             } => {
                 let mut descriptors = Vec::new();
                 if *cached {
                     descriptors.push("cached".to_string());
                 }
+                if *flaky {
+                    descriptors.push("flaky".to_string());
+                }
                 descriptors.push(format!("exit code {exit_code}"));
                 if *interactive {
                     descriptors.push("interactive".to_string());
@@ -1079,6 +1188,7 @@ impl TestOutput {
 
         let interactive = match self.test_status {
             TestStatus::CheckoutFailed
+            | TestStatus::WorktreeSetupFailed(_)
             | TestStatus::SpawnTestFailed(_)
             | TestStatus::TerminatedBySignal
             | TestStatus::AlreadyInProgress
@@ -1273,8 +1383,10 @@ fn run_tests_inner<'a>(
         is_dry_run: _,     // Used only in `apply_fixes`.
         is_interactive: _, // Used in `test_commit`.
         num_jobs,
-        verbosity: _,   // Verbosity used by caller to print results.
+        num_runs: _,     // Used in `test_commit`.
+        verbosity: _,    // Verbosity used by caller to print results.
         fix_options: _, // Whether to apply fixes is checked by `test_commit`, after the working directory is set up.
+        shared_cache: _, // Used in `make_test_files` and `test_commit`.
     } = &options;
 
     let shell_path = match get_sh() {
@@ -1387,6 +1499,7 @@ fn run_tests_inner<'a>(
 
         let work_queue = WorkQueue::new();
         let repo_dir = repo.get_path();
+        let worktree_setup_error = Mutex::new(None);
         crossbeam::thread::scope(|scope| -> eyre::Result<_> {
             let (result_tx, result_rx) = crossbeam::channel::unbounded();
             let workers: HashMap<WorkerId, crossbeam::thread::ScopedJoinHandle<()>> = {
@@ -1395,6 +1508,7 @@ fn run_tests_inner<'a>(
                     let effects = &effects;
                     let progress = &progress;
                     let shell_path = &shell_path;
+                    let worktree_setup_error = &worktree_setup_error;
                     let work_queue = work_queue.clone();
                     let result_tx = result_tx.clone();
                     let setup = move || -> eyre::Result<Repo> {
@@ -1416,6 +1530,7 @@ fn run_tests_inner<'a>(
                             event_tx_id,
                             options,
                             worker_id,
+                            worktree_setup_error,
                             &commit,
                         )
                     };
@@ -1651,13 +1766,17 @@ fn event_loop(
         } = job;
         let (maybe_testing_aborted_error, search_status) = match &test_output.test_status {
             TestStatus::CheckoutFailed
+            | TestStatus::WorktreeSetupFailed(_)
             | TestStatus::SpawnTestFailed(_)
             | TestStatus::TerminatedBySignal
             | TestStatus::AlreadyInProgress
             | TestStatus::ReadCacheFailed(_)
             | TestStatus::Indeterminate { .. } => (None, search::Status::Indeterminate),
 
-            TestStatus::Abort { exit_code } => (
+            TestStatus::Abort {
+                cached: _,
+                exit_code,
+            } => (
                 Some(TestingAbortedError {
                     commit_oid,
                     exit_code: *exit_code,
@@ -1669,6 +1788,7 @@ fn event_loop(
                 cached: _,
                 interactive: _,
                 exit_code: _,
+                flaky: _,
             } => (None, search::Status::Failure),
 
             TestStatus::Passed {
@@ -1739,6 +1859,7 @@ fn print_summary(
         )?;
         match test_output.test_status {
             TestStatus::CheckoutFailed
+            | TestStatus::WorktreeSetupFailed(_)
             | TestStatus::SpawnTestFailed(_)
             | TestStatus::AlreadyInProgress
             | TestStatus::ReadCacheFailed(_)
@@ -1752,6 +1873,7 @@ fn print_summary(
                 cached,
                 exit_code: _,
                 interactive: _,
+                flaky: _,
             } => {
                 num_failed += 1;
                 if cached {
@@ -1948,6 +2070,7 @@ fn apply_fixes(
                 interactive: _,
             }
             | TestStatus::CheckoutFailed
+            | TestStatus::WorktreeSetupFailed(_)
             | TestStatus::SpawnTestFailed(_)
             | TestStatus::TerminatedBySignal
             | TestStatus::AlreadyInProgress
@@ -2191,6 +2314,7 @@ fn run_test(
     event_tx_id: EventTransactionId,
     options: &ResolvedTestOptions,
     worker_id: WorkerId,
+    worktree_setup_error: &Mutex<Option<String>>,
     commit: &Commit,
 ) -> eyre::Result<TestOutput> {
     let ResolvedTestOptions {
@@ -2201,8 +2325,10 @@ fn run_test(
         is_dry_run: _,      // Used only in `apply_fixes`.
         is_interactive: _,  // Used in `test_commit`.
         num_jobs: _,        // Caller handles job management.
+        num_runs: _,        // Used in `test_commit`.
         verbosity: _,
         fix_options,
+        shared_cache: _, // Used in `make_test_files` and `test_commit`.
     } = options;
     let (effects, progress) = effects.start_operation(operation_type);
     progress.notify_status(
@@ -2218,16 +2344,34 @@ fn run_test(
     let test_output = match make_test_files(repo, commit, options)? {
         TestFilesResult::Cached(test_output) => test_output,
         TestFilesResult::NotCached(test_files) => {
-            match prepare_working_directory(
-                git_run_info,
-                repo,
-                event_tx_id,
-                commit,
-                *execution_strategy,
-                worker_id,
-            )? {
-                Err(err) => {
-                    info!(?err, "Failed to prepare working directory for testing");
+            // Once setting up a worktree has failed, don't set up any more
+            // worktrees during this invocation, since the setup command would
+            // most likely fail again for each remaining commit.
+            let previous_worktree_setup_error = worktree_setup_error.lock().unwrap().clone();
+            let prepared_working_directory = match previous_worktree_setup_error {
+                Some(output) => Err(TestStatus::WorktreeSetupFailed(output)),
+                None => match prepare_working_directory(
+                    git_run_info,
+                    repo,
+                    event_tx_id,
+                    commit,
+                    *execution_strategy,
+                    worker_id,
+                )? {
+                    Ok(prepared_working_directory) => Ok(prepared_working_directory),
+                    Err(PrepareWorkingDirectoryError::WorktreeSetupFailed { path, output }) => {
+                        info!(?path, ?output, "Failed to set up worktree for testing");
+                        *worktree_setup_error.lock().unwrap() = Some(output.clone());
+                        Err(TestStatus::WorktreeSetupFailed(output))
+                    }
+                    Err(err) => {
+                        info!(?err, "Failed to prepare working directory for testing");
+                        Err(TestStatus::CheckoutFailed)
+                    }
+                },
+            };
+            match prepared_working_directory {
+                Err(test_status) => {
                     let TestFiles {
                         temp_dir,
                         lock_file: _, // Drop lock.
@@ -2243,7 +2387,8 @@ fn run_test(
                         result_path,
                         stdout_path,
                         stderr_path,
-                        test_status: TestStatus::CheckoutFailed,
+                        test_status,
+                        duration: Duration::ZERO,
                     }
                 }
                 Ok(PreparedWorkingDirectory {
@@ -2291,6 +2436,7 @@ fn run_test(
     progress.notify_status(
         match test_output.test_status {
             TestStatus::CheckoutFailed
+            | TestStatus::WorktreeSetupFailed(_)
             | TestStatus::SpawnTestFailed(_)
             | TestStatus::AlreadyInProgress
             | TestStatus::ReadCacheFailed(_)
@@ -2390,72 +2536,51 @@ fn make_test_files(
             stdout_path,
             stderr_path,
             test_status: TestStatus::AlreadyInProgress,
+            duration: Duration::ZERO,
         }));
     }
 
+    if let Some(shared_cache) = &options.shared_cache {
+        let has_local_result = matches!(
+            std::fs::read_to_string(&result_path),
+            Ok(contents) if !contents.is_empty()
+        );
+        if !has_local_result {
+            // We hold the lock, so it's safe to populate the command directory
+            // with the shared result, which is then read below as if it had
+            // been produced locally.
+            if let Err(err) = shared_cache.fetch(
+                commit.get_tree_oid(),
+                &options.make_command_slug(),
+                &command_dir,
+            ) {
+                warn!(
+                    ?err,
+                    ?shared_cache,
+                    "Could not fetch test result from shared cache"
+                );
+            }
+        }
+    }
+
     if let Ok(contents) = std::fs::read_to_string(&result_path) {
         // If the file exists but was empty, this indicates that a previous
         // attempt did not complete successfully. However, we successfully took
         // the lock, so it should be the case that we are the exclusive writers
         // to the contents of this directory (i.e. the previous attempt is not
         // still running), so it's safe to proceed and overwrite these files.
-        if !contents.is_empty() {
-            let serialized_result: Result<SerializedTestResult, _> =
-                serde_json::from_str(&contents);
-            let test_status = match serialized_result {
-                Ok(SerializedTestResult {
-                    command: _,
-                    exit_code: 0,
-                    head_commit_oid,
-                    snapshot_tree_oid,
-                    interactive,
-                }) => TestStatus::Passed {
-                    cached: true,
-                    fix_info: FixInfo {
-                        head_commit_oid: head_commit_oid.map(|SerializedNonZeroOid(oid)| oid),
-                        snapshot_tree_oid: snapshot_tree_oid.map(|SerializedNonZeroOid(oid)| oid),
-                    },
-
-                    interactive,
-                },
-
-                Ok(SerializedTestResult {
-                    command: _,
-                    exit_code,
-                    head_commit_oid: _,
-                    snapshot_tree_oid: _,
-                    interactive: _,
-                }) if exit_code == TEST_INDETERMINATE_EXIT_CODE => {
-                    TestStatus::Indeterminate { exit_code }
-                }
-
-                Ok(SerializedTestResult {
-                    command: _,
-                    exit_code,
-                    head_commit_oid: _,
-                    snapshot_tree_oid: _,
-                    interactive: _,
-                }) if exit_code == TEST_ABORT_EXIT_CODE => TestStatus::Abort { exit_code },
-
-                Ok(SerializedTestResult {
-                    command: _,
-                    exit_code,
-                    head_commit_oid: _,
-                    snapshot_tree_oid: _,
-                    interactive,
-                }) => TestStatus::Failed {
-                    cached: true,
-                    exit_code,
-                    interactive,
-                },
-                Err(err) => TestStatus::ReadCacheFailed(err.to_string()),
-            };
+        //
+        // Likewise, a result from fewer runs than were requested with
+        // `--repeat` is overwritten by running the command again.
+        if !contents.is_empty() && has_enough_cached_runs(&contents, options.num_runs) {
+            let test_status = make_cached_test_status(&contents);
             return Ok(TestFilesResult::Cached(TestOutput {
                 temp_dir: None,
                 result_path,
                 stdout_path,
                 stderr_path,
                 test_status,
+                duration: Duration::ZERO,
             }));
         }
     }
@@ -2478,6 +2603,118 @@ fn make_test_files(
     }))
 }
 
+/// Determine whether the cached `result` file contents come from at least
+/// `num_runs` runs of the test command. Results which can't be parsed are
+/// treated as sufficient, so that the parse error is reported.
+fn has_enough_cached_runs(contents: &str, num_runs: usize) -> bool {
+    match serde_json::from_str::<SerializedTestResult>(contents) {
+        Ok(serialized_result) => serialized_result.num_runs >= num_runs,
+        Err(_) => true,
+    }
+}
+
+/// Convert the contents of a cached `result` file into the status of the
+/// test.
+fn make_cached_test_status(contents: &str) -> TestStatus {
+    let serialized_result: Result<SerializedTestResult, _> = serde_json::from_str(contents);
+    match serialized_result {
+        Ok(SerializedTestResult {
+            command: _,
+            exit_code: 0,
+            head_commit_oid,
+            snapshot_tree_oid,
+            interactive,
+            duration_secs: _,
+            num_runs: _,
+            flaky: false,
+        }) => TestStatus::Passed {
+            cached: true,
+            fix_info: FixInfo {
+                head_commit_oid: head_commit_oid.map(|SerializedNonZeroOid(oid)| oid),
+                snapshot_tree_oid: snapshot_tree_oid.map(|SerializedNonZeroOid(oid)| oid),
+            },
+
+            interactive,
+        },
+
+        Ok(SerializedTestResult {
+            command: _,
+            exit_code,
+            head_commit_oid: _,
+            snapshot_tree_oid: _,
+            interactive: _,
+            duration_secs: _,
+            num_runs: _,
+            flaky: false,
+        }) if exit_code == TEST_INDETERMINATE_EXIT_CODE => TestStatus::Indeterminate {
+            cached: true,
+            exit_code,
+        },
+
+        Ok(SerializedTestResult {
+            command: _,
+            exit_code,
+            head_commit_oid: _,
+            snapshot_tree_oid: _,
+            interactive: _,
+            duration_secs: _,
+            num_runs: _,
+            flaky: false,
+        }) if exit_code == TEST_ABORT_EXIT_CODE => TestStatus::Abort {
+            cached: true,
+            exit_code,
+        },
+
+        Ok(SerializedTestResult {
+            command: _,
+            exit_code,
+            head_commit_oid: _,
+            snapshot_tree_oid: _,
+            interactive,
+            duration_secs: _,
+            num_runs: _,
+            flaky,
+        }) => TestStatus::Failed {
+            cached: true,
+            exit_code,
+            interactive,
+            flaky,
+        },
+        Err(err) => TestStatus::ReadCacheFailed(err.to_string()),
+    }
+}
+
+/// Read the cached output of the test command in `options` for the given
+/// commit, if any. Unlike `make_test_files`, this doesn't create any files or
+/// take any locks, so it's safe to call while tests are running.
+#[instrument]
+fn read_cached_test_output(
+    repo: &Repo,
+    commit: &Commit,
+    options: &ResolvedTestOptions,
+) -> eyre::Result<Option<TestOutput>> {
+    let command_dir = get_test_tree_dir(repo, commit)?.join(options.make_command_slug());
+    let result_path = command_dir.join("result");
+    let contents = match std::fs::read_to_string(&result_path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err).wrap_err_with(|| format!("Reading test result {result_path:?}"))
+        }
+    };
+    if contents.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(TestOutput {
+        temp_dir: None,
+        result_path,
+        stdout_path: command_dir.join("stdout"),
+        stderr_path: command_dir.join("stderr"),
+        test_status: make_cached_test_status(&contents),
+        duration: Duration::ZERO,
+    }))
+}
+
 #[derive(Debug)]
 struct PreparedWorkingDirectory {
     lock_file: LockFile,
@@ -2491,6 +2728,81 @@ enum PrepareWorkingDirectoryError {
     NoWorkingCopy,
     CheckoutFailed(NonZeroOid),
     CreateWorktreeFailed(PathBuf),
+    WorktreeSetupFailed { path: PathBuf, output: String },
+}
+
+/// The config key for the command to run in a testing worktree after it's
+/// created, such as to fetch dependencies or to share a build cache.
+const WORKTREE_SETUP_CONFIG_KEY: &str = "branchless.test.worktreeSetup";
+
+/// The config key for the command to run in a testing worktree before it's
+/// removed with `git test clean --worktrees`.
+const WORKTREE_TEARDOWN_CONFIG_KEY: &str = "branchless.test.worktreeTeardown";
+
+const WORKTREE_DIR_NAME_PREFIX: &str = "testing-worktree-";
+
+fn get_worktree_lock_file_name(worker_id: &str) -> String {
+    format!("worktree-{worker_id}.lock")
+}
+
+/// Run the command configured at `config_key`, if any, in the given worktree.
+/// If the command fails, returns a description of the failure which includes
+/// the command's output.
+#[instrument]
+fn run_worktree_hook(
+    repo: &Repo,
+    config_key: &str,
+    worktree_dir: &Path,
+) -> eyre::Result<Result<(), String>> {
+    let hook_command: Option<String> = repo.get_readonly_config()?.get(config_key)?;
+    let hook_command = match hook_command {
+        Some(hook_command) => hook_command,
+        None => return Ok(Ok(())),
+    };
+    let shell_path = match get_sh() {
+        Some(shell_path) => shell_path,
+        None => eyre::bail!("Could not determine path to shell to run {config_key}"),
+    };
+
+    let output = Command::new(shell_path)
+        .arg("-c")
+        .arg(&hook_command)
+        .current_dir(worktree_dir)
+        .stdin(Stdio::null())
+        .output()
+        .wrap_err_with(|| format!("Running {config_key} command: {hook_command}"))?;
+    if output.status.success() {
+        Ok(Ok(()))
+    } else {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        warn!(
+            ?config_key,
+            ?hook_command,
+            ?worktree_dir,
+            exit_status = ?output.status,
+            %stdout,
+            %stderr,
+            "Worktree hook failed"
+        );
+        // Keep the description on one line, since it's shown as a status.
+        let hook_output = stdout
+            .lines()
+            .chain(stderr.lines())
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("; ");
+        let description = if hook_output.is_empty() {
+            format!("{config_key} command failed ({})", output.status)
+        } else {
+            format!(
+                "{config_key} command failed ({}): {hook_output}",
+                output.status
+            )
+        };
+        Ok(Err(description))
+    }
 }
 
 #[instrument]
@@ -2508,9 +2820,7 @@ fn prepare_working_directory(
 
     let lock_file_name = match strategy {
         TestExecutionStrategy::WorkingCopy => "working-copy.lock".to_string(),
-        TestExecutionStrategy::Worktree => {
-            format!("worktree-{worker_id}.lock")
-        }
+        TestExecutionStrategy::Worktree => get_worktree_lock_file_name(&worker_id.to_string()),
     };
     let lock_path = test_lock_dir_path.join(lock_file_name);
     let mut lock_file = LockFile::open(&lock_path)
@@ -2555,7 +2865,7 @@ fn prepare_working_directory(
             std::fs::create_dir_all(&parent_dir)
                 .wrap_err_with(|| format!("Creating worktree parent dir at {parent_dir:?}"))?;
 
-            let worktree_dir_name = format!("testing-worktree-{worker_id}");
+            let worktree_dir_name = format!("{WORKTREE_DIR_NAME_PREFIX}{worker_id}");
             let worktree_dir = parent_dir.join(worktree_dir_name);
             let worktree_dir_str = match worktree_dir.to_str() {
                 Some(worktree_dir) => worktree_dir,
@@ -2566,7 +2876,16 @@ fn prepare_working_directory(
                 }
             };
 
-            if !worktree_dir.exists() {
+            // Worktrees are kept between runs so that untracked build
+            // artifacts can be reused. If the directory is left over but Git
+            // no longer knows about the worktree (such as after `git worktree
+            // prune`), then recreate it from scratch.
+            if worktree_dir.exists() && !is_worktree_registered(&worktree_dir) {
+                std::fs::remove_dir_all(&worktree_dir)
+                    .wrap_err_with(|| format!("Removing stale worktree at {worktree_dir:?}"))?;
+            }
+            let is_new_worktree = !worktree_dir.exists();
+            if is_new_worktree {
                 let GitRunResult {
                     exit_code,
                     stdout: _,
@@ -2605,6 +2924,23 @@ fn prepare_working_directory(
                     commit.get_oid(),
                 )));
             }
+
+            // Run the setup command after the first checkout so that it can
+            // depend on the contents of the commit. If it fails, remove the
+            // worktree so that setup is attempted again by the next `git test
+            // run` invocation.
+            if is_new_worktree {
+                if let Err(output) =
+                    run_worktree_hook(repo, WORKTREE_SETUP_CONFIG_KEY, &worktree_dir)?
+                {
+                    remove_worktree(git_run_info, repo, event_tx_id, &worktree_dir)?;
+                    return Ok(Err(PrepareWorkingDirectoryError::WorktreeSetupFailed {
+                        path: worktree_dir,
+                        output,
+                    }));
+                }
+            }
+
             Ok(Ok(PreparedWorkingDirectory {
                 lock_file,
                 path: worktree_dir,
@@ -2613,6 +2949,86 @@ fn prepare_working_directory(
     }
 }
 
+/// Determine whether the worktree at the given path is still known to Git. The
+/// worktree's `.git` file points to its administrative directory in the main
+/// repository, which is deleted by `git worktree prune` and `git worktree
+/// remove`.
+fn is_worktree_registered(worktree_dir: &Path) -> bool {
+    let dot_git_contents = match std::fs::read_to_string(worktree_dir.join(".git")) {
+        Ok(dot_git_contents) => dot_git_contents,
+        Err(_) => return false,
+    };
+    let admin_dir = match dot_git_contents.trim_end().strip_prefix("gitdir: ") {
+        Some(admin_dir) => worktree_dir.join(admin_dir),
+        None => return false,
+    };
+    // The administrative directory records the location of the worktree's
+    // `.git` file, so a worktree whose administrative directory was reused for
+    // another worktree is also considered stale.
+    match std::fs::read_to_string(admin_dir.join("gitdir")) {
+        Ok(registered_dot_git) => {
+            let registered_dot_git = Path::new(registered_dot_git.trim_end());
+            match (
+                registered_dot_git.canonicalize(),
+                worktree_dir.join(".git").canonicalize(),
+            ) {
+                (Ok(registered_dot_git), Ok(dot_git)) => registered_dot_git == dot_git,
+                _ => false,
+            }
+        }
+        Err(_) => false,
+    }
+}
+
+/// Remove a testing worktree, including any untracked files in it.
+#[instrument]
+fn remove_worktree(
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    event_tx_id: EventTransactionId,
+    worktree_dir: &Path,
+) -> eyre::Result<()> {
+    let worktree_dir_str = match worktree_dir.to_str() {
+        Some(worktree_dir_str) => worktree_dir_str,
+        None => eyre::bail!("Worktree path is not valid UTF-8: {worktree_dir:?}"),
+    };
+    let GitRunResult {
+        exit_code,
+        stdout: _,
+        stderr: _,
+    } = git_run_info.run_silent(
+        repo,
+        Some(event_tx_id),
+        &["worktree", "remove", "--force", worktree_dir_str],
+        Default::default(),
+    )?;
+    if !exit_code.is_success() {
+        // Git may not know about the worktree anymore, in which case the
+        // directory can be removed directly.
+        if worktree_dir.exists() {
+            std::fs::remove_dir_all(worktree_dir)
+                .wrap_err_with(|| format!("Removing worktree at {worktree_dir:?}"))?;
+        }
+        let GitRunResult {
+            exit_code,
+            stdout: _,
+            stderr,
+        } = git_run_info.run_silent(
+            repo,
+            Some(event_tx_id),
+            &["worktree", "prune"],
+            Default::default(),
+        )?;
+        if !exit_code.is_success() {
+            warn!(
+                stderr = %String::from_utf8_lossy(&stderr),
+                "Could not prune worktrees"
+            );
+        }
+    }
+    Ok(())
+}
+
 #[instrument]
 fn test_commit(
     effects: &Effects,
@@ -2691,37 +3107,70 @@ To abort testing entirely, run:      {exit127}",
             }
         }
     } else {
-        command
-            .stdin(Stdio::null())
-            .stdout(stdout_file)
-            .stderr(stderr_file);
+        command.stdin(Stdio::null());
     }
 
-    let exit_code = match command.status() {
-        Ok(status) => status.code(),
-        Err(err) => {
-            return Ok(TestOutput {
-                temp_dir,
-                result_path,
-                stdout_path,
-                stderr_path,
-                test_status: TestStatus::SpawnTestFailed(err.to_string()),
-            });
+    let num_runs = options.num_runs.max(1);
+    let mut exit_codes = Vec::with_capacity(num_runs);
+    let start_time = Instant::now();
+    for _ in 0..num_runs {
+        if !options.is_interactive {
+            // The cloned file handles share the same offset, so the output of
+            // each run is appended after that of the previous run.
+            command
+                .stdout(stdout_file.try_clone()?)
+                .stderr(stderr_file.try_clone()?);
         }
-    };
-    let exit_code = match exit_code {
-        Some(exit_code) => exit_code,
-        None => {
-            return Ok(TestOutput {
-                temp_dir,
-                result_path,
-                stdout_path,
-                stderr_path,
-                test_status: TestStatus::TerminatedBySignal,
-            });
+        let exit_code = match command.status() {
+            Ok(status) => status.code(),
+            Err(err) => {
+                return Ok(TestOutput {
+                    temp_dir,
+                    result_path,
+                    stdout_path,
+                    stderr_path,
+                    test_status: TestStatus::SpawnTestFailed(err.to_string()),
+                    duration: start_time.elapsed(),
+                });
+            }
+        };
+        match exit_code {
+            Some(exit_code) => exit_codes.push(exit_code),
+            None => {
+                return Ok(TestOutput {
+                    temp_dir,
+                    result_path,
+                    stdout_path,
+                    stderr_path,
+                    test_status: TestStatus::TerminatedBySignal,
+                    duration: start_time.elapsed(),
+                });
+            }
         }
+    }
+    let duration = start_time.elapsed() / u32::try_from(num_runs)?;
+
+    let first_exit_code = exit_codes[0];
+    let flaky = exit_codes
+        .iter()
+        .any(|exit_code| *exit_code != first_exit_code);
+    let exit_code = if flaky {
+        exit_codes
+            .iter()
+            .copied()
+            .find(|exit_code| *exit_code != TEST_SUCCESS_EXIT_CODE)
+            .unwrap_or(first_exit_code)
+    } else {
+        first_exit_code
     };
     let test_status = match exit_code {
+        exit_code if flaky => TestStatus::Failed {
+            cached: false,
+            exit_code,
+            interactive: options.is_interactive,
+            flaky: true,
+        },
+
         TEST_SUCCESS_EXIT_CODE => {
             let fix_info = {
                 let repo = Repo::from_dir(working_directory)?;
@@ -2764,13 +3213,20 @@ To abort testing entirely, run:      {exit127}",
             }
         }
 
-        exit_code @ TEST_INDETERMINATE_EXIT_CODE => TestStatus::Indeterminate { exit_code },
-        exit_code @ TEST_ABORT_EXIT_CODE => TestStatus::Abort { exit_code },
+        exit_code @ TEST_INDETERMINATE_EXIT_CODE => TestStatus::Indeterminate {
+            cached: false,
+            exit_code,
+        },
+        exit_code @ TEST_ABORT_EXIT_CODE => TestStatus::Abort {
+            cached: false,
+            exit_code,
+        },
 
         exit_code => TestStatus::Failed {
             cached: false,
             exit_code,
             interactive: options.is_interactive,
+            flaky: false,
         },
     };
 
@@ -2781,6 +3237,7 @@ To abort testing entirely, run:      {exit127}",
             interactive: _,
         } => Some(fix_info),
         TestStatus::CheckoutFailed
+        | TestStatus::WorktreeSetupFailed(_)
         | TestStatus::SpawnTestFailed(_)
         | TestStatus::TerminatedBySignal
         | TestStatus::AlreadyInProgress
@@ -2797,16 +3254,37 @@ To abort testing entirely, run:      {exit127}",
         snapshot_tree_oid: fix_info
             .and_then(|fix_info| fix_info.snapshot_tree_oid.map(SerializedNonZeroOid)),
         interactive: options.is_interactive,
+        duration_secs: Some(duration.as_secs_f64()),
+        num_runs,
+        flaky,
     };
     serde_json::to_writer_pretty(result_file, &serialized_test_result)
         .wrap_err_with(|| format!("Writing test status {test_status:?} to {result_path:?}"))?;
 
+    if let (None, Some(shared_cache), Some(command_dir)) =
+        (&temp_dir, &options.shared_cache, result_path.parent())
+    {
+        let tree_oid = commit.get_tree_oid();
+        if serialized_test_result.is_shareable(tree_oid) {
+            if let Err(err) =
+                shared_cache.store(tree_oid, &options.make_command_slug(), command_dir)
+            {
+                warn!(
+                    ?err,
+                    ?shared_cache,
+                    "Could not store test result in shared cache"
+                );
+            }
+        }
+    }
+
     Ok(TestOutput {
         temp_dir,
         result_path,
         stdout_path,
         stderr_path,
         test_status,
+        duration,
     })
 }
 
@@ -2818,6 +3296,7 @@ fn subcommand_show(
     options: &RawTestOptions,
     revset: Revset,
     resolve_revset_options: &ResolveRevsetOptions,
+    diff: bool,
 ) -> EyreExitOr<()> {
     let now = SystemTime::now();
     let repo = Repo::from_current_dir()?;
@@ -2856,10 +3335,12 @@ fn subcommand_show(
     )?);
 
     let commits = sorted_commit_set(&repo, &dag, &commit_set)?;
+    if diff {
+        return show_test_output_diff(effects, &repo, &options, &commits);
+    }
     for commit in commits {
-        let test_files = make_test_files(&repo, &commit, &options)?;
-        match test_files {
-            TestFilesResult::NotCached(_) => {
+        match read_cached_test_output(&repo, &commit, &options)? {
+            None => {
                 writeln!(
                     effects.get_output_stream(),
                     "No cached test data for {}",
@@ -2868,7 +3349,7 @@ fn subcommand_show(
                         .render(commit.friendly_describe(effects.get_glyphs())?)?
                 )?;
             }
-            TestFilesResult::Cached(test_output) => {
+            Some(test_output) => {
                 write!(
                     effects.get_output_stream(),
                     "{}",
@@ -2908,6 +3389,118 @@ fn subcommand_show(
     Ok(Ok(()))
 }
 
+/// Show the differences between the cached output of the test command on the
+/// two given commits.
+fn show_test_output_diff(
+    effects: &Effects,
+    repo: &Repo,
+    options: &ResolvedTestOptions,
+    commits: &[Commit],
+) -> EyreExitOr<()> {
+    let (old_commit, new_commit) = match commits {
+        [old_commit, new_commit] => (old_commit, new_commit),
+        commits => {
+            writeln!(
+                effects.get_output_stream(),
+                "The --diff option requires exactly 2 commits, but {} provided.",
+                Pluralize {
+                    determiner: None,
+                    amount: commits.len(),
+                    unit: ("commit was", "commits were"),
+                }
+            )?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+
+    let mut test_outputs = Vec::new();
+    for commit in [old_commit, new_commit] {
+        match read_cached_test_output(repo, commit, options)? {
+            Some(test_output) => {
+                write!(
+                    effects.get_output_stream(),
+                    "{}",
+                    effects.get_glyphs().render(test_output.describe(
+                        effects,
+                        commit,
+                        false,
+                        Verbosity::None
+                    )?)?,
+                )?;
+                test_outputs.push(test_output);
+            }
+            None => {
+                writeln!(
+                    effects.get_output_stream(),
+                    "No cached test data for {}",
+                    effects
+                        .get_glyphs()
+                        .render(commit.friendly_describe(effects.get_glyphs())?)?
+                )?;
+            }
+        }
+    }
+    let (old_output, new_output) = match test_outputs.as_slice() {
+        [old_output, new_output] => (old_output, new_output),
+        _ => return Ok(Err(ExitCode(1))),
+    };
+
+    let old_short_oid = old_commit.get_short_oid()?;
+    let new_short_oid = new_commit.get_short_oid()?;
+    for (name, old_path, new_path) in [
+        ("stdout", &old_output.stdout_path, &new_output.stdout_path),
+        ("stderr", &old_output.stderr_path, &new_output.stderr_path),
+    ] {
+        let read_output = |path: &Path| -> eyre::Result<String> {
+            match std::fs::read(path) {
+                Ok(contents) => Ok(String::from_utf8_lossy(&contents).into_owned()),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+                Err(err) => Err(err).wrap_err_with(|| format!("Reading test output {path:?}")),
+            }
+        };
+        let old_contents = read_output(old_path)?;
+        let new_contents = read_output(new_path)?;
+        let patch = diffy::create_patch(&old_contents, &new_contents);
+        if patch.hunks().is_empty() {
+            writeln!(
+                effects.get_output_stream(),
+                "{}",
+                effects.get_glyphs().render(
+                    StyledStringBuilder::new()
+                        .append_styled(format!("No differences in {name}"), Effect::Bold)
+                        .build()
+                )?
+            )?;
+            continue;
+        }
+
+        writeln!(
+            effects.get_output_stream(),
+            "{}",
+            effects.get_glyphs().render(
+                StyledStringBuilder::new()
+                    .append_styled(format!("--- {old_short_oid} {name}"), Effect::Bold)
+                    .build()
+            )?
+        )?;
+        writeln!(
+            effects.get_output_stream(),
+            "{}",
+            effects.get_glyphs().render(
+                StyledStringBuilder::new()
+                    .append_styled(format!("+++ {new_short_oid} {name}"), Effect::Bold)
+                    .build()
+            )?
+        )?;
+        // Skip the `---`/`+++` header lines, which were replaced above.
+        for line in patch.to_string().lines().skip(2) {
+            writeln!(effects.get_output_stream(), "{line}")?;
+        }
+    }
+
+    Ok(Ok(()))
+}
+
 /// Delete cached test output for the commits in `revset`.
 #[instrument]
 pub fn subcommand_clean(
@@ -2974,6 +3567,251 @@ pub fn subcommand_clean(
     Ok(Ok(()))
 }
 
+/// Remove the worktrees used by the `worktree` execution strategy, running the
+/// configured teardown command in each first. Worktrees which are currently in
+/// use are skipped.
+#[instrument]
+fn subcommand_clean_worktrees(effects: &Effects, git_run_info: &GitRunInfo) -> EyreExitOr<()> {
+    let repo = Repo::from_current_dir()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_tx_id = event_log_db.make_transaction_id(SystemTime::now(), "test clean")?;
+
+    let worktrees_dir = get_test_worktrees_dir(&repo)?;
+    let mut worktree_dirs = Vec::new();
+    if worktrees_dir.exists() {
+        for entry in std::fs::read_dir(&worktrees_dir)
+            .wrap_err_with(|| format!("Reading worktrees dir {worktrees_dir:?}"))?
+        {
+            let entry = entry?;
+            let worker_id = match entry.file_name().to_str() {
+                Some(file_name) => match file_name.strip_prefix(WORKTREE_DIR_NAME_PREFIX) {
+                    Some(worker_id) => worker_id.to_owned(),
+                    None => continue,
+                },
+                None => continue,
+            };
+            if entry.file_type()?.is_dir() {
+                worktree_dirs.push((worker_id, entry.path()));
+            }
+        }
+    }
+    worktree_dirs.sort();
+
+    let locks_dir = get_test_locks_dir(&repo)?;
+    std::fs::create_dir_all(&locks_dir)
+        .wrap_err_with(|| format!("Creating test lock dir path: {locks_dir:?}"))?;
+    let mut num_cleaned_worktrees = 0;
+    for (worker_id, worktree_dir) in worktree_dirs {
+        let lock_path = locks_dir.join(get_worktree_lock_file_name(&worker_id));
+        let mut lock_file = LockFile::open(&lock_path)
+            .wrap_err_with(|| format!("Opening worktree lock at {lock_path:?}"))?;
+        if !lock_file
+            .try_lock_with_pid()
+            .wrap_err_with(|| format!("Locking worktree with {lock_path:?}"))?
+        {
+            writeln!(
+                effects.get_output_stream(),
+                "Skipping worktree in use: {}",
+                worktree_dir.display()
+            )?;
+            continue;
+        }
+
+        if let Err(output) = run_worktree_hook(&repo, WORKTREE_TEARDOWN_CONFIG_KEY, &worktree_dir)?
+        {
+            writeln!(
+                effects.get_output_stream(),
+                "Teardown command failed for worktree: {}: {output}",
+                worktree_dir.display()
+            )?;
+        }
+        remove_worktree(git_run_info, &repo, event_tx_id, &worktree_dir)?;
+        writeln!(
+            effects.get_output_stream(),
+            "Removed worktree: {}",
+            worktree_dir.display()
+        )?;
+        num_cleaned_worktrees += 1;
+    }
+
+    writeln!(
+        effects.get_output_stream(),
+        "Cleaned {}.",
+        Pluralize {
+            determiner: None,
+            amount: num_cleaned_worktrees,
+            unit: ("worktree", "worktrees")
+        }
+    )?;
+    Ok(Ok(()))
+}
+
+/// Export the shareable cached test results for the commits in `revset` into
+/// the directory at `path`.
+#[instrument]
+fn subcommand_export(
+    effects: &Effects,
+    path: &Path,
+    revset: Revset,
+    resolve_revset_options: &ResolveRevsetOptions,
+) -> EyreExitOr<()> {
+    let repo = Repo::from_current_dir()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    let references_snapshot = repo.get_references_snapshot()?;
+    let mut dag = Dag::open_and_sync(
+        effects,
+        &repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    )?;
+
+    let commit_set =
+        match resolve_commits(effects, &repo, &mut dag, &[revset], resolve_revset_options) {
+            Ok(mut commit_sets) => commit_sets.pop().unwrap(),
+            Err(err) => {
+                err.describe(effects)?;
+                return Ok(Err(ExitCode(1)));
+            }
+        };
+    let tree_oids: HashSet<MaybeZeroOid> = sorted_commit_set(&repo, &dag, &commit_set)?
+        .into_iter()
+        .map(|commit| commit.get_tree_oid())
+        .collect();
+
+    let local_cache = DirectoryTestCache::for_repo(&repo)?;
+    let export_cache = DirectoryTestCache::new(path.to_owned());
+    let mut num_exported = 0;
+    for (tree_oid, command_slug) in local_cache.list_entries()? {
+        if !tree_oids.contains(&tree_oid) {
+            continue;
+        }
+        match local_cache.read_result(tree_oid, &command_slug) {
+            Ok(Some(result)) if result.is_shareable(tree_oid) => {}
+            Ok(_) => continue,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    ?tree_oid,
+                    ?command_slug,
+                    "Skipping unreadable test result"
+                );
+                continue;
+            }
+        }
+        if export_cache.store(
+            tree_oid,
+            &command_slug,
+            &local_cache.get_entry_dir(tree_oid, &command_slug),
+        )? {
+            num_exported += 1;
+        }
+    }
+
+    writeln!(
+        effects.get_output_stream(),
+        "Exported {}.",
+        Pluralize {
+            determiner: None,
+            amount: num_exported,
+            unit: ("cached test result", "cached test results")
+        }
+    )?;
+    Ok(Ok(()))
+}
+
+/// Import the shareable test results from the directory at `path` into the
+/// repository's cache.
+#[instrument]
+fn subcommand_import(effects: &Effects, path: &Path) -> EyreExitOr<()> {
+    let repo = Repo::from_current_dir()?;
+    if !path.is_dir() {
+        writeln!(
+            effects.get_error_stream(),
+            "Not a directory: {}",
+            path.display()
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    let local_cache = DirectoryTestCache::for_repo(&repo)?;
+    let import_cache = DirectoryTestCache::new(path.to_owned());
+    let mut num_imported = 0;
+    let mut num_in_progress = 0;
+    for (tree_oid, command_slug) in import_cache.list_entries()? {
+        match import_cache.read_result(tree_oid, &command_slug) {
+            Ok(Some(result)) if result.is_shareable(tree_oid) => {}
+            Ok(_) => continue,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    ?tree_oid,
+                    ?command_slug,
+                    "Skipping unreadable test result"
+                );
+                continue;
+            }
+        }
+
+        // Take the same lock as `make_test_files`, so that the entry isn't
+        // replaced while the command is running on it.
+        let command_dir = local_cache.get_entry_dir(tree_oid, &command_slug);
+        std::fs::create_dir_all(&command_dir)
+            .wrap_err_with(|| format!("Creating command directory {command_dir:?}"))?;
+        let lock_path = command_dir.join("pid.lock");
+        let mut lock_file = LockFile::open(&lock_path)
+            .wrap_err_with(|| format!("Opening lock file {lock_path:?}"))?;
+        if !lock_file
+            .try_lock_with_pid()
+            .wrap_err_with(|| format!("Locking file {lock_path:?}"))?
+        {
+            num_in_progress += 1;
+            continue;
+        }
+
+        // Local failures are frequently caused by the environment, so they're
+        // replaced by the imported passing result.
+        let should_import = match local_cache.read_result(tree_oid, &command_slug) {
+            Ok(Some(local_result)) => local_result.exit_code != TEST_SUCCESS_EXIT_CODE,
+            Ok(None) | Err(_) => true,
+        };
+        if should_import {
+            local_cache.replace(
+                tree_oid,
+                &command_slug,
+                &import_cache.get_entry_dir(tree_oid, &command_slug),
+            )?;
+            num_imported += 1;
+        }
+    }
+
+    writeln!(
+        effects.get_output_stream(),
+        "Imported {}.",
+        Pluralize {
+            determiner: None,
+            amount: num_imported,
+            unit: ("cached test result", "cached test results")
+        }
+    )?;
+    if num_in_progress > 0 {
+        writeln!(
+            effects.get_output_stream(),
+            "Skipped {} for commands which are currently running.",
+            Pluralize {
+                determiner: None,
+                amount: num_in_progress,
+                unit: ("cached test result", "cached test results")
+            }
+        )?;
+    }
+    Ok(Ok(()))
+}
+
 #[cfg(test)]
 mod tests {
     use lib::testing::make_git;
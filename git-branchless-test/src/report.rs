@@ -0,0 +1,331 @@
+//! Write the results of `git test run` to files in machine-readable formats,
+//! such as for publishing from CI.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use eyre::WrapErr;
+use git_branchless_opts::{TestReport, TestReportFormat};
+use lib::git::{NonZeroOid, Repo, SerializedTestResult, TestCommand};
+use serde::Serialize;
+
+use crate::{TestOutput, TestResults, TestStatus};
+
+/// The outcome of testing a commit, as it appears in a report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ReportStatus {
+    Passed,
+    Failed,
+    Aborted,
+    Skipped,
+    Error,
+}
+
+/// The result of testing a single commit, as it appears in a report.
+#[derive(Debug, Serialize)]
+struct ReportEntry {
+    commit_oid: String,
+    summary: String,
+    command: String,
+    status: ReportStatus,
+    exit_code: Option<i32>,
+    duration_secs: f64,
+    cached: bool,
+    message: Option<String>,
+
+    /// The paths to the stored output of the test command. These are `None`
+    /// when running with `--no-cache`, since the files are deleted once
+    /// testing finishes.
+    stdout_path: Option<PathBuf>,
+    stderr_path: Option<PathBuf>,
+}
+
+impl ReportEntry {
+    fn new(
+        repo: &Repo,
+        command: &TestCommand,
+        commit_oid: NonZeroOid,
+        test_output: &TestOutput,
+    ) -> eyre::Result<Self> {
+        let TestOutput {
+            temp_dir,
+            result_path,
+            stdout_path,
+            stderr_path,
+            test_status,
+            duration,
+        } = test_output;
+        let commit = repo.find_commit_or_fail(commit_oid)?;
+
+        let (status, exit_code, cached, message) = match test_status {
+            TestStatus::CheckoutFailed => (
+                ReportStatus::Error,
+                None,
+                false,
+                Some("failed to check out commit".to_string()),
+            ),
+            TestStatus::WorktreeSetupFailed(output) => (
+                ReportStatus::Error,
+                None,
+                false,
+                Some(format!("failed to set up worktree: {output}")),
+            ),
+            TestStatus::SpawnTestFailed(err) => (
+                ReportStatus::Error,
+                None,
+                false,
+                Some(format!("failed to spawn command: {err}")),
+            ),
+            TestStatus::TerminatedBySignal => (
+                ReportStatus::Error,
+                None,
+                false,
+                Some("command terminated by signal".to_string()),
+            ),
+            TestStatus::AlreadyInProgress => (
+                ReportStatus::Error,
+                None,
+                false,
+                Some("command already in progress".to_string()),
+            ),
+            TestStatus::ReadCacheFailed(err) => (
+                ReportStatus::Error,
+                None,
+                true,
+                Some(format!("could not read cached command result: {err}")),
+            ),
+            TestStatus::Indeterminate { cached, exit_code } => (
+                ReportStatus::Skipped,
+                Some(*exit_code),
+                *cached,
+                Some(format!(
+                    "exit code {exit_code} indicated to skip this commit"
+                )),
+            ),
+            TestStatus::Abort { cached, exit_code } => (
+                ReportStatus::Aborted,
+                Some(*exit_code),
+                *cached,
+                Some(format!("exit code {exit_code} indicated to abort testing")),
+            ),
+            TestStatus::Failed {
+                cached,
+                exit_code,
+                interactive: _,
+                flaky,
+            } => (
+                ReportStatus::Failed,
+                Some(*exit_code),
+                *cached,
+                Some(if *flaky {
+                    format!("exit code {exit_code} (flaky)")
+                } else {
+                    format!("exit code {exit_code}")
+                }),
+            ),
+            TestStatus::Passed {
+                cached,
+                fix_info: _,
+                interactive: _,
+            } => (ReportStatus::Passed, Some(0), *cached, None),
+        };
+
+        // Cached results don't run the command, so report how long it took
+        // when the result was recorded.
+        let duration = if cached {
+            read_cached_duration(result_path).unwrap_or(*duration)
+        } else {
+            *duration
+        };
+
+        let (stdout_path, stderr_path) = match temp_dir {
+            Some(_) => (None, None),
+            None => (Some(stdout_path.clone()), Some(stderr_path.clone())),
+        };
+
+        Ok(Self {
+            commit_oid: commit_oid.to_string(),
+            summary: commit.get_summary()?.to_string(),
+            command: command.to_string(),
+            status,
+            exit_code,
+            duration_secs: duration.as_secs_f64(),
+            cached,
+            message,
+            stdout_path,
+            stderr_path,
+        })
+    }
+}
+
+/// Read the duration of a single run of the test command from a cached result
+/// file, if it was recorded.
+fn read_cached_duration(result_path: &Path) -> Option<Duration> {
+    let contents = std::fs::read_to_string(result_path).ok()?;
+    let SerializedTestResult { duration_secs, .. } = serde_json::from_str(&contents).ok()?;
+    Duration::try_from_secs_f64(duration_secs?).ok()
+}
+
+/// Write each of the requested reports for the given test results.
+pub(crate) fn write_reports(
+    repo: &Repo,
+    command: &TestCommand,
+    test_results: &TestResults,
+    reports: &[TestReport],
+) -> eyre::Result<()> {
+    if reports.is_empty() {
+        return Ok(());
+    }
+
+    let entries: Vec<ReportEntry> = test_results
+        .test_outputs
+        .iter()
+        .map(|(commit_oid, test_output)| ReportEntry::new(repo, command, *commit_oid, test_output))
+        .collect::<eyre::Result<_>>()?;
+    for TestReport { format, path } in reports {
+        let contents = match format {
+            TestReportFormat::Junit => render_junit(command, &entries)?,
+            TestReportFormat::Json => serde_json::to_string_pretty(&entries)?,
+        };
+        std::fs::write(path, contents)
+            .wrap_err_with(|| format!("Writing test report to {path:?}"))?;
+    }
+    Ok(())
+}
+
+/// Render the entries as a JUnit XML document with a single test suite, where
+/// each commit is a test case.
+fn render_junit(command: &TestCommand, entries: &[ReportEntry]) -> eyre::Result<String> {
+    let count_status = |status: ReportStatus| {
+        entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    };
+    let total_duration: Duration = entries
+        .iter()
+        .map(|entry| Duration::from_secs_f64(entry.duration_secs))
+        .sum();
+
+    let mut result = String::new();
+    writeln!(result, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(result, "<testsuites>")?;
+    writeln!(
+        result,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+        escape_xml(&command.to_string()),
+        entries.len(),
+        count_status(ReportStatus::Failed) + count_status(ReportStatus::Aborted),
+        count_status(ReportStatus::Error),
+        count_status(ReportStatus::Skipped),
+        total_duration.as_secs_f64(),
+    )?;
+
+    for entry in entries {
+        let ReportEntry {
+            commit_oid,
+            summary,
+            command,
+            status,
+            exit_code,
+            duration_secs,
+            cached,
+            message,
+            stdout_path,
+            stderr_path,
+        } = entry;
+        writeln!(
+            result,
+            r#"    <testcase name="{}" classname="{}" time="{duration_secs:.3}">"#,
+            escape_xml(&format!("{commit_oid} {summary}")),
+            escape_xml(command),
+        )?;
+
+        writeln!(result, "      <properties>")?;
+        let mut properties = vec![
+            ("commit", commit_oid.clone()),
+            ("cached", cached.to_string()),
+        ];
+        if let Some(exit_code) = exit_code {
+            properties.push(("exit_code", exit_code.to_string()));
+        }
+        if let Some(stdout_path) = stdout_path {
+            properties.push(("stdout", stdout_path.to_string_lossy().into_owned()));
+        }
+        if let Some(stderr_path) = stderr_path {
+            properties.push(("stderr", stderr_path.to_string_lossy().into_owned()));
+        }
+        for (name, value) in properties {
+            writeln!(
+                result,
+                r#"        <property name="{name}" value="{}"/>"#,
+                escape_xml(&value)
+            )?;
+        }
+        writeln!(result, "      </properties>")?;
+
+        let element = match status {
+            ReportStatus::Passed => None,
+            ReportStatus::Failed | ReportStatus::Aborted => Some("failure"),
+            ReportStatus::Skipped => Some("skipped"),
+            ReportStatus::Error => Some("error"),
+        };
+        if let Some(element) = element {
+            writeln!(
+                result,
+                r#"      <{element} message="{}"/>"#,
+                escape_xml(message.as_deref().unwrap_or_default()),
+            )?;
+        }
+        writeln!(result, "    </testcase>")?;
+    }
+
+    writeln!(result, "  </testsuite>")?;
+    writeln!(result, "</testsuites>")?;
+    Ok(result)
+}
+
+/// Escape a string for use in an XML attribute value. Characters which aren't
+/// allowed in XML 1.0 documents, such as most control characters, are replaced
+/// with U+FFFD, and whitespace other than spaces is written as character
+/// references so that it isn't normalized away.
+fn escape_xml(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            '\t' => result.push_str("&#9;"),
+            '\n' => result.push_str("&#10;"),
+            '\r' => result.push_str("&#13;"),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => {
+                result.push(char::REPLACEMENT_CHARACTER)
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape_xml;
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml("<a href=\"x\">'&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+        assert_eq!(escape_xml("a\tb\r\nc"), "a&#9;b&#13;&#10;c");
+        assert_eq!(
+            escape_xml("\u{1b}[31mred\u{1b}[0m\u{0}\u{ffff}"),
+            "\u{fffd}[31mred\u{fffd}[0m\u{fffd}\u{fffd}"
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use lib::testing::{make_git, GitRunOptions, GitWrapper};
+use lib::testing::{
+    make_git, make_git_with_remote_repo, GitInitOptions, GitRunOptions, GitWrapper,
+    GitWrapperWithRemoteRepo,
+};
 use maplit::hashmap;
 
 fn write_test_script(git: &GitWrapper) -> eyre::Result<()> {
@@ -368,9 +371,9 @@ fn test_test_show() -> eyre::Result<()> {
         let (stdout, stderr) = git.branchless("test", &["clean"])?;
         insta::assert_snapshot!(stderr, @"");
         insta::assert_snapshot!(stdout, @r###"
-        Cleaning results for 62fc20d create test1.txt
+        Nothing to clean for 62fc20d create test1.txt
         Cleaning results for 96d1c37 create test2.txt
-        Cleaned 2 cached test results.
+        Cleaned 1 cached test result.
         "###);
     }
 
@@ -388,6 +391,54 @@ fn test_test_show() -> eyre::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_test_show_diff() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    let command = "ls *.txt; test ! -f test2.txt || echo test2 exists >&2";
+    git.branchless("test", &["run", "-x", command, "stack()"])?;
+
+    {
+        let (stdout, _stderr) =
+            git.branchless("test", &["show", "-x", command, "--diff", "stack()"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        ✓ Passed (cached): 62fc20d create test1.txt
+        ✓ Passed (cached): 96d1c37 create test2.txt
+        --- 62fc20d stdout
+        +++ 96d1c37 stdout
+        @@ -1,2 +1,3 @@
+         initial.txt
+         test1.txt
+        +test2.txt
+        --- 62fc20d stderr
+        +++ 96d1c37 stderr
+        @@ -0,0 +1 @@
+        +test2 exists
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["show", "-x", command, "--diff", "@"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        The --diff option requires exactly 2 commits, but 1 commit was provided.
+        "###);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_test_command_alias() -> eyre::Result<()> {
     let git = make_git()?;
@@ -1897,3 +1948,613 @@ fn test_test_no_cache() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_test_report() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    let json_path = git.repo_path.join("report.json");
+    let junit_path = git.repo_path.join("report.xml");
+    let redact = |contents: String| -> String {
+        let contents = contents.replace(&git.repo_path.to_string_lossy().to_string(), "<repo>");
+        // Durations vary between runs.
+        let mut result = String::new();
+        let mut rest = contents.as_str();
+        while let Some((before, after)) = rest.split_once("time=\"") {
+            result.push_str(before);
+            result.push_str("time=\"<duration>");
+            rest = after.split_once('"').map_or("", |(_duration, rest)| rest);
+            result.push('"');
+        }
+        result.push_str(rest);
+        result
+            .lines()
+            .map(|line| match line.split_once("\"duration_secs\": ") {
+                Some((before, _duration)) => format!("{before}\"duration_secs\": <duration>,"),
+                None => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &[
+                "run",
+                "-x",
+                "! test -f test2.txt",
+                "--report",
+                &format!("json={}", json_path.to_str().unwrap()),
+                "--report",
+                &format!("junit={}", junit_path.to_str().unwrap()),
+            ],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 62fc20d create test1.txt
+        X Failed (exit code 1): 96d1c37 create test2.txt
+        Ran command on 2 commits: ! test -f test2.txt
+        1 passed, 1 failed, 0 skipped
+        "###);
+        insta::assert_snapshot!(redact(std::fs::read_to_string(&json_path)?), @r###"
+        [
+          {
+            "commit_oid": "62fc20d2a290daea0d52bdc2ed2ad4be6491010e",
+            "summary": "create test1.txt",
+            "command": "! test -f test2.txt",
+            "status": "passed",
+            "exit_code": 0,
+            "duration_secs": <duration>,
+            "cached": false,
+            "message": null,
+            "stdout_path": "<repo>/.git/branchless/test/8108c01b1930423879f106c1ebf725fcbfedccda/!__test__-f__test2.txt/stdout",
+            "stderr_path": "<repo>/.git/branchless/test/8108c01b1930423879f106c1ebf725fcbfedccda/!__test__-f__test2.txt/stderr"
+          },
+          {
+            "commit_oid": "96d1c37a3d4363611c49f7e52186e189a04c531f",
+            "summary": "create test2.txt",
+            "command": "! test -f test2.txt",
+            "status": "failed",
+            "exit_code": 1,
+            "duration_secs": <duration>,
+            "cached": false,
+            "message": "exit code 1",
+            "stdout_path": "<repo>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/!__test__-f__test2.txt/stdout",
+            "stderr_path": "<repo>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/!__test__-f__test2.txt/stderr"
+          }
+        ]
+        "###);
+        insta::assert_snapshot!(redact(std::fs::read_to_string(&junit_path)?), @r###"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <testsuites>
+          <testsuite name="! test -f test2.txt" tests="2" failures="1" errors="0" skipped="0" time="<duration>">
+            <testcase name="62fc20d2a290daea0d52bdc2ed2ad4be6491010e create test1.txt" classname="! test -f test2.txt" time="<duration>">
+              <properties>
+                <property name="commit" value="62fc20d2a290daea0d52bdc2ed2ad4be6491010e"/>
+                <property name="cached" value="false"/>
+                <property name="exit_code" value="0"/>
+                <property name="stdout" value="<repo>/.git/branchless/test/8108c01b1930423879f106c1ebf725fcbfedccda/!__test__-f__test2.txt/stdout"/>
+                <property name="stderr" value="<repo>/.git/branchless/test/8108c01b1930423879f106c1ebf725fcbfedccda/!__test__-f__test2.txt/stderr"/>
+              </properties>
+            </testcase>
+            <testcase name="96d1c37a3d4363611c49f7e52186e189a04c531f create test2.txt" classname="! test -f test2.txt" time="<duration>">
+              <properties>
+                <property name="commit" value="96d1c37a3d4363611c49f7e52186e189a04c531f"/>
+                <property name="cached" value="false"/>
+                <property name="exit_code" value="1"/>
+                <property name="stdout" value="<repo>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/!__test__-f__test2.txt/stdout"/>
+                <property name="stderr" value="<repo>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/!__test__-f__test2.txt/stderr"/>
+              </properties>
+              <failure message="exit code 1"/>
+            </testcase>
+          </testsuite>
+        </testsuites>
+        "###);
+    }
+
+    {
+        // Cached results report the duration recorded when the command ran.
+        let read_durations = || -> eyre::Result<Vec<(bool, f64)>> {
+            let entries: Vec<serde_json::Value> =
+                serde_json::from_str(&std::fs::read_to_string(&json_path)?)?;
+            Ok(entries
+                .iter()
+                .map(|entry| {
+                    (
+                        entry["cached"].as_bool().unwrap(),
+                        entry["duration_secs"].as_f64().unwrap(),
+                    )
+                })
+                .collect())
+        };
+        let uncached_durations = read_durations()?;
+        git.branchless_with_options(
+            "test",
+            &[
+                "run",
+                "-x",
+                "! test -f test2.txt",
+                "--report",
+                &format!("json={}", json_path.to_str().unwrap()),
+            ],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        let cached_durations = read_durations()?;
+        assert_eq!(cached_durations.len(), uncached_durations.len());
+        for ((was_cached, uncached_duration), (is_cached, cached_duration)) in
+            uncached_durations.into_iter().zip(cached_durations)
+        {
+            assert!(!was_cached);
+            assert!(is_cached);
+            assert!((cached_duration - uncached_duration).abs() < 1e-6);
+        }
+    }
+
+    {
+        let (_stdout, stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-x", "exit 0", "--report", "xml=report.xml"],
+            &GitRunOptions {
+                expected_exit_code: 2,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @r###"
+        error: invalid value 'xml=report.xml' for '--report <REPORTS>': invalid variant: xml
+
+        For more information, try '--help'.
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_repeat() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    // Only the second run of the command fails, so the first commit tested
+    // is flaky.
+    let command = "echo run >> .git/run-count; test $(wc -l < .git/run-count) -ne 2";
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "--repeat", "3", "-x", command],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        X Failed (flaky, exit code 1): 62fc20d create test1.txt
+        ✓ Passed: 96d1c37 create test2.txt
+        Ran command on 2 commits: echo run >> .git/run-count; test $(wc -l < .git/run-count) -ne 2
+        1 passed, 1 failed, 0 skipped
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("query", &["tests.flaky()"])?;
+        insta::assert_snapshot!(stdout, @"62fc20d create test1.txt");
+    }
+    {
+        let (stdout, _stderr) = git.branchless("query", &["tests.slower_than(0s)"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        62fc20d create test1.txt
+        96d1c37 create test2.txt
+        "###);
+    }
+    {
+        let (stdout, _stderr) = git.branchless("query", &["tests.slower_than('1h')"])?;
+        insta::assert_snapshot!(stdout, @"");
+    }
+    {
+        let (_stdout, stderr) = git.branchless_with_options(
+            "query",
+            &["tests.slower_than(soon)"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @r###"
+        Evaluation error for expression 'tests.slower_than(soon)': not a duration: soon (expected a number followed by a unit, such as 30s, 500ms, 2m or 1h)
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "--repeat", "0", "-x", "true"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @"The --repeat option must be at least 1.");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_repeat_after_cached_run() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    let run_count_path = git.repo_path.join(".git").join("run-count");
+    let command = "echo run >> .git/run-count";
+    {
+        let (stdout, _stderr) = git.branchless("test", &["run", "-x", command])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 62fc20d create test1.txt
+        ✓ Passed: 96d1c37 create test2.txt
+        Ran command on 2 commits: echo run >> .git/run-count
+        2 passed, 0 failed, 0 skipped
+        "###);
+        insta::assert_snapshot!(std::fs::read_to_string(&run_count_path)?.lines().count(), @"2");
+    }
+
+    // The cached results only come from one run each, so the command is run
+    // again to satisfy `--repeat`.
+    {
+        let (stdout, _stderr) = git.branchless("test", &["run", "--repeat", "3", "-x", command])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 62fc20d create test1.txt
+        ✓ Passed: 96d1c37 create test2.txt
+        Ran command on 2 commits: echo run >> .git/run-count
+        2 passed, 0 failed, 0 skipped
+        "###);
+        insta::assert_snapshot!(std::fs::read_to_string(&run_count_path)?.lines().count(), @"8");
+    }
+
+    // Results from at least as many runs as requested are reused.
+    {
+        let (stdout, _stderr) = git.branchless("test", &["run", "--repeat", "2", "-x", command])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed (cached): 62fc20d create test1.txt
+        ✓ Passed (cached): 96d1c37 create test2.txt
+        Ran command on 2 commits: echo run >> .git/run-count
+        2 passed, 0 failed, 0 skipped
+        hint: there were 2 cached test results
+        hint: to clear these cached results, run: git test clean "stack() | @"
+        hint: disable this hint by running: git config --global branchless.hint.cleanCachedTestResults false
+        "###);
+        insta::assert_snapshot!(std::fs::read_to_string(&run_count_path)?.lines().count(), @"8");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_worktree_pool() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    let teardown_log_path = git.repo_path.join("teardown.log");
+    git.run(&["config", "branchless.test.strategy", "worktree"])?;
+    git.run(&[
+        "config",
+        "branchless.test.worktreeSetup",
+        "echo set up >> setup.log",
+    ])?;
+    git.run(&[
+        "config",
+        "branchless.test.worktreeTeardown",
+        &format!(
+            "echo torn down after $(wc -l < setup.log) setup >> {}",
+            teardown_log_path.to_str().unwrap()
+        ),
+    ])?;
+
+    // The setup command is only run when the worktree is created, and the
+    // worktree is reused for later commits and later invocations.
+    let command = "test $(wc -l < setup.log) -eq 1";
+    {
+        let (stdout, _stderr) = git.branchless("test", &["run", "-x", command])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        ✓ Passed: 62fc20d create test1.txt
+        ✓ Passed: 96d1c37 create test2.txt
+        Ran command on 2 commits: test $(wc -l < setup.log) -eq 1
+        2 passed, 0 failed, 0 skipped
+        "###);
+    }
+    {
+        let (stdout, _stderr) = git.branchless("test", &["run", "--no-cache", "-x", command])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        ✓ Passed: 62fc20d create test1.txt
+        ✓ Passed: 96d1c37 create test2.txt
+        Ran command on 2 commits: test $(wc -l < setup.log) -eq 1
+        2 passed, 0 failed, 0 skipped
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("test", &["clean", "--worktrees"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Removed worktree: <repo-path>/.git/branchless/test/worktrees/testing-worktree-1
+        Cleaned 1 worktree.
+        "###);
+        insta::assert_snapshot!(std::fs::read_to_string(&teardown_log_path)?, @"torn down after 1 setup");
+    }
+    {
+        let (stdout, _stderr) = git.branchless("test", &["clean", "--worktrees"])?;
+        insta::assert_snapshot!(stdout, @"Cleaned 0 worktrees.");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_worktree_setup_failure() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    let setup_log_path = git.repo_path.join("setup.log");
+    let setup_log_path = setup_log_path.to_str().unwrap();
+    git.run(&["config", "branchless.test.strategy", "worktree"])?;
+    git.run(&[
+        "config",
+        "branchless.test.worktreeSetup",
+        &format!("echo set up >> {setup_log_path}; echo missing dependency >&2; exit 1"),
+    ])?;
+
+    // The setup command isn't run again for the remaining commits once it
+    // has failed.
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-x", "exit 0"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        ! Failed to set up worktree: branchless.test.worktreeSetup command failed (exit status: 1): missing dependency: 62fc20d create test1.txt
+        ! Failed to set up worktree: branchless.test.worktreeSetup command failed (exit status: 1): missing dependency: 96d1c37 create test2.txt
+        Ran command on 2 commits: exit 0
+        0 passed, 0 failed, 2 skipped
+        "###);
+        insta::assert_snapshot!(std::fs::read_to_string(setup_log_path)?, @"set up");
+    }
+
+    // The setup command is attempted again by the next invocation.
+    git.run(&[
+        "config",
+        "branchless.test.worktreeSetup",
+        &format!("echo set up >> {setup_log_path}"),
+    ])?;
+    {
+        let (stdout, _stderr) = git.branchless("test", &["run", "-x", "exit 0"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        ✓ Passed: 62fc20d create test1.txt
+        ✓ Passed: 96d1c37 create test2.txt
+        Ran command on 2 commits: exit 0
+        2 passed, 0 failed, 0 skipped
+        "###);
+        insta::assert_snapshot!(std::fs::read_to_string(setup_log_path)?, @r###"
+        set up
+        set up
+        "###);
+    }
+
+    // A worktree which Git no longer knows about is recreated, even though
+    // its directory still exists.
+    std::fs::remove_dir_all(git.repo_path.join(".git").join("worktrees"))?;
+    {
+        let (stdout, _stderr) = git.branchless("test", &["run", "--no-cache", "-x", "exit 0"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        ✓ Passed: 62fc20d create test1.txt
+        ✓ Passed: 96d1c37 create test2.txt
+        Ran command on 2 commits: exit 0
+        2 passed, 0 failed, 0 skipped
+        "###);
+        insta::assert_snapshot!(std::fs::read_to_string(setup_log_path)?, @r###"
+        set up
+        set up
+        set up
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_shared_cache() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir,
+        original_repo,
+        cloned_repo,
+    } = make_git_with_remote_repo()?;
+
+    original_repo.init_repo()?;
+    original_repo.commit_file("test1", 1)?;
+    original_repo.clone_repo_into(&cloned_repo, &[])?;
+    cloned_repo.init_repo_with_options(&GitInitOptions {
+        make_initial_commit: false,
+        ..Default::default()
+    })?;
+    for git in [&original_repo, &cloned_repo] {
+        git.detach_head()?;
+        git.commit_file("test2", 2)?;
+    }
+
+    let export_dir = temp_dir.path().join("export");
+    let export_dir = export_dir.to_str().unwrap();
+    {
+        let (stdout, _stderr) = original_repo.branchless("test", &["run", "-x", "exit 0"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 96d1c37 create test2.txt
+        Ran command on 1 commit: exit 0
+        1 passed, 0 failed, 0 skipped
+        "###);
+
+        // Failing results aren't exported.
+        original_repo.branchless_with_options(
+            "test",
+            &["run", "-x", "exit 1"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+
+        // Untracked files aren't part of the tree, so this only passes in the
+        // original repository.
+        std::fs::write(original_repo.repo_path.join("marker"), "")?;
+        original_repo.branchless("test", &["run", "-x", "test -f marker"])?;
+
+        let (stdout, _stderr) = original_repo.branchless("test", &["export", export_dir])?;
+        insta::assert_snapshot!(stdout, @"Exported 2 cached test results.");
+    }
+
+    {
+        cloned_repo.branchless_with_options(
+            "test",
+            &["run", "-x", "test -f marker"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+
+        // The local failure is replaced by the imported passing result.
+        let (stdout, _stderr) = cloned_repo.branchless("test", &["import", export_dir])?;
+        insta::assert_snapshot!(stdout, @"Imported 2 cached test results.");
+
+        let (stdout, _stderr) = cloned_repo.branchless("test", &["run", "-x", "test -f marker"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed (cached): 96d1c37 create test2.txt
+        Ran command on 1 commit: test -f marker
+        1 passed, 0 failed, 0 skipped
+        hint: there was 1 cached test result
+        hint: to clear these cached results, run: git test clean "stack() | @"
+        hint: disable this hint by running: git config --global branchless.hint.cleanCachedTestResults false
+        "###);
+
+        let (stdout, _stderr) = cloned_repo.branchless("test", &["run", "-x", "exit 0"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed (cached): 96d1c37 create test2.txt
+        Ran command on 1 commit: exit 0
+        1 passed, 0 failed, 0 skipped
+        hint: there was 1 cached test result
+        hint: to clear these cached results, run: git test clean "stack() | @"
+        hint: disable this hint by running: git config --global branchless.hint.cleanCachedTestResults false
+        "###);
+
+        // Importing again doesn't overwrite the existing results.
+        let (stdout, _stderr) = cloned_repo.branchless("test", &["import", export_dir])?;
+        insta::assert_snapshot!(stdout, @"Imported 0 cached test results.");
+    }
+
+    let shared_dir = temp_dir.path().join("shared");
+    let shared_dir = shared_dir.to_str().unwrap();
+    for git in [&original_repo, &cloned_repo] {
+        git.run(&["config", "branchless.test.sharedCacheDir", shared_dir])?;
+    }
+    {
+        let (stdout, _stderr) = original_repo.branchless("test", &["run", "-x", "echo hello"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 96d1c37 create test2.txt
+        Ran command on 1 commit: echo hello
+        1 passed, 0 failed, 0 skipped
+        "###);
+
+        let (stdout, _stderr) = cloned_repo.branchless("test", &["run", "-x", "echo hello"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed (cached): 96d1c37 create test2.txt
+        Ran command on 1 commit: echo hello
+        1 passed, 0 failed, 0 skipped
+        hint: there was 1 cached test result
+        hint: to clear these cached results, run: git test clean "stack() | @"
+        hint: disable this hint by running: git config --global branchless.hint.cleanCachedTestResults false
+        "###);
+    }
+
+    Ok(())
+}
@@ -21,7 +21,7 @@ use lib::core::formatting::Glyphs;
 use lib::core::node_descriptors::{
     BranchesDescriptor, CommitMessageDescriptor, CommitOidDescriptor,
     DifferentialRevisionDescriptor, ObsolescenceExplanationDescriptor, Redactor,
-    RelativeTimeDescriptor,
+    RelativeTimeDescriptor, ReviewStatusDescriptor,
 };
 use lib::git::{GitRunInfo, Repo, ResolvedReferenceInfo};
 
@@ -154,6 +154,7 @@ fn describe_event_cursor(
             &mut ObsolescenceExplanationDescriptor::new(event_replayer, event_cursor)?,
             &mut BranchesDescriptor::new(repo, head_info, references_snapshot, redactor)?,
             &mut DifferentialRevisionDescriptor::new(repo, redactor)?,
+            &mut ReviewStatusDescriptor::new(repo, redactor)?,
             &mut CommitMessageDescriptor::new(redactor)?,
         ],
     )?;
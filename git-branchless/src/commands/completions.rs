@@ -0,0 +1,139 @@
+//! Shell completion scripts, and the dynamic completion of revset arguments
+//! which those scripts call back into.
+
+use std::fmt::Write;
+
+use git_branchless_init::ALL_ALIASES;
+use git_branchless_opts::{write_completion_script, Shell};
+use git_branchless_revset::{list_functions, FunctionInfo};
+use lib::core::dag::Dag;
+use lib::core::effects::Effects;
+use lib::core::eventlog::{EventLogDb, EventReplayer};
+use lib::core::repo_ext::RepoExt;
+use lib::git::{CategorizedReferenceName, Repo};
+use lib::util::EyreExitOr;
+use tracing::{instrument, warn};
+
+/// Print the completion script for the given shell or, if `revset` is
+/// provided, the candidates for completing that partial revset expression.
+#[instrument]
+pub fn completions(effects: &Effects, shell: Shell, revset: Option<String>) -> EyreExitOr<()> {
+    match revset {
+        None => {
+            let mut buffer = Vec::new();
+            write_completion_script(shell, ALL_ALIASES, &mut buffer)?;
+            write!(
+                effects.get_output_stream(),
+                "{}",
+                String::from_utf8_lossy(&buffer)
+            )?;
+        }
+
+        Some(revset) => {
+            let (prefix, stem) = split_revset_word(&revset);
+            for (candidate, description) in get_revset_candidates(effects) {
+                if !candidate.starts_with(stem) {
+                    continue;
+                }
+                let candidate = format!("{prefix}{candidate}");
+                match shell {
+                    Shell::Bash => writeln!(effects.get_output_stream(), "{candidate}")?,
+                    Shell::Zsh => writeln!(
+                        effects.get_output_stream(),
+                        "{}:{description}",
+                        candidate.replace(':', r"\:")
+                    )?,
+                    Shell::Fish => {
+                        writeln!(effects.get_output_stream(), "{candidate}\t{description}")?
+                    }
+                }
+            }
+        }
+    }
+    Ok(Ok(()))
+}
+
+/// Split a partial revset expression into the part before the name currently
+/// being typed, and the name itself. For example, `draft() & fo` is split into
+/// `draft() & ` and `fo`.
+fn split_revset_word(revset: &str) -> (&str, &str) {
+    let operator_end = revset
+        .rfind(|c: char| c.is_whitespace() || "()|&,:+~^%".contains(c))
+        .map(|index| index + 1);
+    let range_end = revset.rfind("..").map(|index| index + 2);
+    let index = operator_end.max(range_end).unwrap_or_default();
+    revset.split_at(index)
+}
+
+/// Get the names which could appear in a revset expression, along with a
+/// description of each. Failures are ignored, since there's no way to report
+/// them during completion.
+fn get_revset_candidates(effects: &Effects) -> Vec<(String, String)> {
+    let repo = match Repo::from_current_dir() {
+        Ok(repo) => repo,
+        Err(err) => {
+            warn!(?err, "Not completing revset outside of a repository");
+            return Vec::new();
+        }
+    };
+
+    let mut result = match get_commit_candidates(effects, &repo) {
+        Ok(result) => result,
+        Err(err) => {
+            warn!(
+                ?err,
+                "Could not get branches and commits for revset completion"
+            );
+            Vec::new()
+        }
+    };
+    match list_functions(&repo) {
+        Ok(functions) => {
+            for function in functions {
+                let description = match &function {
+                    FunctionInfo::Builtin { .. } => "function".to_owned(),
+                    FunctionInfo::Alias { alias, .. } => format!("alias for {alias}"),
+                };
+                result.push((format!("{}(", function.get_name()), description));
+            }
+        }
+        Err(err) => {
+            warn!(?err, "Could not list functions for revset completion");
+        }
+    }
+    result
+}
+
+/// Get the branch names and the short OIDs of draft commits.
+fn get_commit_candidates(effects: &Effects, repo: &Repo) -> eyre::Result<Vec<(String, String)>> {
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_replayer = EventReplayer::from_event_log_db(effects, repo, &event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    let references_snapshot = repo.get_references_snapshot()?;
+    let dag = Dag::open_and_sync(
+        effects,
+        repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    )?;
+
+    let mut branch_names: Vec<String> = references_snapshot
+        .branch_oid_to_names
+        .values()
+        .flatten()
+        .map(|reference_name| CategorizedReferenceName::new(reference_name).render_suffix())
+        .collect();
+    branch_names.sort();
+    let mut result: Vec<(String, String)> = branch_names
+        .into_iter()
+        .map(|branch_name| (branch_name, "branch".to_owned()))
+        .collect();
+
+    for commit_oid in dag.sort(dag.query_draft_commits()?)? {
+        let commit = repo.find_commit_or_fail(commit_oid)?;
+        result.push((commit.get_short_oid()?, commit.get_summary()?.to_string()));
+    }
+    Ok(result)
+}
@@ -2,6 +2,7 @@
 
 mod amend;
 mod bug_report;
+mod completions;
 mod hide;
 mod repair;
 mod restack;
@@ -16,7 +17,8 @@ use lib::util::ExitCode;
 use lib::{core::gc, util::EyreExitOr};
 
 use git_branchless_opts::{
-    rewrite_args, Command, Opts, ResolveRevsetOptions, SnapshotSubcommand, WrappedCommand,
+    rewrite_args, Command, CompletionsArgs, Opts, ResolveRevsetOptions, SnapshotSubcommand,
+    WrappedCommand,
 };
 use lib::git::GitRunInfo;
 
@@ -44,6 +46,10 @@ fn command_main(ctx: CommandContext, opts: Opts) -> EyreExitOr<()> {
 
         Command::BugReport => bug_report::bug_report(&effects, &git_run_info)?,
 
+        Command::Completions(CompletionsArgs { shell, revset }) => {
+            completions::completions(&effects, shell, revset)?
+        }
+
         Command::Difftool(opts) => {
             let result = scm_diff_editor::run(opts);
             match result {
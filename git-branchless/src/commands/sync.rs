@@ -1,7 +1,10 @@
 //! Implements the `git sync` command.
 
 use cursive_core::theme::BaseColor;
+use lazy_static::lazy_static;
 use lib::try_exit_code;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::time::SystemTime;
 
@@ -13,19 +16,23 @@ use rayon::{ThreadPool, ThreadPoolBuilder};
 
 use git_branchless_opts::{MoveOptions, ResolveRevsetOptions, Revset};
 use git_branchless_revset::{check_revset_syntax, resolve_commits};
-use lib::core::config::get_restack_preserve_timestamps;
+use git_branchless_submit::gerrit::get_change_id;
+use git_branchless_submit::{read_stack_records, should_record_stacks, StackRecord};
+use lib::core::config::{get_main_branch_name, get_restack_preserve_timestamps};
 use lib::core::dag::{sorted_commit_set, union_all, CommitSet, Dag};
 use lib::core::effects::{Effects, OperationType, WithProgress};
-use lib::core::eventlog::{EventLogDb, EventReplayer};
+use lib::core::eventlog::{Event, EventLogDb, EventReplayer, EventTransactionId};
 use lib::core::formatting::{Pluralize, StyledStringBuilder};
+use lib::core::node_descriptors::extract_diff_number;
 use lib::core::rewrite::{
-    execute_rebase_plan, BuildRebasePlanError, BuildRebasePlanOptions, ExecuteRebasePlanOptions,
-    ExecuteRebasePlanResult, FailedMergeInfo, RebasePlan, RebasePlanBuilder, RebasePlanPermissions,
-    RepoPool, RepoResource,
+    execute_rebase_plan, move_branches, BuildRebasePlanError, BuildRebasePlanOptions,
+    ExecuteRebasePlanOptions, ExecuteRebasePlanResult, FailedMergeInfo, MergeConflictRemediation,
+    RebasePlan, RebasePlanBuilder, RebasePlanPermissions, RepoPool, RepoResource,
 };
 use lib::core::task::ResourcePool;
 use lib::git::{
-    CategorizedReferenceName, Commit, GitRunInfo, NonZeroOid, Repo, ResolvedReferenceInfo,
+    read_review_info_cache, update_review_info_cache, BranchType, CategorizedReferenceName, Commit,
+    GitRunInfo, MaybeZeroOid, NonZeroOid, Repo, ResolvedReferenceInfo, ReviewInfoCache,
 };
 
 fn get_stack_roots(dag: &Dag, commit_sets: Vec<CommitSet>) -> eyre::Result<CommitSet> {
@@ -45,6 +52,247 @@ fn get_stack_roots(dag: &Dag, commit_sets: Vec<CommitSet>) -> eyre::Result<Commi
         .map_err(|err| eyre::eyre!("Could not query DAG for stack roots: {err}"))
 }
 
+/// An identifier which ties a local commit to the commit that it was landed
+/// as on the main branch, even if the landed commit has a different patch or
+/// message (such as when a pull request is squash-merged).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum LandedCommitKey {
+    /// The Gerrit `Change-Id` trailer.
+    ChangeId(String),
+
+    /// The Phabricator revision from the `Differential Revision` trailer,
+    /// such as `D123`.
+    DifferentialRevision(String),
+
+    /// The forge-specific name of the code review, such as `#123` for a
+    /// GitHub pull request or `!123` for a GitLab merge request.
+    CodeReview(String),
+}
+
+/// Extract the code review names which the forges include in the messages of
+/// the commits that they land, such as `Fix bug (#123)` for a squash-merged
+/// GitHub pull request.
+fn extract_landed_code_reviews(message: &str) -> Vec<String> {
+    lazy_static! {
+        static ref RES: Vec<Regex> = [
+            // GitHub squash merges.
+            r"(?m)\A.*\(#(?P<number>[0-9]+)\)[ \t]*$",
+            // GitHub merge commits.
+            r"(?m)\AMerge pull request #(?P<number>[0-9]+) ",
+        ]
+        .into_iter()
+        .map(|re| Regex::new(re).expect("Failed to compile `extract_landed_code_reviews` regex"))
+        .collect();
+        static ref GITLAB_RE: Regex = Regex::new(r"(?m)^See merge request \S*!(?P<number>[0-9]+)$")
+            .expect("Failed to compile `extract_landed_code_reviews` regex");
+    }
+    RES.iter()
+        .filter_map(|re| re.captures(message))
+        .map(|captures| format!("#{}", &captures["number"]))
+        .chain(
+            GITLAB_RE
+                .captures(message)
+                .map(|captures| format!("!{}", &captures["number"])),
+        )
+        .collect()
+}
+
+fn get_landed_commit_keys(
+    commit: &Commit,
+    review_info_cache: Option<&ReviewInfoCache>,
+) -> eyre::Result<Vec<LandedCommitKey>> {
+    let message = commit.get_message_raw();
+    let message = String::from_utf8_lossy(&message);
+    let mut keys = Vec::new();
+    if let Some(change_id) = get_change_id(commit)? {
+        keys.push(LandedCommitKey::ChangeId(change_id));
+    }
+    if let Some(diff_number) = extract_diff_number(&message) {
+        keys.push(LandedCommitKey::DifferentialRevision(diff_number));
+    }
+    match review_info_cache {
+        // Local commits are associated with their code reviews via the cache
+        // written by `git submit`.
+        Some(review_info_cache) => {
+            if let Some(review_info) = review_info_cache.get(commit.get_oid()) {
+                keys.push(LandedCommitKey::CodeReview(review_info.name.clone()));
+            }
+        }
+        None => {
+            keys.extend(
+                extract_landed_code_reviews(&message)
+                    .into_iter()
+                    .map(LandedCommitKey::CodeReview),
+            );
+        }
+    }
+    Ok(keys)
+}
+
+/// Find the draft commits in the stacks rooted at `root_commit_oids` which
+/// have already been landed on the main branch, according to their
+/// identifying trailers or code reviews. Only commits whose draft ancestors
+/// have all been landed are returned, so that the rest of each stack can be
+/// moved directly onto the main branch.
+fn find_landed_commits(
+    repo: &Repo,
+    dag: &Dag,
+    root_commit_oids: &CommitSet,
+) -> eyre::Result<CommitSet> {
+    if dag.set_is_empty(root_commit_oids)? {
+        return Ok(CommitSet::empty());
+    }
+
+    // Only commits which were added to the main branch after the stacks were
+    // created could have landed them.
+    let main_branch_commits = dag.query_only(
+        dag.main_branch_commit.clone(),
+        dag.query_common_ancestors(
+            root_commit_oids
+                .clone()
+                .union(&dag.main_branch_commit.clone()),
+        )?,
+    )?;
+    let mut main_branch_keys: HashSet<LandedCommitKey> = HashSet::new();
+    for commit_oid in dag.commit_set_to_vec(&main_branch_commits)? {
+        let commit = repo.find_commit_or_fail(commit_oid)?;
+        main_branch_keys.extend(get_landed_commit_keys(&commit, None)?);
+    }
+    if main_branch_keys.is_empty() {
+        return Ok(CommitSet::empty());
+    }
+
+    let review_info_cache = read_review_info_cache(repo)?;
+    let draft_commits = dag.query_draft_commits()?;
+    let stack_commits = dag
+        .query_descendants(root_commit_oids.clone())?
+        .intersection(draft_commits);
+    let mut landed_commit_oids: HashSet<NonZeroOid> = HashSet::new();
+    for commit_oid in dag.sort(&stack_commits)? {
+        let commit = repo.find_commit_or_fail(commit_oid)?;
+        let is_landed = get_landed_commit_keys(&commit, Some(&review_info_cache))?
+            .into_iter()
+            .any(|key| main_branch_keys.contains(&key));
+        let mut parents_landed = true;
+        for parent_oid in commit.get_parent_oids() {
+            if !landed_commit_oids.contains(&parent_oid)
+                && dag.set_contains(draft_commits, parent_oid)?
+            {
+                parents_landed = false;
+            }
+        }
+        if is_landed && parents_landed {
+            landed_commit_oids.insert(commit_oid);
+        }
+    }
+    Ok(landed_commit_oids.into_iter().collect())
+}
+
+/// Hide the provided landed commits and delete their branches, as long as
+/// none of their descendants are still visible (such as if they couldn't be
+/// moved due to a merge conflict).
+fn hide_landed_commits(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    event_tx_id: EventTransactionId,
+    now: SystemTime,
+    landed_commits: CommitSet,
+) -> eyre::Result<()> {
+    let event_replayer = EventReplayer::from_event_log_db(effects, repo, event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    let references_snapshot = repo.get_references_snapshot()?;
+    let dag = Dag::open_and_sync(
+        effects,
+        repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    )?;
+    let remaining_commits = dag
+        .filter_visible_commits(dag.query_descendants(landed_commits.clone())?)?
+        .difference(&landed_commits);
+    let landed_commits = landed_commits.difference(&dag.query_ancestors(remaining_commits)?);
+    let landed_commits = sorted_commit_set(repo, &dag, &landed_commits)?;
+    if landed_commits.is_empty() {
+        return Ok(());
+    }
+
+    let timestamp = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs_f64();
+    event_log_db.add_events(
+        landed_commits
+            .iter()
+            .map(|commit| Event::ObsoleteEvent {
+                timestamp,
+                event_tx_id,
+                commit_oid: commit.get_oid(),
+            })
+            .collect(),
+    )?;
+    for commit in landed_commits.iter() {
+        writeln!(
+            effects.get_output_stream(),
+            "Hid landed commit: {}",
+            effects
+                .get_glyphs()
+                .render(commit.friendly_describe(effects.get_glyphs())?)?,
+        )?;
+    }
+
+    let head_info = repo.get_head_info()?;
+    let abandoned_branches: HashMap<NonZeroOid, MaybeZeroOid> = landed_commits
+        .iter()
+        .map(|commit| (commit.get_oid(), MaybeZeroOid::Zero))
+        .collect();
+    if let Some(head_oid) = head_info.oid {
+        if abandoned_branches.contains_key(&head_oid) {
+            repo.detach_head(&head_info)?;
+        }
+    }
+    move_branches(
+        effects,
+        git_run_info,
+        repo,
+        event_tx_id,
+        &abandoned_branches,
+    )?;
+    let mut deleted_branches: Vec<String> = landed_commits
+        .iter()
+        .filter_map(|commit| {
+            references_snapshot
+                .branch_oid_to_names
+                .get(&commit.get_oid())
+        })
+        .flatten()
+        .map(|branch_name| CategorizedReferenceName::new(branch_name).render_suffix())
+        .collect();
+    if !deleted_branches.is_empty() {
+        deleted_branches.sort_unstable();
+        writeln!(
+            effects.get_output_stream(),
+            "Deleted {}: {}",
+            Pluralize {
+                determiner: None,
+                amount: deleted_branches.len(),
+                unit: ("branch", "branches"),
+            },
+            deleted_branches.join(", ")
+        )?;
+    }
+
+    // The code reviews for the landed commits are closed, so don't keep
+    // displaying them.
+    update_review_info_cache(
+        repo,
+        landed_commits
+            .iter()
+            .map(|commit| (commit.get_oid(), None))
+            .collect(),
+    )?;
+    Ok(())
+}
+
 /// Move all commit stacks on top of the main branch.
 pub fn sync(
     effects: &Effects,
@@ -116,6 +364,19 @@ pub fn sync(
         )?);
     }
 
+    try_exit_code!(execute_stack_parents_sync_plan(
+        effects,
+        git_run_info,
+        &repo,
+        &event_log_db,
+        &build_options,
+        &execute_options,
+        &thread_pool,
+        &repo_pool,
+        &revsets,
+        resolve_revset_options,
+    )?);
+
     // The main branch might have changed since we synced with `master`, so read its information again.
 
     execute_sync_plans(
@@ -294,6 +555,155 @@ fn execute_main_branch_sync_plan(
     )
 }
 
+/// If `branchless.submit.branch.recordStacks` is set, move each branch which
+/// isn't on top of the parent branch recorded for it by `git submit` onto that
+/// parent branch, so that stacks submitted from another clone are
+/// reconstructed. Branches recorded as being on top of the main branch are
+/// left for the regular sync, and branches which have moved since they were
+/// recorded (such as by being re-parented locally) are left alone.
+fn execute_stack_parents_sync_plan(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    build_options: &BuildRebasePlanOptions,
+    execute_options: &ExecuteRebasePlanOptions,
+    thread_pool: &ThreadPool,
+    repo_pool: &RepoPool,
+    revsets: &[Revset],
+    resolve_revset_options: &ResolveRevsetOptions,
+) -> EyreExitOr<()> {
+    if !should_record_stacks(repo)? {
+        return Ok(Ok(()));
+    }
+    let stack_records = read_stack_records(repo)?;
+    if stack_records.is_empty() {
+        return Ok(Ok(()));
+    }
+
+    let event_replayer = EventReplayer::from_event_log_db(effects, repo, event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    let references_snapshot = repo.get_references_snapshot()?;
+    let mut dag = Dag::open_and_sync(
+        effects,
+        repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    )?;
+    let commit_sets =
+        match resolve_commits(effects, repo, &mut dag, revsets, resolve_revset_options) {
+            Ok(commit_sets) => commit_sets,
+            Err(err) => {
+                err.describe(effects)?;
+                return Ok(Err(ExitCode(1)));
+            }
+        };
+    let stack_commits = dag
+        .query_descendants(get_stack_roots(&dag, commit_sets)?)?
+        .intersection(dag.query_draft_commits()?);
+    let main_branch_name = get_main_branch_name(repo)?;
+
+    let mut moves: Vec<(NonZeroOid, NonZeroOid)> = Vec::new();
+    let mut moved_branches: Vec<(&str, &str)> = Vec::new();
+    for (
+        branch_name,
+        StackRecord {
+            parent_branch_name,
+            head_oid,
+        },
+    ) in stack_records
+        .iter()
+        .sorted_by_key(|(branch_name, _)| *branch_name)
+    {
+        if parent_branch_name == &main_branch_name {
+            continue;
+        }
+        let get_branch_oid = |branch_name: &str| -> eyre::Result<Option<NonZeroOid>> {
+            match repo.find_branch(branch_name, BranchType::Local)? {
+                Some(branch) => Ok(branch.get_oid()?),
+                None => Ok(None),
+            }
+        };
+        let (branch_oid, parent_branch_oid) = match (
+            get_branch_oid(branch_name)?,
+            get_branch_oid(parent_branch_name)?,
+        ) {
+            (Some(branch_oid), Some(parent_branch_oid)) => (branch_oid, parent_branch_oid),
+            _ => continue,
+        };
+        if branch_oid != *head_oid {
+            continue;
+        }
+        if !dag.set_contains(&stack_commits, branch_oid)?
+            || dag.query_is_ancestor(parent_branch_oid, branch_oid)?
+        {
+            continue;
+        }
+
+        // Any commits which the branch shares with an outdated version of its
+        // parent branch are dropped as duplicates when moved.
+        let own_commits = dag.query_only(
+            CommitSet::from(branch_oid),
+            CommitSet::from(parent_branch_oid).union(&dag.main_branch_commit),
+        )?;
+        for root_oid in dag.commit_set_to_vec(&dag.query_roots(own_commits)?)? {
+            moves.push((root_oid, parent_branch_oid));
+        }
+        moved_branches.push((branch_name, parent_branch_name));
+    }
+    if moves.is_empty() {
+        return Ok(Ok(()));
+    }
+
+    let permissions = match RebasePlanPermissions::verify_rewrite_set(
+        &dag,
+        build_options.clone(),
+        &moves.iter().map(|(root_oid, _)| *root_oid).collect(),
+    )? {
+        Ok(permissions) => permissions,
+        Err(err) => {
+            err.describe(effects, repo, &dag)?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+    let mut builder = RebasePlanBuilder::new(&dag, permissions);
+    for (root_oid, parent_branch_oid) in moves {
+        builder.move_subtree(root_oid, vec![parent_branch_oid])?;
+    }
+    let rebase_plan = match builder.build(effects, thread_pool, repo_pool)? {
+        Ok(Some(rebase_plan)) => rebase_plan,
+        Ok(None) => return Ok(Ok(())),
+        Err(err) => {
+            err.describe(effects, repo, &dag)?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+    match execute_rebase_plan(
+        effects,
+        git_run_info,
+        repo,
+        event_log_db,
+        &rebase_plan,
+        execute_options,
+    )? {
+        ExecuteRebasePlanResult::Succeeded { rewritten_oids: _ } => {
+            for (branch_name, parent_branch_name) in moved_branches {
+                writeln!(
+                    effects.get_output_stream(),
+                    "Moved {branch_name} onto its recorded parent branch {parent_branch_name}"
+                )?;
+            }
+            Ok(Ok(()))
+        }
+        ExecuteRebasePlanResult::DeclinedToMerge { failed_merge_info } => {
+            failed_merge_info.describe(effects, repo, MergeConflictRemediation::Retry)?;
+            Ok(Err(ExitCode(1)))
+        }
+        ExecuteRebasePlanResult::Failed { exit_code } => Ok(Err(exit_code)),
+    }
+}
+
 fn execute_sync_plans(
     effects: &Effects,
     git_run_info: &GitRunInfo,
@@ -326,6 +736,19 @@ fn execute_sync_plans(
         };
     let main_branch_oid = repo.get_main_branch_oid()?;
     let root_commit_oids = get_stack_roots(&dag, commit_sets)?;
+
+    // Landed commits are dropped from their stacks, and the rest of each
+    // stack is moved onto the main branch instead.
+    let landed_commits = find_landed_commits(repo, &dag, &root_commit_oids)?;
+    let root_commit_oids = if dag.set_is_empty(&landed_commits)? {
+        root_commit_oids
+    } else {
+        let remaining_commits = dag
+            .filter_visible_commits(dag.query_descendants(root_commit_oids)?)?
+            .difference(&landed_commits);
+        dag.query_roots(remaining_commits)?
+    };
+
     let root_commits = sorted_commit_set(repo, &dag, &root_commit_oids)?;
     let permissions =
         match RebasePlanPermissions::verify_rewrite_set(&dag, build_options, &root_commit_oids)? {
@@ -379,14 +802,27 @@ fn execute_sync_plans(
             return Ok(Err(ExitCode(1)));
         }
     };
-    execute_plans(
+    try_exit_code!(execute_plans(
         effects,
         git_run_info,
         repo,
         event_log_db,
         execute_options,
         root_commit_and_plans,
-    )
+    )?);
+
+    if !dag.set_is_empty(&landed_commits)? {
+        hide_landed_commits(
+            effects,
+            git_run_info,
+            repo,
+            event_log_db,
+            execute_options.event_tx_id,
+            execute_options.now,
+            landed_commits,
+        )?;
+    }
+    Ok(Ok(()))
 }
 
 fn execute_plans(
@@ -0,0 +1,111 @@
+use lib::testing::make_git;
+
+#[test]
+fn test_completions_script() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    {
+        let (stdout, _stderr) = git.branchless("completions", &["bash"])?;
+        let move_block: Vec<&str> = stdout
+            .lines()
+            .skip_while(|line| line.trim() != "move)")
+            .take_while(|line| line.trim() != ";;")
+            .collect();
+        insta::assert_snapshot!(move_block.join("\n"), @r###"
+        move)
+            flags='--base --color --debug-dump-rebase-constraints --debug-dump-rebase-plan --dest --exact --fixup --force-rewrite --fr --help --hidden --in-memory --insert --merge --no-deduplicate-commits --on-disk --source -C -F -I -b -d -f -h -m -s -x'
+            revset_flags='--base --dest --exact --source -b -d -s -x'
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_completions_aliases() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    // Keep the lines which complete the `git sl` alias like `smartlog`.
+    let alias_lines = |stdout: &str| -> String {
+        let lines: Vec<&str> = stdout.lines().collect();
+        lines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, line)| match line.trim() {
+                "sl)" | "case sl" => &lines[i..=i + 1],
+                trimmed if trimmed.starts_with("_git_sl") || trimmed.starts_with("_git-sl") => {
+                    &lines[i..=i]
+                }
+                trimmed
+                    if trimmed.starts_with("complete -c git ")
+                        && trimmed.contains("-a 'smartlog'") =>
+                {
+                    &lines[i..=i]
+                }
+                _ => &[],
+            })
+            .flat_map(|line| [*line, "\n"])
+            .collect()
+    };
+
+    {
+        let (stdout, _stderr) = git.branchless("completions", &["bash"])?;
+        insta::assert_snapshot!(alias_lines(&stdout), @r###"
+                    sl)
+                        subcommand='smartlog'
+        _git_sl() { _git_branchless; }
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("completions", &["zsh"])?;
+        insta::assert_snapshot!(alias_lines(&stdout), @r###"
+                    sl)
+                        subcommand='smartlog'
+        _git-sl() { _git-branchless "$@" }
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("completions", &["fish"])?;
+        insta::assert_snapshot!(alias_lines(&stdout), @r###"
+                    case sl
+                        echo smartlog
+        complete -c git -n '__fish_seen_subcommand_from branchless amend hide move next prev query record restack reword sl smartlog submit sw sync test undo unhide; and __git_branchless_needs_subcommand' -f -a 'smartlog' -d '`smartlog` command'
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_completions_revset() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.commit_file("test1", 1)?;
+    git.run(&["branch", "foo"])?;
+    git.detach_head()?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+
+    {
+        let (stdout, _stderr) =
+            git.branchless("completions", &["fish", "--revset", "draft() & fo"])?;
+        insta::assert_snapshot!(stdout, @"draft() & foo	branch");
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("completions", &["bash", "--revset", "foo..9"])?;
+        insta::assert_snapshot!(stdout, @"foo..96d1c37");
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("completions", &["zsh", "--revset", "x::de"])?;
+        insta::assert_snapshot!(stdout, @r###"x\:\:descendants(:function"###);
+    }
+
+    Ok(())
+}
@@ -52,6 +52,40 @@ fn test_gc() -> eyre::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_gc_ignores_non_commit_references() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+
+    // Stack records written by `git submit` point to blobs rather than commits.
+    let blob_oid = git
+        .get_repo()?
+        .create_blob_from_contents(b"parent master\n")?;
+    git.run(&[
+        "update-ref",
+        "refs/branchless/stacks/foo",
+        &blob_oid.to_string(),
+    ])?;
+
+    git.branchless("hide", &["HEAD"])?;
+    {
+        let (stdout, _stderr) = git.branchless("gc", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: collecting garbage
+        branchless: 1 dangling reference deleted
+        "###);
+    }
+    {
+        let (stdout, _stderr) = git.run(&["cat-file", "-p", "refs/branchless/stacks/foo"])?;
+        insta::assert_snapshot!(stdout, @"parent master");
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_gc_reference_transaction() -> eyre::Result<()> {
     let git = make_git()?;
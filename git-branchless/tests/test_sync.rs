@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use lib::git::{update_review_info_cache, ReviewInfo, ReviewState};
 use lib::testing::{
     make_git, make_git_with_remote_repo, remove_nondeterministic_lines, GitInitOptions,
     GitRunOptions, GitWrapperWithRemoteRepo,
@@ -260,6 +263,144 @@ fn test_sync_stack_from_commit() -> eyre::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_sync_recorded_stack_parents() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.run(&["branch", "foo"])?;
+    let bar_oid = git.commit_file("test2", 2)?;
+    git.run(&["branch", "bar"])?;
+
+    // Simulate fetching a new version of `foo` which was submitted from
+    // another clone, along with the stack records for both branches.
+    git.run(&["checkout", "master"])?;
+    let foo_oid = git.commit_file("test1", 3)?;
+    git.run(&["branch", "-f", "foo", "HEAD"])?;
+    git.run(&["reset", "--hard", "HEAD^"])?;
+    for (branch_name, parent_branch_name, head_oid) in
+        [("foo", "master", foo_oid), ("bar", "foo", bar_oid)]
+    {
+        let blob_oid = git.get_repo()?.create_blob_from_contents(
+            format!("parent {parent_branch_name}\nhead {head_oid}\n").as_bytes(),
+        )?;
+        git.run(&[
+            "update-ref",
+            &format!("refs/branchless/stacks/{branch_name}"),
+            &blob_oid.to_string(),
+        ])?;
+    }
+
+    // Recorded stacks are only reconstructed if stacks are being recorded.
+    {
+        let (stdout, _stderr) = git.branchless("sync", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Not moving up-to-date stack at 62fc20d create test1.txt
+        Not moving up-to-date stack at f0542e4 create test1.txt
+        "###);
+    }
+    git.run(&["config", "branchless.submit.branch.recordStacks", "true"])?;
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        @ f777ecc (> master) create initial.txt
+        |\
+        | o 62fc20d create test1.txt
+        | |
+        | o 96d1c37 (bar) create test2.txt
+        |
+        o f0542e4 (foo) create test1.txt
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("sync", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Attempting rebase in-memory...
+        [1/2] Skipped commit (was already applied upstream): 62fc20d create test1.txt
+        [2/2] Committed as: 8d5a7a6 create test2.txt
+        branchless: processing 1 update: branch bar
+        branchless: processing 2 rewritten commits
+        branchless: running command: <git-executable> checkout master
+        In-memory rebase succeeded.
+        Moved bar onto its recorded parent branch foo
+        Not moving up-to-date stack at f0542e4 create test1.txt
+        "###);
+    }
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        @ f777ecc (> master) create initial.txt
+        |
+        o f0542e4 (foo) create test1.txt
+        |
+        o 8d5a7a6 (bar) create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_sync_recorded_stack_parents_moved_locally() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+    git.run(&["config", "branchless.submit.branch.recordStacks", "true"])?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.run(&["branch", "foo"])?;
+    let bar_oid = git.commit_file("test2", 2)?;
+    let blob_oid = git
+        .get_repo()?
+        .create_blob_from_contents(format!("parent foo\nhead {bar_oid}\n").as_bytes())?;
+    git.run(&[
+        "update-ref",
+        "refs/branchless/stacks/bar",
+        &blob_oid.to_string(),
+    ])?;
+
+    // `bar` was re-parented onto `master` after it was recorded, so it's not
+    // moved back onto `foo`.
+    git.run(&["checkout", "master"])?;
+    git.commit_file("test2", 2)?;
+    git.run(&["branch", "bar"])?;
+    git.run(&["reset", "--hard", "HEAD^"])?;
+    {
+        let (stdout, _stderr) = git.branchless("sync", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Not moving up-to-date stack at 62fc20d create test1.txt
+        Not moving up-to-date stack at fe65c1f create test2.txt
+        "###);
+    }
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        @ f777ecc (> master) create initial.txt
+        |\
+        | o 62fc20d (foo) create test1.txt
+        | |
+        | o 96d1c37 create test2.txt
+        |
+        o fe65c1f (bar) create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_sync_divergent_main_branch() -> eyre::Result<()> {
     let GitWrapperWithRemoteRepo {
@@ -532,3 +673,164 @@ fn test_sync_checked_out_main_with_dirty_working_copy() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_sync_hide_landed_commits_by_trailer() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.write_file_txt("test1", "test1 contents\n")?;
+    git.run(&["add", "."])?;
+    git.run_with_options(
+        &[
+            "commit",
+            "-m",
+            "create test1.txt\n\nChange-Id: I0123456789abcdef0123456789abcdef01234567",
+        ],
+        &GitRunOptions {
+            time: 1,
+            ..Default::default()
+        },
+    )?;
+    git.run(&["branch", "foo"])?;
+    git.commit_file("test2", 2)?;
+
+    // Land an edited version of the first commit, so that its patch differs.
+    git.run(&["checkout", "master"])?;
+    git.write_file_txt("test1", "test1 edited contents\n")?;
+    git.run(&["add", "."])?;
+    git.run_with_options(
+        &[
+            "commit",
+            "-m",
+            "Add test1.txt\n\nChange-Id: I0123456789abcdef0123456789abcdef01234567",
+        ],
+        &GitRunOptions {
+            time: 3,
+            ..Default::default()
+        },
+    )?;
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc create initial.txt
+        |\
+        | o aa6876f (foo) create test1.txt
+        | |
+        | o 7b3de80 create test2.txt
+        |
+        @ 071ab64 (> master) Add test1.txt
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("sync", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Attempting rebase in-memory...
+        [1/1] Committed as: f5f7c12 create test2.txt
+        branchless: processing 1 rewritten commit
+        branchless: running command: <git-executable> checkout master
+        In-memory rebase succeeded.
+        Synced 7b3de80 create test2.txt
+        Hid landed commit: aa6876f create test1.txt
+        branchless: processing 1 update: branch foo
+        Deleted 1 branch: foo
+        "###);
+    }
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        :
+        @ 071ab64 (> master) Add test1.txt
+        |
+        o f5f7c12 create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_sync_hide_landed_commits_by_code_review() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.detach_head()?;
+    let test1_oid = git.commit_file("test1", 1)?;
+    git.run(&["branch", "foo"])?;
+    git.commit_file("test2", 2)?;
+
+    // Simulate the pull request for the first commit being squash-merged with
+    // an edited message.
+    git.run(&["checkout", "master"])?;
+    git.commit_file_with_contents_and_message("test1", 3, "test1 edited contents\n", "Add")?;
+    git.run(&[
+        "commit",
+        "--amend",
+        "-m",
+        "Add test1.txt with some edits (#5)",
+    ])?;
+    update_review_info_cache(
+        &git.get_repo()?,
+        HashMap::from([(
+            test1_oid,
+            Some(ReviewInfo {
+                name: "#5".to_owned(),
+                url: None,
+                review_state: Some(ReviewState::Approved),
+                checks_state: None,
+            }),
+        )]),
+    )?;
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc create initial.txt
+        |\
+        | o 62fc20d (foo) #5 (approved) create test1.txt
+        | |
+        | o 96d1c37 create test2.txt
+        |
+        @ 87a9708 (> master) Add test1.txt with some edits (#5)
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("sync", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Attempting rebase in-memory...
+        [1/1] Committed as: 4f544be create test2.txt
+        branchless: processing 1 rewritten commit
+        branchless: running command: <git-executable> checkout master
+        In-memory rebase succeeded.
+        Synced 96d1c37 create test2.txt
+        Hid landed commit: 62fc20d create test1.txt
+        branchless: processing 1 update: branch foo
+        Deleted 1 branch: foo
+        "###);
+    }
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        :
+        @ 87a9708 (> master) Add test1.txt with some edits (#5)
+        |
+        o 4f544be create test2.txt
+        "###);
+    }
+
+    Ok(())
+}